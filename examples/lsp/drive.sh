@@ -0,0 +1,15 @@
+#!/bin/sh
+# Drive `vesuvius lsp` with the scripted messages in `session.jsonl`,
+# and compare each message that the server sends back with the
+# matching line of `expected.jsonl`. Exits with an error if any differ.
+#
+# Usage : ./drive.sh [path to vesuvius binary]
+
+cd "$(dirname "$0")"
+ROOT="$(pwd)"
+VESUVIUS="${1:-cargo run --quiet --}"
+
+sed "s|\$ROOT|$ROOT|g" session.jsonl | while IFS= read -r line; do
+    printf 'Content-Length: %d\r\n\r\n%s' "$(printf '%s' "$line" | wc -c)" "$line"
+done | $VESUVIUS lsp | tr -d '\r' | sed -E 's/Content-Length: [0-9]+/\n/g' | grep -v '^$' \
+    | sed "s|$ROOT|\$ROOT|g" | diff -u expected.jsonl - && echo "All responses matched."
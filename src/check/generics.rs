@@ -0,0 +1,248 @@
+use crate::{
+    parse::node::{
+        Generic,
+        Method,
+        Range,
+        TypeDescriptor
+    },
+    scope::{
+        ProgramInfo,
+        Instance
+    },
+    notes::push_error,
+    check::types::{
+        Value,
+        ValueType,
+        Type
+    }
+};
+
+
+/// The type arguments found so far for the type parameters of a generic declaration.
+pub(crate) struct Inference {
+    owner  : Vec<String>,
+    params : Vec<Vec<String>>,
+    args   : Vec<Option<Type>>
+}
+
+impl Inference {
+
+    /// Start inferring the type arguments of the declaration at `owner`.
+    /// Declarations without type parameters have nothing to infer.
+    pub(crate) fn new(owner : &Vec<String>) -> Self {
+        let params = params_of(owner);
+        return Self {
+            owner  : owner.clone(),
+            args   : vec![None; params.len()],
+            params
+        };
+    }
+
+    /// Match a type which may contain the type parameters against the type of the value
+    /// given for it, and infer the parameters that it finds.
+    /// The first type found for a parameter is kept, unless only part of it was known.
+    /// Conflicts are left for the caller to report when it compares the types.
+    pub(crate) fn unify(&mut self, expected : &Type, found : &Type) {
+        match ((expected, found)) {
+            (_, Type::Failed | Type::Never) => {},
+            (Type::Param(path), found) => {
+                let Some(i) = self.params.iter().position(|param| param == path) else {
+                    return;
+                };
+                if (self.args[i].as_ref().map_or(true, |old| old.is_partial() && old.accepts(found))) {
+                    self.args[i] = Some(found.clone());
+                }
+            },
+            (Type::Struct(path, args), Type::Struct(other_path, other_args))
+                | (Type::Enum(path, args), Type::Enum(other_path, other_args)) => {
+                if (path == other_path) {
+                    args.iter().zip(other_args).for_each(|(arg, other_arg)| self.unify(arg, other_arg));
+                }
+            },
+            (Type::Tuple(types), Type::Tuple(other_types)) => {
+                types.iter().zip(other_types).for_each(|(typ, other_typ)| self.unify(typ, other_typ));
+            },
+            (Type::Array(typ, _), Type::Array(other_typ, _))
+                | (Type::List(typ), Type::List(other_typ)) => self.unify(typ, other_typ),
+            (Type::Function(args, ret), Type::Function(other_args, other_ret)) => {
+                args.iter().zip(other_args).for_each(|(arg, other_arg)| self.unify(arg, other_arg));
+                self.unify(ret, other_ret);
+            },
+            _ => {}
+        }
+    }
+
+    /// Replace the type parameters in a type with the arguments inferred for them.
+    pub(crate) fn apply(&self, typ : &Type) -> Type {
+        return typ.substitute(&self.params, &self.args());
+    }
+
+    /// The type arguments. Any which were not inferred are `Failed`.
+    pub(crate) fn args(&self) -> Vec<Type> {
+        return self.args.iter().map(|arg| arg.clone().unwrap_or(Type::Failed)).collect();
+    }
+
+    /// Report each type parameter which could not be inferred for the call at `range`.
+    /// Returns whether every one was.
+    pub(crate) fn report_missing(&self, range : &Range, name : &String) -> bool {
+        let generics = ProgramInfo::get().generics_of(&self.owner);
+        let mut inferred = true;
+        for (generic, arg) in generics.iter().zip(&self.args) {
+            if (arg.is_none()) {
+                push_error!(UninferredTypeArgument, Always, {
+                    Some(range.clone())         => {"Can not infer the type of `{}` for `{}`.", generic.name, name},
+                    Some(generic.range.clone()) => {"No argument uses `{}`.", generic.name}
+                });
+                inferred = false;
+            }
+        }
+        return inferred;
+    }
+
+    /// Remember that the declaration was used at `range` with the inferred arguments,
+    /// if every one of them is fully known.
+    pub(crate) fn record(&self, range : &Range) {
+        record_instance(&self.owner, self.args(), range);
+    }
+
+}
+
+
+/// Get the path of each type parameter of the declaration at `owner`.
+fn params_of(owner : &Vec<String>) -> Vec<Vec<String>> {
+    return ProgramInfo::get().generics_of(owner).iter()
+        .map(|generic| [owner.as_slice(), &[generic.name.clone()]].concat())
+        .collect();
+}
+
+/// Replace the type parameters of the declaration at `owner` in `typ` with `args`.
+pub(crate) fn instantiate(owner : &Vec<String>, args : &[Type], typ : &Type) -> Type {
+    return typ.substitute(&params_of(owner), args);
+}
+
+/// Resolve the type of a field of a struct or enum, for the instance `owner` of it.
+pub(crate) fn field_type(owner : &Type, typ : &TypeDescriptor) -> Type {
+    let typ = typ.resolve(false);
+    return match (owner) {
+        Type::Struct(path, args) | Type::Enum(path, args) => instantiate(path, args, &typ),
+        _                                                 => typ
+    };
+}
+
+/// Remember that the declaration at `path` was used at `range` with `args`,
+/// if every one of them is fully known.
+pub(crate) fn record_instance(path : &Vec<String>, args : Vec<Type>, range : &Range) {
+    if (! args.is_empty() && ! args.iter().any(|arg| arg.is_partial())) {
+        ProgramInfo::get().add_instance(Instance {
+            path  : path.clone(),
+            args,
+            range : range.clone()
+        });
+    }
+}
+
+/// Report the type parameters of a generic enum that `value` is a unit variant of.
+/// A unit variant holds nothing to infer them from, so if it is stored in a
+/// variable, nothing else can give them a type.
+pub(crate) fn report_uninferred_variant(value : &Value) {
+    let ValueType::Enum(path, args, Some(variant), Some(fields)) = value.value_ref() else {
+        return;
+    };
+    if (! fields.is_empty()) {
+        return;
+    }
+    for (generic, arg) in ProgramInfo::get().generics_of(path).iter().zip(args) {
+        if (matches!(arg, Type::Failed)) {
+            push_error!(UninferredTypeArgument, Always, {
+                Some(value.range().clone()) => {"Can not infer the type of `{}` for `{}::{}`.", generic.name, path.join("::"), variant},
+                Some(generic.range.clone()) => {"`{}` holds no values that use `{}`.", variant, generic.name}
+            });
+        }
+    }
+}
+
+
+/// Resolve the traits that a type parameter is bound by, and add references to them.
+/// Bounds which are not traits are reported, and left out.
+pub(crate) fn expand_generics(generics : &Vec<Generic>) {
+    for (i, generic) in generics.iter().enumerate() {
+        if let Some(first) = generics[..i].iter().find(|other| other.name == generic.name) {
+            push_error!(DuplicateSymbol, Always, {
+                Some(first.range.clone())   => {"Type parameter `{}` already defined here.", generic.name},
+                Some(generic.range.clone()) => {"Defined again here."}
+            });
+        }
+        for (path, range) in &generic.bounds {
+            resolve_bound(path, range, true);
+        }
+    }
+}
+
+/// Find the trait that a bound refers to.
+/// Returns its path, and the methods that it requires.
+fn resolve_bound(path : &Vec<String>, range : &Range, report : bool) -> Option<(&'static Vec<String>, &'static Vec<Method>)> {
+    let symbol = ProgramInfo::get().scope_of(&range.0)?.resolve_path(path, range, report)?;
+    if (report) {
+        ProgramInfo::get().add_reference(range.clone(), &path[path.len() - 1], symbol);
+    }
+    return match (symbol.value().value_ref()) {
+        ValueType::TraitType(trait_path, methods) => Some((trait_path, methods)),
+        ValueType::Failed                         => None,
+        _                                         => {
+            if (report) {
+                push_error!(InvalidTypeReceived, Always, {
+                    Some(range.clone())          => {"`{}` is not a trait.", path.join("::")},
+                    Some(symbol.range().clone()) => {"Defined here."}
+                });
+            }
+            None
+        }
+    };
+}
+
+/// Get the type parameter at the path.
+fn generic_of(path : &Vec<String>) -> Option<&'static Generic> {
+    let name = &path[path.len() - 1];
+    return ProgramInfo::get().generics_of(&path[..(path.len() - 1)].to_vec()).iter().find(|generic| generic.name == *name);
+}
+
+/// Get every method called `name` that the bounds of the type parameter at the path give it,
+/// with the path of the trait that it is from.
+pub(crate) fn bound_methods(path : &Vec<String>, name : &String) -> Vec<(&'static Vec<String>, &'static Method)> {
+    let Some(generic) = generic_of(path) else {
+        return Vec::new();
+    };
+    return generic.bounds.iter()
+        .filter_map(|(bound, range)| resolve_bound(bound, range, false))
+        .filter_map(|(trait_path, methods)| methods.iter().find(|method| method.name == *name).map(|method| (trait_path, method)))
+        .collect();
+}
+
+/// Whether the trait at `trait_path` is implemented for `typ`.
+/// A type parameter implements the traits that it is bound by.
+fn implements(typ : &Type, trait_path : &Vec<String>) -> bool {
+    if let Type::Param(path) = typ {
+        return generic_of(path).is_some_and(|generic| generic.bounds.iter()
+            .any(|(bound, range)| resolve_bound(bound, range, false).is_some_and(|(other, _)| other == trait_path))
+        );
+    }
+    return ProgramInfo::get().is_implemented(trait_path, typ);
+}
+
+/// Report each use of a generic declaration with a type argument
+/// which does not implement every trait that its type parameter is bound by.
+pub(crate) fn check_instances() {
+    for instance in ProgramInfo::get().instances() {
+        for (generic, arg) in ProgramInfo::get().generics_of(&instance.path).iter().zip(&instance.args) {
+            for (bound, range) in &generic.bounds {
+                let Some((trait_path, _)) = resolve_bound(bound, range, false) else {continue};
+                if (! implements(arg, trait_path)) {
+                    push_error!(UnsatisfiedBound, Always, {
+                        Some(instance.range.clone()) => {"`{}` does not implement `{}`.", arg, trait_path.join("::")},
+                        Some(range.clone())          => {"Required by `{}` here.", generic.name}
+                    });
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,224 @@
+use std::str::FromStr;
+
+use num_bigint::BigInt;
+use num_bigfloat::BigFloat;
+
+use crate::{
+    parse::node::{
+        Pattern,
+        PatternType,
+        LiteralType,
+        VariantPatterns,
+        VariantFields
+    },
+    scope::ProgramInfo,
+    check::{
+        types::{
+            ValueType,
+            Type
+        },
+        generics
+    }
+};
+
+
+/// A pattern, reduced to the values that it matches.
+/// Used to find out if a `match` is exhaustive, and which arms can never match.
+#[derive(Clone)]
+pub(crate) enum PatternShape {
+    /// Matches every value.
+    Any,
+    /// A variant or `bool` value, with a shape for each of its fields.
+    Constructor(String, Vec<PatternShape>),
+    /// Any other literal. There are too many of these for them to ever cover a whole type.
+    /// Patterns which failed to check become a literal that nothing equals.
+    Literal(String)
+}
+
+impl PatternShape {
+
+    /// Reduce a pattern, which matches values of type `typ`.
+    pub(crate) fn of(pattern : &Pattern, typ : &Type) -> Self {
+        let failed = Self::Literal(String::new());
+        return match (&pattern.pat) {
+
+            PatternType::Wildcard | PatternType::Binding(_) => Self::Any,
+
+            // Other literals are keyed on their value, so that `1` and `01` are the same.
+            // Strings and characters are quoted, so they never equal a number.
+            PatternType::Literal(lit) => match (&lit.lit) {
+                LiteralType::Bool(value)                                         => Self::Constructor(value.to_string(), Vec::new()),
+                LiteralType::Int(int, _)                                         => Self::Literal(BigInt::from_str(int).map_or_else(|_| int.clone(), |int| int.to_string())),
+                LiteralType::Float(int, dec, _)                                  => {
+                    let text = format!("{}.{}", int, dec);
+                    Self::Literal(BigFloat::parse(&text).map_or(text, |float| float.to_string()))
+                },
+                LiteralType::String(_, value) | LiteralType::RawString(_, value) => Self::Literal(format!("{:?}", value)),
+                LiteralType::Char(_, value)                                      => Self::Literal(format!("{:?}", value)),
+                LiteralType::Identifier(name)                                    => Self::Literal(name.clone()),
+                LiteralType::Path(path)                                          => Self::Literal(path.join("::"))
+            },
+
+            PatternType::Variant(path, _, patterns) => {
+                let name = &path[path.len() - 1];
+                let Some((_, fields)) = constructors(typ).and_then(|constructors| constructors.into_iter().find(|(other, _)| other == name)) else {
+                    return failed;
+                };
+                let shapes = match (patterns) {
+                    VariantPatterns::Unit if (fields.is_empty()) => Vec::new(),
+                    VariantPatterns::Tuple(patterns) if (patterns.len() == fields.len() && fields.iter().all(|(field, _)| field.is_none())) => {
+                        patterns.iter().zip(&fields)
+                            .map(|(pattern, (_, typ))| Self::of(pattern, typ))
+                            .collect()
+                    },
+                    VariantPatterns::Named(patterns) if (fields.iter().all(|(field, _)| field.is_some())) => {
+                        if (patterns.iter().any(|(name, _, _)| ! fields.iter().any(|(field, _)| field.as_ref() == Some(name)))) {
+                            return failed;
+                        }
+                        // Fields which are not given match anything.
+                        fields.iter()
+                            .map(|(field, typ)| match (patterns.iter().find(|(name, _, _)| field.as_ref() == Some(name))) {
+                                Some((_, _, Some(pattern))) => Self::of(pattern, typ),
+                                _                           => Self::Any
+                            })
+                            .collect()
+                    },
+                    _ => {return failed;}
+                };
+                Self::Constructor(name.clone(), shapes)
+            },
+
+            PatternType::Tuple(patterns) | PatternType::Array(patterns) => {
+                let (name, types) = match ((&pattern.pat, typ)) {
+                    (PatternType::Tuple(_), Type::Tuple(types))       => ("()", types.clone()),
+                    (PatternType::Array(_), Type::Array(typ, length)) => ("[]", vec![(**typ).clone(); *length]),
+                    _                                                 => {return failed;}
+                };
+                if (patterns.len() != types.len()) {
+                    return failed;
+                }
+                Self::Constructor(String::from(name), patterns.iter().zip(&types).map(|(pattern, typ)| Self::of(pattern, typ)).collect())
+            }
+
+        };
+    }
+
+    /// Whether this or any shape inside it came from a pattern which failed to check.
+    pub(crate) fn is_failed(&self) -> bool {
+        return match (self) {
+            Self::Any                    => false,
+            Self::Constructor(_, fields) => fields.iter().any(|field| field.is_failed()),
+            Self::Literal(text)          => text.is_empty()
+        };
+    }
+
+}
+
+
+/// Get every value that a type can have, if there are few enough to list.
+/// Each is given with the name and type of each of its fields.
+pub(crate) fn constructors(typ : &Type) -> Option<Vec<(String, Vec<(Option<String>, Type)>)>> {
+    return match (typ) {
+        Type::Bool               => Some(vec![
+            (String::from("true"), Vec::new()),
+            (String::from("false"), Vec::new())
+        ]),
+        Type::Enum(path, args)   => {
+            let scope  = ProgramInfo::get().scope_of(&path[..(path.len() - 1)].to_vec())?;
+            let symbol = scope.get_symbol(&path[path.len() - 1])?;
+            let ValueType::EnumType(_, variants) = symbol.value().value_ref() else {
+                return None;
+            };
+            let mut constructors = Vec::<(String, Vec<(Option<String>, Type)>)>::new();
+            for (variant, _, fields) in variants {
+                // Duplicates are reported where the enum is declared.
+                if (constructors.iter().any(|(other, _)| other == variant)) {
+                    continue;
+                }
+                constructors.push((variant.clone(), match (fields) {
+                    VariantFields::Unit          => Vec::new(),
+                    VariantFields::Tuple(types)  => types.iter().map(|typ| (None, generics::instantiate(path, args, &typ.resolve(false)))).collect(),
+                    VariantFields::Named(fields) => fields.iter().map(|(field, _, typ)| (Some(field.clone()), generics::instantiate(path, args, &typ.resolve(false)))).collect()
+                }));
+            }
+            Some(constructors)
+        },
+        // Tuples and arrays only have one shape, which holds each element.
+        Type::Tuple(types)       => Some(vec![(String::from("()"), types.iter().map(|typ| (None, typ.clone())).collect())]),
+        Type::Array(typ, length) => Some(vec![(String::from("[]"), vec![(None, (**typ).clone()); *length])]),
+        _                        => None
+    };
+}
+
+
+/// Whether `row` matches any values that none of `rows` match.
+/// Every row holds one shape for each type in `types`.
+pub(crate) fn is_useful(rows : &[Vec<PatternShape>], row : &[PatternShape], types : &[Type]) -> bool {
+    let Some((head, rest)) = row.split_first() else {
+        return rows.is_empty();
+    };
+    let (typ, rest_types) = (&types[0], &types[1..]);
+    return match (head) {
+
+        PatternShape::Constructor(name, fields) => {
+            let field_types = constructors(typ)
+                .and_then(|constructors| constructors.into_iter().find(|(other, _)| other == name))
+                .map_or_else(|| vec![Type::Failed; fields.len()], |(_, fields)| fields.into_iter().map(|(_, typ)| typ).collect());
+            is_useful(
+                &specialise(rows, name, fields.len()),
+                &[fields.as_slice(), rest].concat(),
+                &[field_types.as_slice(), rest_types].concat()
+            )
+        },
+
+        PatternShape::Literal(text) => {
+            let rows = rows.iter()
+                .filter(|row| match (&row[0]) {
+                    PatternShape::Any           => true,
+                    PatternShape::Literal(other) => other == text && ! text.is_empty(),
+                    _                           => false
+                })
+                .map(|row| row[1..].to_vec())
+                .collect::<Vec<_>>();
+            is_useful(&rows, rest, rest_types)
+        },
+
+        PatternShape::Any => {
+            // Large arrays are only split into their elements if some arm needs it.
+            let named        = rows.iter().any(|row| matches!(row[0], PatternShape::Constructor(_, _)));
+            let constructors = named.then(|| constructors(typ)).flatten().filter(|constructors| constructors.iter().all(|(name, _)|
+                rows.iter().any(|row| matches!(&row[0], PatternShape::Constructor(other, _) if other == name))
+            ));
+            if let Some(constructors) = constructors {
+                // Every value is matched by some arm. Check each of them.
+                constructors.iter().any(|(name, fields)| {
+                    let field_types = fields.iter().map(|(_, typ)| typ.clone()).collect::<Vec<_>>();
+                    is_useful(
+                        &specialise(rows, name, fields.len()),
+                        &[vec![PatternShape::Any; fields.len()].as_slice(), rest].concat(),
+                        &[field_types.as_slice(), rest_types].concat()
+                    )
+                })
+            } else {
+                // Some value is not matched by name. Only the rows which match anything can match it.
+                let rows = rows.iter()
+                    .filter(|row| matches!(row[0], PatternShape::Any))
+                    .map(|row| row[1..].to_vec())
+                    .collect::<Vec<_>>();
+                is_useful(&rows, rest, rest_types)
+            }
+        }
+
+    };
+}
+
+/// Keep the rows which can match the constructor `name`, with its fields in place of the first shape.
+fn specialise(rows : &[Vec<PatternShape>], name : &String, arity : usize) -> Vec<Vec<PatternShape>> {
+    return rows.iter()
+        .filter_map(|row| match (&row[0]) {
+            PatternShape::Any                                          => Some([vec![PatternShape::Any; arity].as_slice(), &row[1..]].concat()),
+            PatternShape::Constructor(other, fields) if (other == name) => Some([fields.as_slice(), &row[1..]].concat()),
+            _                                                          => None
+        })
+        .collect();
+}
@@ -1,18 +1,37 @@
 pub(crate) mod types;
+pub(crate) mod matches;
+pub(crate) mod generics;
+
+use std::str::FromStr;
+
+use num_bigint::BigInt;
+use num_bigfloat::BigFloat;
 
 use crate::{
     parse::node::*,
     scope::{
+        ProgramInfo,
         Scope,
-        Symbol
+        Symbol,
+        Implementation
     },
-    check::types::{
-        Value,
-        ValueType
+    check::{
+        types::{
+            Value,
+            ValueType,
+            IntRange,
+            Type
+        },
+        matches::PatternShape
     },
     notes::{
         push_warn,
-        push_error
+        push_error,
+        push_note_details,
+        NoteType,
+        NoteOccurance,
+        ErrorType,
+        WarnType
     }
 };
 
@@ -21,16 +40,11 @@ use crate::{
 impl Program {
 
     pub(crate) fn register_decls(&self, scope : &mut Scope) {
-        push_warn!(InternalWarning, Always, {
-            None => {"Todo : Declaration Headers"}
-        });
         self.decls.iter().for_each(|decl| decl.register(scope));
     }
 
-    pub(crate) fn expand_types(&self, _scope : &mut Scope) {
-        push_error!(InternalError, Always, {
-            None => {"Todo : Check Contents"}
-        });
+    pub(crate) fn expand_types(&self, scope : &mut Scope) {
+        self.decls.iter().for_each(|decl| decl.expand_types(scope));
     }
 
     pub(crate) fn check_contents(&self, scope : &mut Scope) {
@@ -45,37 +59,295 @@ impl Declaration {
 
 
     fn register(&self, scope : &mut Scope) {
+        for header in &self.headers {
+            header.register(&self.decl);
+        }
+        let public = matches!(self.vis.vis, DeclarationVisibilityType::Public);
         use DeclarationType::*;
         match (&self.decl) {
 
-            Module(parts, _) => {
-                scope.init_symbol(
-                    parts[parts.len() - 1].clone(),
-                    Symbol::new(
-                        Value::new(
-                            ValueType::ModuleAccess(parts.clone()),
-                            self.range.clone()
-                        ),
-                        false,
+            Module(parts, range) => {
+                let mut path = self.range.0.clone();
+                path.remove(path.len() - 1);
+                path.append(&mut parts.clone());
+                let name   = parts[parts.len() - 1].clone();
+                let symbol = Symbol::new(
+                    Value::new(
+                        ValueType::ModuleAccess(path),
                         self.range.clone()
-                    )
+                    ),
+                    false,
+                    public,
+                    self.range.clone()
                 );
+                ProgramInfo::get().add_reference(range.clone(), &name, &symbol);
+                scope.init_symbol(name, symbol);
+            },
+
+            Use(base, items, _) => {
+                for (name, range, alias) in items {
+                    let mut path = base.iter().map(|(part, _)| part.clone()).collect::<Vec<_>>();
+                    path.push(name.clone());
+                    let (name, range) = alias.clone().unwrap_or_else(|| (name.clone(), range.clone()));
+                    scope.init_symbol(
+                        name,
+                        Symbol::new(
+                            Value::new(
+                                ValueType::Alias(path),
+                                range.clone()
+                            ),
+                            false,
+                            public,
+                            range
+                        )
+                    );
+                }
             },
 
-            Function(name, _, args, ret, block) => {
-                scope.init_symbol(
-                    name.clone(),
-                    Symbol::new(
-                        Value::new(
-                            ValueType::Function(name.clone(), args.clone(), ret.clone(), block.clone()),
-                            self.range.clone()
-                        ),
-                        false,
+            Function(name, name_range, generics, args, ret, block) => {
+                let mut path = self.range.0.clone();
+                path.push(name.clone());
+                ProgramInfo::get().add_generics(path, self.range.clone(), generics.clone());
+                let symbol = Symbol::new(
+                    Value::new(
+                        ValueType::Function(name.clone(), args.clone(), ret.clone(), block.clone()),
                         self.range.clone()
-                    )
-                );
+                    ),
+                    false,
+                    public,
+                    self.range.clone()
+                ).with_name_range(name_range.clone());
+                ProgramInfo::get().add_reference(name_range.clone(), name, &symbol);
+                scope.init_symbol(name.clone(), symbol);
+            },
+
+            Struct(name, name_range, generics, fields) => {
+                let mut path = self.range.0.clone();
+                path.push(name.clone());
+                ProgramInfo::get().add_generics(path.clone(), self.range.clone(), generics.clone());
+                let fields = fields.iter()
+                    .map(|(vis, field, range, typ)| (field.clone(), range.clone(), matches!(vis.vis, DeclarationVisibilityType::Public), typ.clone()))
+                    .collect();
+                let symbol = Symbol::new(
+                    Value::new(
+                        ValueType::StructType(path, fields),
+                        self.range.clone()
+                    ),
+                    false,
+                    public,
+                    self.range.clone()
+                ).with_name_range(name_range.clone());
+                ProgramInfo::get().add_reference(name_range.clone(), name, &symbol);
+                scope.init_symbol(name.clone(), symbol);
+            },
+
+            Enum(name, name_range, generics, variants) => {
+                let mut path = self.range.0.clone();
+                path.push(name.clone());
+                ProgramInfo::get().add_generics(path.clone(), self.range.clone(), generics.clone());
+                let symbol = Symbol::new(
+                    Value::new(
+                        ValueType::EnumType(path, variants.clone()),
+                        self.range.clone()
+                    ),
+                    false,
+                    public,
+                    self.range.clone()
+                ).with_name_range(name_range.clone());
+                ProgramInfo::get().add_reference(name_range.clone(), name, &symbol);
+                scope.init_symbol(name.clone(), symbol);
+            },
+
+            Trait(name, name_range, methods) => {
+                let mut path = self.range.0.clone();
+                path.push(name.clone());
+                let symbol = Symbol::new(
+                    Value::new(
+                        ValueType::TraitType(path, methods.clone()),
+                        self.range.clone()
+                    ),
+                    false,
+                    public,
+                    self.range.clone()
+                ).with_name_range(name_range.clone());
+                ProgramInfo::get().add_reference(name_range.clone(), name, &symbol);
+                scope.init_symbol(name.clone(), symbol);
+            },
+
+            // Impls are found by the type that they are for, once every type is known.
+            Impl(_, _, _, _) => {},
+
+            // Uses of a declaration that failed to parse should not also be reported as unknown.
+            Error(Some((_, name, name_range))) => {
+                let symbol = Symbol::new(
+                    Value::new(ValueType::Failed, self.range.clone()),
+                    false,
+                    public,
+                    self.range.clone()
+                ).with_name_range(name_range.clone());
+                scope.init_symbol(name.clone(), symbol);
+            },
+
+            Error(None) => {}
+
+        }
+    }
+
+
+    fn expand_types(&self, scope : &mut Scope) {
+        use DeclarationType::*;
+        match (&self.decl) {
+
+            Module(_, _) => {},
+
+            Use(base, items, _) => {
+                for (name, range, _) in items {
+                    let (mut path, mut ranges) = base.iter().cloned().unzip::<_, _, Vec<_>, Vec<_>>();
+                    path.push(name.clone());
+                    ranges.push(range.clone());
+                    scope.check_import(&path, &ranges);
+                    if let Some(symbol) = scope.resolve_path(&path, range, false) {
+                        ProgramInfo::get().add_reference(range.clone(), name, symbol);
+                    }
+                }
+            },
+
+            Function(_, _, generics, args, ret, _) => {
+                generics::expand_generics(generics);
+                for (_, _, typ) in args {
+                    typ.resolve(true);
+                }
+                if let Some(ret) = ret {
+                    ret.resolve(true);
+                }
+            },
+
+            Struct(name, name_range, generics, fields) => {
+                generics::expand_generics(generics);
+                for (i, (_, field, range, typ)) in fields.iter().enumerate() {
+                    if let Some((_, _, first, _)) = fields[..i].iter().find(|(_, other, _, _)| other == field) {
+                        push_error!(DuplicateSymbol, Always, {
+                            Some(first.clone()) => {"Field `{}` already defined here.", field},
+                            Some(range.clone()) => {"Defined again here."}
+                        });
+                    }
+                    typ.resolve(true);
+                }
+                self.check_recursion(name, name_range);
+            },
+
+            Enum(name, name_range, generics, variants) => {
+                generics::expand_generics(generics);
+                for (i, (variant, range, fields)) in variants.iter().enumerate() {
+                    if let Some((_, first, _)) = variants[..i].iter().find(|(other, _, _)| other == variant) {
+                        push_error!(DuplicateSymbol, Always, {
+                            Some(first.clone()) => {"Variant `{}` already defined here.", variant},
+                            Some(range.clone()) => {"Defined again here."}
+                        });
+                    }
+                    match (fields) {
+                        VariantFields::Unit          => {},
+                        VariantFields::Tuple(types)  => types.iter().for_each(|typ| {typ.resolve(true);}),
+                        VariantFields::Named(fields) => {
+                            for (j, (field, range, typ)) in fields.iter().enumerate() {
+                                if let Some((_, first, _)) = fields[..j].iter().find(|(other, _, _)| other == field) {
+                                    push_error!(DuplicateSymbol, Always, {
+                                        Some(first.clone()) => {"Field `{}` already defined here.", field},
+                                        Some(range.clone()) => {"Defined again here."}
+                                    });
+                                }
+                                typ.resolve(true);
+                            }
+                        }
+                    }
+                }
+                self.check_recursion(name, name_range);
+            },
+
+            Trait(_, _, methods) => expand_methods(methods),
+
+            Impl(path, path_range, typ, methods) => {
+                expand_methods(methods);
+                self.expand_impl(scope, path, path_range, typ, methods);
+            },
+
+            Error(_) => {}
+
+        }
+    }
+
+
+    /// Check an `impl` against the trait that it implements,
+    /// then remember it so that its methods can be called.
+    fn expand_impl(&self, scope : &mut Scope, path : &Vec<String>, path_range : &Range, typ : &TypeDescriptor, methods : &Vec<Method>) {
+        let target = typ.resolve(true);
+        let Some(symbol) = scope.resolve_path(path, path_range, true) else {
+            return;
+        };
+        ProgramInfo::get().add_reference(path_range.clone(), &path[path.len() - 1], symbol);
+        let ValueType::TraitType(trait_path, required) = symbol.value().value_ref() else {
+            if (! matches!(symbol.value().value_ref(), ValueType::Failed)) {
+                push_error!(InvalidTypeReceived, Always, {
+                    Some(path_range.clone())     => {"`{}` is not a trait.", path.join("::")},
+                    Some(symbol.range().clone()) => {"Defined here."}
+                });
             }
+            return;
+        };
+        let range   = Range(self.range.0.clone(), path_range.1, typ.range.2);
+        let missing = required.iter()
+            .filter(|method| ! methods.iter().any(|other| other.name == method.name))
+            .collect::<Vec<_>>();
+        if (! missing.is_empty()) {
+            let mut details = vec![(Some(range.clone()), format!("Missing method{} {}.",
+                if (missing.len() != 1) {"s"} else {""},
+                missing.iter().map(|method| format!("`{}`", method.name)).collect::<Vec<_>>().join(", ")
+            ))];
+            details.extend(missing.iter().map(|method| (Some(method.name_range.clone()), format!("`{}` declared here.", method.name))));
+            push_note_details!(
+                NoteType::Error(ErrorType::MissingTraitMethod),
+                NoteOccurance::Always,
+                details
+            );
+        }
+        for method in methods {
+            let Some(declared) = required.iter().find(|other| other.name == method.name) else {
+                push_error!(UnknownSymbol, Always, {
+                    Some(method.name_range.clone()) => {"`{}` is not a method of `{}`.", method.name, trait_path.join("::")},
+                    Some(symbol.range().clone())    => {"`{}` defined here.", trait_path.join("::")}
+                });
+                continue;
+            };
+            ProgramInfo::get().add_reference(method.name_range.clone(), &method.name, &method_symbol(declared));
+            method.check_signature(declared);
+        }
+        if (! matches!(target, Type::Failed)) {
+            ProgramInfo::get().add_impl(Implementation {
+                trait_path : trait_path.clone(),
+                typ        : target,
+                methods    : methods.clone(),
+                range
+            });
+        }
+    }
 
+
+    /// Report the struct or enum declared here if it contains itself without any indirection.
+    fn check_recursion(&self, name : &String, name_range : &Range) {
+        let mut path = self.range.0.clone();
+        path.push(name.clone());
+        let Some(cycle) = type_cycle(&path, &path, &mut Vec::new()) else {
+            return;
+        };
+        // Every type in the cycle finds it, so only report it from the first.
+        if (cycle.iter().all(|(owner, _)| path <= *owner)) {
+            let mut details = vec![(Some(name_range.clone()), format!("`{}` contains itself, so it would have infinite size.", name))];
+            details.extend(cycle.iter().map(|(owner, range)| (Some(range.clone()), format!("`{}` contains this field.", owner.join("::")))));
+            push_note_details!(
+                NoteType::Error(ErrorType::RecursiveType),
+                NoteOccurance::Always,
+                details
+            );
         }
     }
 
@@ -84,15 +356,322 @@ impl Declaration {
         use DeclarationType::*;
         match (&self.decl) {
 
-            Module(_, _) => {},
+            Module(_, _) | Use(_, _, _) | Struct(_, _, _, _) | Enum(_, _, _, _) | Trait(_, _, _) | Error(_) => {},
+
+            Function(name, _, _, args, ret, block) => {
+                let args = args.iter()
+                    .map(|(arg, range, typ)| bind_argument(arg, range, typ.unknown_value()))
+                    .collect::<Vec<_>>();
+                check_function(scope, name, args, ret, block);
+            },
+
+            Impl(_, _, typ, methods) => {
+                let target = typ.resolve(false);
+                for method in methods {
+                    let Some(block) = &method.block else {continue};
+                    let args = [bind_argument(&String::from("self"), &method.receiver, target.unknown_value())].into_iter()
+                        .chain(method.args.iter().map(|(arg, range, typ)| bind_argument(arg, range, typ.unknown_value())))
+                        .collect::<Vec<_>>();
+                    check_function(scope, &method.name, args, &method.ret, block);
+                }
+            }
+
+        }
+    }
+
+}
+
+
+
+/// Check the body of a function or method, which is given `args`.
+fn check_function(scope : &mut Scope, name : &String, args : Vec<(String, Symbol)>, ret : &Option<TypeDescriptor>, block : &Block) {
+    let expected = ret.as_ref().map_or(Type::Void, |ret| ret.resolve(false));
+    let mut subscope = scope.enter_function(name.clone(), expected.clone(), ret.clone());
+    check_body(&mut subscope, name, args, ret, &expected, block);
+}
+
+/// Check the body of a function, method or closure in the scope that it returns from.
+/// Returns the type of the value that the body ends with.
+fn check_body(subscope : &mut Scope, name : &String, args : Vec<(String, Symbol)>, ret : &Option<TypeDescriptor>, expected : &Type, block : &Block) -> Type {
+    let value = block.check_contents(subscope, name.clone(), args);
+    let found = value.value_ref().type_of();
+    // The last statement is returned if every path before it did not.
+    let last = block.stmts.last().map_or(value.range().clone(), |stmt| stmt.range.clone());
+    if (matches!(found, Type::Void) && ! matches!(expected, Type::Void | Type::Failed)) {
+        push_error!(MissingReturn, Always, {
+            Some(last)                                => {"Expected `{}`, but nothing is returned here.", expected},
+            ret.as_ref().map(|ret| ret.range.clone()) => {"Return type declared here."}
+        });
+    } else if (! expected.accepts(&found)) {
+        push_error!(InvalidTypeReceived, Always, {
+            Some(last)                                => {"Expected `{}`, found `{}`.", expected, found},
+            ret.as_ref().map(|ret| ret.range.clone()) => {"Return type declared here."}
+        });
+    } else if let Some(ret) = ret {
+        check_bounds(&Value::new(value.value(), last), ret);
+    }
+    return found;
+}
+
+/// Create the variable for an argument of a function, method or closure.
+/// `value` holds every value that the argument can be given.
+fn bind_argument(name : &String, range : &Range, value : ValueType) -> (String, Symbol) {
+    let symbol = Symbol::new(
+        Value::new(value, range.clone()),
+        false,
+        false,
+        range.clone()
+    );
+    ProgramInfo::get().add_reference(range.clone(), name, &symbol);
+    return (name.clone(), symbol);
+}
+
+/// Resolve the types in the signatures of the methods of a trait or an impl,
+/// and report any method that is declared more than once.
+fn expand_methods(methods : &Vec<Method>) {
+    for (i, method) in methods.iter().enumerate() {
+        if let Some(first) = methods[..i].iter().find(|other| other.name == method.name) {
+            push_error!(DuplicateSymbol, Always, {
+                Some(first.name_range.clone())  => {"Method `{}` already defined here.", method.name},
+                Some(method.name_range.clone()) => {"Defined again here."}
+            });
+        }
+        for (_, _, typ) in &method.args {
+            typ.resolve(true);
+        }
+        if let Some(ret) = &method.ret {
+            ret.resolve(true);
+        }
+    }
+}
 
-            Function(name, _, _, _, block) => {
-                let _ = block.check_contents(scope, name.clone());
+/// Check the elements of an array or list, which must all be the same type.
+/// Returns the element type, and the value of each element.
+fn check_elements(values : &Vec<Expression>, scope : &mut Scope) -> (Type, Vec<ValueType>) {
+    let values = values.iter().map(|value| value.check_contents(scope)).collect::<Vec<_>>();
+    let Some(first) = values.iter().find(|value| ! matches!(value.value_ref().type_of(), Type::Failed | Type::Never)) else {
+        return (Type::Failed, values.into_iter().map(|value| value.value()).collect());
+    };
+    let element = first.value_ref().type_of();
+    for value in &values {
+        let found = value.value_ref().type_of();
+        if (! element.accepts(&found)) {
+            push_error!(InvalidTypeReceived, Always, {
+                Some(value.range().clone()) => {"Expected `{}`, found `{}`.", element, found},
+                Some(first.range().clone()) => {"First element is `{}`.", element}
+            });
+            return (element, vec![ValueType::Failed; values.len()]);
+        }
+    }
+    return (element, values.into_iter().map(|value| value.value()).collect());
+}
+
+/// Report the mutable variables captured by a closure value which are declared `depth`
+/// scopes deep or deeper, as the value is about to outlive them. Closures held by
+/// structs, enums, tuples, arrays and lists are found too.
+/// They are removed from the value, so that they are only reported once.
+fn check_escape(value : Value, depth : usize) -> Value {
+    let range = value.range().clone();
+    return Value::new(remove_escaping(value.value(), depth, &range, false), range);
+}
+
+/// See `check_escape`. `held` is whether the value is inside of the value at `range`.
+fn remove_escaping(value : ValueType, depth : usize, range : &Range, held : bool) -> ValueType {
+    let remove = |values : Vec<ValueType>| values.into_iter().map(|value| remove_escaping(value, depth, range, true)).collect::<Vec<_>>();
+    return match (value) {
+        ValueType::Closure(args, ret, captures) => {
+            let (escaping, kept) = captures.into_iter().partition::<Vec<_>, _>(|(_, _, declared)| *declared >= depth);
+            for (name, declared, _) in escaping {
+                push_error!(EscapingCapture, Always, {
+                    Some(range.clone()) => {"{} captures `{}`, and outlives it.", if (held) {"A closure held by this value"} else {"This closure"}, name},
+                    Some(declared)      => {"`{}` is declared here, and is mutable.", name}
+                });
             }
+            ValueType::Closure(args, ret, kept)
+        },
+        ValueType::Struct(path, args, Some(fields)) => ValueType::Struct(path, args, Some(
+            fields.into_iter().map(|(name, value)| (name, remove_escaping(value, depth, range, true))).collect()
+        )),
+        ValueType::Enum(path, args, variant, Some(values)) => ValueType::Enum(path, args, variant, Some(remove(values))),
+        ValueType::Array(typ, length, Some(values))        => ValueType::Array(typ, length, Some(remove(values))),
+        ValueType::Tuple(values)                           => ValueType::Tuple(remove(values)),
+        ValueType::List(typ, Some(values))                 => ValueType::List(typ, Some(remove(values))),
+        value                                              => value
+    };
+}
+
+/// Report an integer given to `typ` which can be outside of the bounds of the type.
+/// Values which are always outside are errors, and values which only sometimes are are warnings.
+/// Returns whether the value is always within the bounds.
+fn check_bounds(value : &Value, typ : &TypeDescriptor) -> bool {
+    let Some(range) = value.value_ref().int_range() else {
+        return true;
+    };
+    let bounds = typ.bounds();
+    let Some(occurance) = range.leaves(&bounds) else {
+        return true;
+    };
+    let found = if (*range == IntRange::any()) {
+        String::from("a value without bounds")
+    } else {
+        format!("`{}`", range)
+    };
+    let note = if (matches!(occurance, NoteOccurance::Always)) {
+        NoteType::Error(ErrorType::Bound_Broken)
+    } else {
+        NoteType::Warn(WarnType::Bound_Broken)
+    };
+    push_note_details!(
+        note,
+        occurance,
+        vec![
+            (Some(value.range().clone()), format!("Expected a value in `{}`, found {}.", bounds, found)),
+            (Some(typ.range.clone()),     String::from("Bounds declared here."))
+        ]
+    );
+    return false;
+}
+
+/// Get the name of an integer type with an article, used in error messages.
+fn int_type_name(typ : &Type) -> String {
+    return format!("{} `{}`", if (matches!(typ, Type::Int)) {"an"} else {"a"}, typ);
+}
+
+/// Get a symbol which describes a method, so that references can be added to it.
+fn method_symbol(method : &Method) -> Symbol {
+    let block = method.block.clone().unwrap_or_else(|| Block {
+        stmts   : Vec::new(),
+        retlast : false,
+        range   : method.range.clone()
+    });
+    return Symbol::new(
+        Value::new(ValueType::Function(method.name.clone(), method.args.clone(), method.ret.clone(), block), method.range.clone()),
+        false,
+        true,
+        method.range.clone()
+    ).with_name_range(method.name_range.clone());
+}
+
+
 
+impl Method {
+
+    /// Check that a method in an impl takes and returns the same types
+    /// as the method that it implements was declared with in the trait.
+    fn check_signature(&self, declared : &Method) {
+        if (self.args.len() != declared.args.len()) {
+            push_error!(InvalidArgumentCount, Always, {
+                Some(self.name_range.clone())     => {"Expected {} argument{} after `self`, found {}.", declared.args.len(), if (declared.args.len() != 1) {"s"} else {""}, self.args.len()},
+                Some(declared.name_range.clone()) => {"Declared here."}
+            });
+        }
+        for ((_, _, typ), (_, _, declared_typ)) in self.args.iter().zip(&declared.args) {
+            let expected = declared_typ.resolve(false);
+            let found    = typ.resolve(false);
+            if (! expected.accepts(&found)) {
+                push_error!(InvalidTypeReceived, Always, {
+                    Some(typ.range.clone())          => {"Expected `{}`, found `{}`.", expected, found},
+                    Some(declared_typ.range.clone()) => {"Declared here."}
+                });
+            }
+        }
+        let expected = declared.ret.as_ref().map_or(Type::Void, |ret| ret.resolve(false));
+        let found    = self.ret.as_ref().map_or(Type::Void, |ret| ret.resolve(false));
+        if (! expected.accepts(&found)) {
+            push_error!(InvalidTypeReceived, Always, {
+                Some(self.ret.as_ref().map_or(&self.name_range, |ret| &ret.range).clone())         => {"Expected to return `{}`, found `{}`.", expected, found},
+                Some(declared.ret.as_ref().map_or(&declared.name_range, |ret| &ret.range).clone()) => {"Declared here."}
+            });
         }
     }
 
+}
+
+
+
+impl DeclarationHeader {
+
+    fn register(&self, decl : &DeclarationType) {
+        use DeclarationHeaderType::*;
+        match (self.header) {
+
+            Entry => {
+                match (decl) {
+                    DeclarationType::Function(name, _, generics, args, _, _) => {
+                        if (args.len() > 0) {
+                            push_error!(InvalidEntryHeader, Always, {
+                                Some(self.range.clone())    => {"Entry function can not take arguments."},
+                                Some(args[0].1.clone())     => {"Argument defined here."}
+                            });
+                        } else if (generics.len() > 0) {
+                            push_error!(InvalidEntryHeader, Always, {
+                                Some(self.range.clone())        => {"Entry function can not be generic."},
+                                Some(generics[0].range.clone()) => {"Type parameter defined here."}
+                            });
+                        }
+                        // Invalid entry functions are still kept, so that the entry is not also reported missing.
+                        // The program does not run if an error was reported.
+                        ProgramInfo::get().set_entry(self.range.clone(), name.clone());
+                    },
+                    DeclarationType::Module(_, range) => {
+                        push_error!(InvalidEntryHeader, Always, {
+                            Some(self.range.clone()) => {"Only functions can be entry points."},
+                            Some(range.clone())      => {"This is a module."}
+                        });
+                    },
+                    DeclarationType::Use(_, items, _) => {
+                        push_error!(InvalidEntryHeader, Always, {
+                            Some(self.range.clone())  => {"Only functions can be entry points."},
+                            Some(items[0].1.clone())  => {"This is an import."}
+                        });
+                    },
+                    DeclarationType::Struct(_, range, _, _) => {
+                        push_error!(InvalidEntryHeader, Always, {
+                            Some(self.range.clone()) => {"Only functions can be entry points."},
+                            Some(range.clone())      => {"This is a struct."}
+                        });
+                    },
+                    DeclarationType::Enum(_, range, _, _) => {
+                        push_error!(InvalidEntryHeader, Always, {
+                            Some(self.range.clone()) => {"Only functions can be entry points."},
+                            Some(range.clone())      => {"This is an enum."}
+                        });
+                    },
+                    DeclarationType::Trait(_, range, _) => {
+                        push_error!(InvalidEntryHeader, Always, {
+                            Some(self.range.clone()) => {"Only functions can be entry points."},
+                            Some(range.clone())      => {"This is a trait."}
+                        });
+                    },
+                    DeclarationType::Impl(_, range, _, _) => {
+                        push_error!(InvalidEntryHeader, Always, {
+                            Some(self.range.clone()) => {"Only functions can be entry points."},
+                            Some(range.clone())      => {"This is an impl."}
+                        });
+                    },
+                    // The function failed to parse, so it might have been a valid entry.
+                    // The program does not run, but the entry is not also reported missing.
+                    DeclarationType::Error(Some((keyword, name, range))) => {
+                        if (keyword == "fn") {
+                            ProgramInfo::get().set_entry(self.range.clone(), name.clone());
+                        } else {
+                            let kind = match (keyword.as_str()) {
+                                "struct" => "a struct",
+                                "enum"   => "an enum",
+                                _        => "a trait"
+                            };
+                            push_error!(InvalidEntryHeader, Always, {
+                                Some(self.range.clone()) => {"Only functions can be entry points."},
+                                Some(range.clone())      => {"This is {}.", kind}
+                            });
+                        }
+                    },
+                    DeclarationType::Error(None) => {}
+                }
+            }
+
+        }
+    }
 
 }
 
@@ -100,28 +679,1650 @@ impl Declaration {
 
 impl Statement {
 
-    pub fn check_contents(&self, _scope : &mut Scope) -> Value {
-        push_error!(InternalError, Always, {
-            None => {"Todo : Check Contents"}
+    pub fn check_contents(&self, scope : &mut Scope) -> Value {
+        use StatementType::*;
+        return match (&self.stmt) {
+
+            InitVar(mutable, name, name_range, typ, value) => {
+                let mut value = value.check_contents(scope);
+                generics::report_uninferred_variant(&value);
+                if let Some(typ) = typ {
+                    let expected = typ.resolve(true);
+                    let found    = value.value_ref().type_of();
+                    if (! expected.accepts(&found)) {
+                        push_error!(InvalidTypeReceived, Always, {
+                            Some(value.range().clone()) => {"Expected `{}`, found `{}`.", expected, found},
+                            Some(typ.range.clone())     => {"`{}` is declared as `{}`.", name, expected}
+                        });
+                        value = Value::new(typ.value_of(&expected), value.range().clone());
+                    } else if (matches!(found, Type::Failed | Type::Never) || ! check_bounds(&value, typ)) {
+                        // Only the declared type is known about the value.
+                        value = Value::new(typ.value_of(&expected), value.range().clone());
+                    }
+                }
+                let mut symbol = Symbol::new(
+                    value,
+                    *mutable,
+                    false,
+                    name_range.clone()
+                );
+                if let Some(typ) = typ {
+                    symbol = symbol.with_declared(typ.clone());
+                }
+                ProgramInfo::get().add_reference(name_range.clone(), name, &symbol);
+                scope.init_symbol(name.clone(), symbol);
+                Value::new(ValueType::Void, self.range.clone())
+            },
+
+            Destructure(mutable, pattern, value) => {
+                let value = value.check_contents(scope);
+                let typ   = value.value_ref().type_of();
+                let mut bindings = Vec::new();
+                pattern.check_contents(scope, &typ, &mut bindings);
+                // There is nothing else to run if the value does not match.
+                // Patterns which failed to check were already reported.
+                let shape = PatternShape::of(pattern, &typ);
+                if (! shape.is_failed() && matches::is_useful(&[vec![shape]], &[PatternShape::Any], &[typ.clone()])) {
+                    push_error!(NonExhaustiveMatch, Always, {
+                        Some(pattern.range.clone()) => {"This pattern does not match every `{}`.", typ},
+                        Some(value.range().clone()) => {"Use `match` to handle the values that it does not match."}
+                    });
+                }
+                for (name, symbol) in bindings {
+                    scope.init_symbol(name, Symbol::new(symbol.value().clone(), *mutable, false, symbol.range().clone()));
+                }
+                Value::new(ValueType::Void, self.range.clone())
+            },
+
+            Assign(name, name_range, op, value) => {
+                // `x += 1` is checked as `x = x + 1`.
+                let value = match (op) {
+                    Some(op) => crate::parse::node::Expression::compound(name, name_range, op, value, &self.range).check_contents(scope),
+                    None => value.check_contents(scope)
+                };
+                // Compound assignments already looked the symbol up.
+                let Some(symbol) = scope.resolve_path(&vec![name.clone()], name_range, op.is_none()) else {
+                    return Value::new(ValueType::Failed, self.range.clone());
+                };
+                if (op.is_none()) {
+                    ProgramInfo::get().add_reference(name_range.clone(), name, symbol);
+                }
+                if (! symbol.is_mutable()) {
+                    // Symbols in the root scope are declarations, not variables.
+                    let variable = scope.symbol_depth(name).is_some_and(|depth| depth > 0);
+                    push_error!(AssignmentToImmutable, Always, {
+                        Some(self.range.clone())     => {"`{}` can not be assigned to.", name},
+                        Some(symbol.range().clone()) => {"{}", if (variable) {
+                            format!("Declared here. Use `let mut {}` to allow assignment.", name)
+                        } else {
+                            String::from("Defined here. Only variables can be assigned to.")
+                        }}
+                    });
+                    return Value::new(ValueType::Failed, self.range.clone());
+                }
+                let expected = symbol.value().value_ref().type_of();
+                let found    = value.value_ref().type_of();
+                if (! expected.accepts(&found)) {
+                    push_error!(InvalidTypeReceived, Always, {
+                        Some(value.range().clone())  => {"Expected `{}`, found `{}`.", expected, found},
+                        Some(symbol.range().clone()) => {"`{}` is `{}`.", name, expected}
+                    });
+                    return Value::new(ValueType::Failed, self.range.clone());
+                }
+                let mut value = value;
+                if let Some(declared) = symbol.declared() {
+                    if (! check_bounds(&value, declared)) {
+                        value = Value::new(declared.value_of(&expected), value.range().clone());
+                    }
+                }
+                // The variable may be declared further out than the variables that the value captures.
+                let value = check_escape(value, scope.symbol_depth(name).unwrap_or(0) + 1);
+                scope.set_symbol_value(name, value);
+                Value::new(ValueType::Void, self.range.clone())
+            },
+
+            Break(value) => {
+                let value = value.as_ref().map(|value| value.check_contents(scope));
+                let Some(looping) = scope.current_loop() else {
+                    push_error!(InvalidLoopControl, Always, {
+                        Some(self.range.clone()) => {"`break` can only be used inside of a loop."}
+                    });
+                    return Value::new(ValueType::Failed, self.range.clone());
+                };
+                if let Some(value) = value {
+                    if (! looping.values) {
+                        push_error!(InvalidLoopControl, Always, {
+                            Some(value.range().clone()) => {"Only `loop` can be broken with a value."}
+                        });
+                        return Value::new(ValueType::Failed, self.range.clone());
+                    }
+                    looping.breaks.borrow_mut().push(value);
+                } else {
+                    looping.breaks.borrow_mut().push(Value::new(ValueType::Void, self.range.clone()));
+                }
+                Value::new(ValueType::Never, self.range.clone())
+            },
+
+            Continue => {
+                if (scope.current_loop().is_none()) {
+                    push_error!(InvalidLoopControl, Always, {
+                        Some(self.range.clone()) => {"`continue` can only be used inside of a loop."}
+                    });
+                    return Value::new(ValueType::Failed, self.range.clone());
+                }
+                Value::new(ValueType::Never, self.range.clone())
+            },
+
+            Return(value) => {
+                let value = match (value) {
+                    Some(value) => value.check_contents(scope),
+                    None        => Value::new(ValueType::Void, self.range.clone())
+                };
+                let value = check_escape(value, scope.function_depth() + 1);
+                if let Some((expected, ret)) = scope.return_type() {
+                    let found = value.value_ref().type_of();
+                    if (! expected.accepts(&found)) {
+                        push_error!(InvalidTypeReceived, Always, {
+                            Some(value.range().clone())               => {"Expected `{}`, found `{}`.", expected, found},
+                            ret.as_ref().map(|ret| ret.range.clone()) => {"Return type declared here."}
+                        });
+                    } else if let Some(ret) = ret {
+                        check_bounds(&value, ret);
+                    }
+                }
+                Value::new(ValueType::Never, self.range.clone())
+            },
+
+            Expression(expr) => expr.check_contents(scope),
+
+            // Uses of a variable that failed to parse should not also be reported as unknown.
+            Error(Some((mutable, name, name_range))) => {
+                let symbol = Symbol::new(
+                    Value::new(ValueType::Failed, self.range.clone()),
+                    *mutable,
+                    false,
+                    name_range.clone()
+                );
+                scope.init_symbol(name.clone(), symbol);
+                Value::new(ValueType::Failed, self.range.clone())
+            },
+
+            Error(None) => Value::new(ValueType::Failed, self.range.clone())
+
+        };
+    }
+
+}
+
+
+
+impl Expression {
+
+    pub fn check_contents(&self, scope : &mut Scope) -> Value {
+        use ExpressionType::*;
+        return match (&self.expr) {
+
+            LogicalOrOperation      (left, right) => self.check_operation("||", left, right, scope),
+            LogicalAndOperation     (left, right) => self.check_operation("&&", left, right, scope),
+            EqualsOperation         (left, right) => self.check_operation("==", left, right, scope),
+            NotEqualsOperation      (left, right) => self.check_operation("!=", left, right, scope),
+            GreaterOperation        (left, right) => self.check_operation(">",  left, right, scope),
+            GreaterEqualsOperation  (left, right) => self.check_operation(">=", left, right, scope),
+            LessOperation           (left, right) => self.check_operation("<",  left, right, scope),
+            LessEqualsOperation     (left, right) => self.check_operation("<=", left, right, scope),
+            AdditionOperation       (left, right) => self.check_operation("+",  left, right, scope),
+            SubtractionOperation    (left, right) => self.check_operation("-",  left, right, scope),
+            MultiplicationOperation (left, right) => self.check_operation("*",  left, right, scope),
+            DivisionOperation       (left, right) => self.check_operation("/",  left, right, scope),
+            ModuloOperation         (left, right) => self.check_operation("%",  left, right, scope),
+            BitwiseOrOperation      (left, right) => self.check_operation("|",  left, right, scope),
+            BitwiseXorOperation     (left, right) => self.check_operation("^",  left, right, scope),
+            BitwiseAndOperation     (left, right) => self.check_operation("&",  left, right, scope),
+            ShiftLeftOperation      (left, right) => self.check_operation("<<", left, right, scope),
+            ShiftRightOperation     (left, right) => self.check_operation(">>", left, right, scope),
+
+            NotOperation    (expr) => self.check_unary("!", expr, scope),
+            NegateOperation (expr) => self.check_unary("-", expr, scope),
+
+            Field(expr, name, name_range) => self.check_field(expr, name, name_range, scope),
+
+            MethodCall(expr, name, name_range, args) => self.check_method_call(expr, name, name_range, args, scope),
+
+            Index(expr, index) => self.check_index(expr, index, scope),
+
+            Atom(atom) => atom.check_contents(scope)
+
+        };
+    }
+
+    /// Check both sides of a binary operator, then get the resulting value.
+    /// If both sides are known at check time, the result will be too.
+    fn check_operation(&self, op : &str, left : &Expression, right : &Expression, scope : &mut Scope) -> Value {
+        let left_value  = left.check_contents(scope);
+        let right_value = right.check_contents(scope);
+        let left_type   = left_value.value_ref().type_of();
+        let right_type  = right_value.value_ref().type_of();
+        if (matches!(left_type, Type::Failed) || matches!(right_type, Type::Failed)) {
+            return Value::new(ValueType::Failed, self.range.clone());
+        }
+        if (matches!(left_type, Type::Never) || matches!(right_type, Type::Never)) {
+            return Value::new(ValueType::Never, self.range.clone());
+        }
+        let allowed = match (op) {
+            "==" | "!="       => left_type.is_numeric() || matches!(left_type, Type::Bool | Type::Char | Type::String),
+            "&&" | "||"       => matches!(left_type, Type::Bool),
+            "&" | "|" | "^"   => left_type.is_integer() || matches!(left_type, Type::Bool),
+            "<<" | ">>"       => left_type.is_integer(),
+            _                 => left_type.is_numeric()
+        };
+        if (left_type != right_type || ! allowed) {
+            push_error!(InvalidTypeReceived, Always, {
+                Some(self.range.clone())  => {"Operator `{}` can not be applied to `{}` and `{}`.", op, left_type, right_type},
+                Some(left.range.clone())  => {"This is `{}`.", left_type},
+                Some(right.range.clone()) => {"This is `{}`.", right_type}
+            });
+            return Value::new(ValueType::Failed, self.range.clone());
+        }
+        use ValueType::*;
+        let value = match ((left_value.value(), right_value.value())) {
+            (Byte(l), Byte(r)) | (Int(l), Int(r)) | (UInt(l), UInt(r)) => self.fold_int(op, l, r, &left_type, right),
+            (Float(l), Float(r)) | (UFloat(l), UFloat(r))              => self.fold_float(op, l, r, &left_type),
+            (Bool(l), Bool(r))                                         => Bool(fold_bool(op, l, r)),
+            (Char(l), Char(r))                                         => Bool(fold_equals(op, l, r)),
+            (String(l), String(r))                                     => Bool(fold_equals(op, l, r)),
+            _ => Failed
+        };
+        return Value::new(value, self.range.clone());
+    }
+
+    /// Check the operand of a unary operator, then get the resulting value.
+    fn check_unary(&self, op : &str, expr : &Expression, scope : &mut Scope) -> Value {
+        let value = expr.check_contents(scope);
+        let typ   = value.value_ref().type_of();
+        if (matches!(typ, Type::Failed | Type::Never)) {
+            return value;
+        }
+        use ValueType::*;
+        let value = match ((op, value.value())) {
+            ("!", Bool(value))  => Bool(value.map(|value| ! value)),
+            ("-", Int(range))   => Int(range.negate()),
+            ("-", Float(value)) => Float(value.map(|value| -value)),
+            _ => {
+                push_error!(InvalidTypeReceived, Always, {
+                    Some(self.range.clone()) => {"Operator `{}` can not be applied to `{}`.", op, typ},
+                    Some(expr.range.clone()) => {"This is `{}`.", typ}
+                });
+                Failed
+            }
+        };
+        return Value::new(value, self.range.clone());
+    }
+
+    /// Check access to a field of a struct instance.
+    /// If the instance was built at check time, the field's value is known too.
+    fn check_field(&self, expr : &Expression, name : &String, name_range : &Range, scope : &mut Scope) -> Value {
+        let value = expr.check_contents(scope);
+        let typ   = value.value_ref().type_of();
+        if (matches!(typ, Type::Failed | Type::Never)) {
+            return Value::new(value.value(), self.range.clone());
+        }
+        if let (ValueType::Tuple(mut values), Ok(i)) = (value.value_ref().clone(), name.parse::<usize>()) {
+            if (i >= values.len()) {
+                push_error!(Bound_Broken, Always, {
+                    Some(name_range.clone()) => {"Index `{}` is out of bounds.", i},
+                    Some(expr.range.clone()) => {"This `{}` has {} element{}.", typ, values.len(), if (values.len() != 1) {"s"} else {""}}
+                });
+                return Value::new(ValueType::Failed, self.range.clone());
+            }
+            return Value::new(values.swap_remove(i), self.range.clone());
+        }
+        let Type::Struct(path, _) = &typ else {
+            push_error!(UnknownSymbol, Always, {
+                Some(name_range.clone()) => {"`{}` has no fields.", typ},
+                Some(expr.range.clone()) => {"This is `{}`.", typ}
+            });
+            return Value::new(ValueType::Failed, self.range.clone());
+        };
+        let Some(field) = struct_fields(path).and_then(|fields| fields.iter().find(|(field, _, _, _)| field == name)) else {
+            push_error!(UnknownSymbol, Always, {
+                Some(name_range.clone()) => {"`{}` has no field `{}`.", typ, name},
+                Some(expr.range.clone()) => {"This is `{}`.", typ}
+            });
+            return Value::new(ValueType::Failed, self.range.clone());
+        };
+        access_field(path, name_range, field);
+        let known = match (value.value()) {
+            ValueType::Struct(_, _, Some(values)) => values.into_iter().find(|(field, _)| field == name).map(|(_, value)| value),
+            _                                     => None
+        };
+        return Value::new(known.unwrap_or_else(|| field.3.value_of(&generics::field_type(&typ, &field.3))), self.range.clone());
+    }
+
+    /// Check an index into an array or list.
+    /// If the index is known at check time, it must be in bounds.
+    fn check_index(&self, expr : &Expression, index : &Expression, scope : &mut Scope) -> Value {
+        let value       = expr.check_contents(scope);
+        let index_value = index.check_contents(scope);
+        let index_type  = index_value.value_ref().type_of();
+        if (! index_type.is_integer() && ! matches!(index_type, Type::Failed | Type::Never)) {
+            push_error!(InvalidTypeReceived, Always, {
+                Some(index.range.clone()) => {"Expected an integer, found `{}`.", index_type}
+            });
+        }
+        let constant = index_value.value_ref().int_constant().flatten().cloned();
+        let typ      = value.value_ref().type_of();
+        let (element, length, known) = match (value.value()) {
+            ValueType::Array(element, length, known) => (element, Some(length), known),
+            ValueType::List(element, known)          => {
+                let length = known.as_ref().map(|known| known.len());
+                (element, length, known)
+            },
+            value @ (ValueType::Failed | ValueType::Never) => {return Value::new(value, self.range.clone());},
+            _ => {
+                push_error!(InvalidTypeReceived, Always, {
+                    Some(expr.range.clone()) => {"`{}` can not be indexed.{}", typ, if (matches!(typ, Type::Tuple(_))) {" Use `.0` to get an element of a tuple."} else {""}}
+                });
+                return Value::new(ValueType::Failed, self.range.clone());
+            }
+        };
+        if let (Some(constant), Some(length)) = (constant, length) {
+            let Some(i) = usize::try_from(&constant).ok().filter(|i| *i < length) else {
+                push_error!(Bound_Broken, Always, {
+                    Some(index.range.clone()) => {"Index `{}` is out of bounds.", constant},
+                    Some(expr.range.clone())  => {"This `{}` has {} element{}.", typ, length, if (length != 1) {"s"} else {""}}
+                });
+                return Value::new(ValueType::Failed, self.range.clone());
+            };
+            if let Some(mut known) = known {
+                return Value::new(known.swap_remove(i), self.range.clone());
+            }
+        }
+        return Value::new(element.unknown_value(), self.range.clone());
+    }
+
+    /// Check a call to a method, which is found from the impls for the type of the receiver.
+    fn check_method_call(&self, expr : &Expression, name : &String, name_range : &Range, args : &Vec<Expression>, scope : &mut Scope) -> Value {
+        let value  = expr.check_contents(scope);
+        let values = args.iter().map(|arg| arg.check_contents(scope)).collect::<Vec<_>>();
+        let typ    = value.value_ref().type_of();
+        if (matches!(typ, Type::Failed | Type::Never)) {
+            return Value::new(value.value(), self.range.clone());
+        }
+        // A type parameter has the methods of the traits that it is bound by.
+        let methods = match (&typ) {
+            Type::Param(path) => generics::bound_methods(path, name),
+            _                 => ProgramInfo::get().methods_of(&typ, name).into_iter()
+                .map(|(implementation, method)| (&implementation.trait_path, method))
+                .collect()
+        };
+        let [(_, method)] = methods[..] else {
+            if (methods.is_empty()) {
+                push_error!(UnknownSymbol, Always, {
+                    Some(name_range.clone()) => {"`{}` has no method `{}`.", typ, name},
+                    Some(expr.range.clone()) => {"This is `{}`.", typ}
+                });
+            } else {
+                let mut details = vec![(Some(name_range.clone()), format!("`{}` has more than one method `{}`.", typ, name))];
+                details.extend(methods.iter().map(|(trait_path, method)| (Some(method.name_range.clone()), format!("From `{}`.", trait_path.join("::")))));
+                push_note_details!(
+                    NoteType::Error(ErrorType::ConflictingImpl),
+                    NoteOccurance::Always,
+                    details
+                );
+            }
+            return Value::new(ValueType::Failed, self.range.clone());
+        };
+        ProgramInfo::get().add_reference(name_range.clone(), name, &method_symbol(method));
+        if (values.len() != method.args.len()) {
+            push_error!(InvalidArgumentCount, Always, {
+                Some(self.range.clone())        => {"Expected {} argument{}, found {}.", method.args.len(), if (method.args.len() != 1) {"s"} else {""}, values.len()},
+                Some(method.name_range.clone()) => {"Defined here."}
+            });
+        }
+        for (arg, (param, param_range, typ)) in values.iter().zip(&method.args) {
+            let expected = typ.resolve(false);
+            let found    = arg.value_ref().type_of();
+            if (! expected.accepts(&found)) {
+                push_error!(InvalidTypeReceived, Always, {
+                    Some(arg.range().clone()) => {"Expected `{}`, found `{}`.", expected, found},
+                    Some(param_range.clone()) => {"Argument `{}` defined here.", param}
+                });
+            } else {
+                check_bounds(arg, typ);
+            }
+        }
+        return Value::new(
+            method.ret.as_ref().map_or(ValueType::Void, |ret| ret.unknown_value()),
+            self.range.clone()
+        );
+    }
+
+    /// Apply an operator to two integers of type `typ`.
+    /// If either side is not known, the range of the result is found instead.
+    fn fold_int(&self, op : &str, left : IntRange, right : IntRange, typ : &Type, right_expr : &Expression) -> ValueType {
+        if ((op == "/" || op == "%") && matches!(right.constant(), Some(r) if *r == BigInt::from(0))) {
+            push_error!(DivisionByZero, Always, {
+                Some(right_expr.range.clone()) => {"This is always zero."}
+            });
+            return typ.unknown_value();
+        }
+        let bits = if (matches!(typ, Type::Byte)) {8} else {64};
+        if ((op == "<<" || op == ">>") && matches!(right.constant(), Some(r) if *r < BigInt::from(0) || *r >= BigInt::from(bits))) {
+            push_error!(ArithmeticOverflow, Always, {
+                Some(right_expr.range.clone()) => {"Shift amount must be between 0 and {}.", bits - 1}
+            });
+            return typ.unknown_value();
+        }
+        let (Some(l), Some(r)) = (left.constant().cloned(), right.constant().cloned()) else {
+            return match (op) {
+                "==" | "!=" | ">" | ">=" | "<" | "<=" => ValueType::Bool(None),
+                _                                     => typ.int_value(left.apply(op, &right))
+            };
+        };
+        let exact = |value : BigInt| {
+            if (IntRange::exact(value.clone()).leaves(&typ.int_limits()).is_some()) {
+                push_error!(ArithmeticOverflow, Always, {
+                    Some(self.range.clone()) => {"Result `{}` does not fit in {}.", value, int_type_name(typ)}
+                });
+                return typ.unknown_value();
+            }
+            typ.int_value(IntRange::exact(value))
+        };
+        return match (op) {
+            "==" => ValueType::Bool(Some(l == r)),
+            "!=" => ValueType::Bool(Some(l != r)),
+            ">"  => ValueType::Bool(Some(l > r)),
+            ">=" => ValueType::Bool(Some(l >= r)),
+            "<"  => ValueType::Bool(Some(l < r)),
+            "<=" => ValueType::Bool(Some(l <= r)),
+            "+"  => exact(l + r),
+            "-"  => exact(l - r),
+            "*"  => exact(l * r),
+            "/"  => exact(l / r),
+            "%"  => exact(l % r),
+            "&"  => exact(l & r),
+            "|"  => exact(l | r),
+            "^"  => exact(l ^ r),
+            // The amount was checked to be in range above.
+            "<<" => exact(l << usize::try_from(r).unwrap_or(0)),
+            ">>" => exact(l >> usize::try_from(r).unwrap_or(0)),
+            _    => ValueType::Failed
+        };
+    }
+
+    /// Apply an operator to two floats of type `typ`.
+    fn fold_float(&self, op : &str, left : Option<BigFloat>, right : Option<BigFloat>, typ : &Type) -> ValueType {
+        let (Some(l), Some(r)) = (left, right) else {
+            return match (op) {
+                "==" | "!=" | ">" | ">=" | "<" | "<=" => ValueType::Bool(None),
+                _                                     => typ.unknown_value()
+            };
+        };
+        return match (op) {
+            "==" => ValueType::Bool(Some(l == r)),
+            "!=" => ValueType::Bool(Some(l != r)),
+            ">"  => ValueType::Bool(Some(l > r)),
+            ">=" => ValueType::Bool(Some(l >= r)),
+            "<"  => ValueType::Bool(Some(l < r)),
+            "<=" => ValueType::Bool(Some(l <= r)),
+            "+"  => typ.float_value(Some(l + r)),
+            "-"  => typ.float_value(Some(l - r)),
+            "*"  => typ.float_value(Some(l * r)),
+            "/"  => typ.float_value(Some(l / r)),
+            "%"  => typ.float_value(Some(l % r)),
+            _    => ValueType::Failed
+        };
+    }
+
+}
+
+/// Apply an operator to two bools.
+/// `&&` and `||` are known if either side decides the result.
+fn fold_bool(op : &str, left : Option<bool>, right : Option<bool>) -> Option<bool> {
+    return match ((op, left, right)) {
+        ("&&", Some(false), _) | ("&&", _, Some(false)) => Some(false),
+        ("||", Some(true), _)  | ("||", _, Some(true))  => Some(true),
+        (_, Some(l), Some(r)) => Some(match (op) {
+            "=="       => l == r,
+            "!=" | "^" => l != r,
+            "&&" | "&" => l && r,
+            "||" | "|" => l || r,
+            _          => {return None;}
+        }),
+        _ => None
+    };
+}
+
+/// Apply `==` or `!=` to two values which can only be compared for equality.
+fn fold_equals<T : PartialEq>(op : &str, left : Option<T>, right : Option<T>) -> Option<bool> {
+    let (Some(l), Some(r)) = (left, right) else {return None};
+    return Some(if (op == "==") {l == r} else {l != r});
+}
+
+
+
+/// Get the fields of the struct declared at `path`.
+fn struct_fields(path : &Vec<String>) -> Option<&'static Vec<(String, Range, bool, TypeDescriptor)>> {
+    let scope  = ProgramInfo::get().scope_of(&path[..(path.len() - 1)].to_vec())?;
+    let symbol = scope.get_symbol(&path[path.len() - 1])?;
+    return match (symbol.value().value_ref()) {
+        ValueType::StructType(_, fields) => Some(fields),
+        _                                => None
+    };
+}
+
+/// Get the types that are stored directly in the struct or enum declared at `path`,
+/// with the range of the field that stores each one.
+fn contained_types(path : &Vec<String>) -> Option<Vec<(&'static Range, &'static TypeDescriptor)>> {
+    let scope  = ProgramInfo::get().scope_of(&path[..(path.len() - 1)].to_vec())?;
+    let symbol = scope.get_symbol(&path[path.len() - 1])?;
+    return match (symbol.value().value_ref()) {
+        ValueType::StructType(_, fields) => Some(fields.iter().map(|(_, range, _, typ)| (range, typ)).collect()),
+        ValueType::EnumType(_, variants) => Some(variants.iter().flat_map(|(_, _, fields)| match (fields) {
+            VariantFields::Unit          => Vec::new(),
+            VariantFields::Tuple(types)  => types.iter().map(|typ| (&typ.range, typ)).collect(),
+            VariantFields::Named(fields) => fields.iter().map(|(_, range, typ)| (range, typ)).collect()
+        }).collect()),
+        _                                => None
+    };
+}
+
+/// Find a chain of fields which leads from the struct or enum at `path` to the one at `target`,
+/// without going through the types in `visited` again.
+/// Each link is the type that the field is in, and the field's range.
+fn type_cycle(path : &Vec<String>, target : &Vec<String>, visited : &mut Vec<Vec<String>>) -> Option<Vec<(Vec<String>, Range)>> {
+    for (range, typ) in contained_types(path)? {
+        for inner in inline_types(typ.resolve(false)) {
+            if (inner == *target) {
+                return Some(vec![(path.clone(), range.clone())]);
+            }
+            if (! visited.contains(&inner)) {
+                visited.push(inner.clone());
+                if let Some(mut chain) = type_cycle(&inner, target, visited) {
+                    chain.insert(0, (path.clone(), range.clone()));
+                    return Some(chain);
+                }
+            }
+        }
+    }
+    return None;
+}
+
+/// Get the paths of the structs and enums stored directly inside a value of the type.
+/// Lists and functions are not included, as their contents are stored elsewhere.
+fn inline_types(typ : Type) -> Vec<Vec<String>> {
+    return match (typ) {
+        Type::Struct(path, _) | Type::Enum(path, _) => vec![path],
+        Type::Array(typ, length)                    => if (length > 0) {inline_types(*typ)} else {Vec::new()},
+        Type::Tuple(types)                          => types.into_iter().flat_map(inline_types).collect(),
+        _                                           => Vec::new()
+    };
+}
+
+/// Find the enum variant at the path, and add a reference to it.
+/// Returns the path and range of the enum, with a copy of the variant.
+/// See `Scope::resolve_variant`.
+fn resolve_variant(scope : &Scope, path : &Vec<String>, range : &Range) -> Option<Result<(Vec<String>, Range, (String, Range, VariantFields)), ()>> {
+    return scope.resolve_variant(path, range).map(|variant| variant.map(|(symbol, variant)| {
+        let ValueType::EnumType(enum_path, _) = symbol.value().value_ref() else {
+            panic!("INTERNAL ERROR");
+        };
+        let value  = ValueType::Enum(enum_path.clone(), Vec::new(), Some(variant.0.clone()), None);
+        let target = Symbol::new(Value::new(value, variant.1.clone()), false, true, variant.1.clone());
+        ProgramInfo::get().add_reference(range.clone(), &variant.0, &target);
+        (enum_path.clone(), symbol.range().clone(), variant.clone())
+    }));
+}
+
+/// Check that a field of the struct at `path` can be accessed from `range`,
+/// then add a reference to where it was declared.
+fn access_field(path : &Vec<String>, range : &Range, field : &(String, Range, bool, TypeDescriptor)) {
+    let (name, field_range, public, typ) = field;
+    if (! public && path[..(path.len() - 1)] != range.0[..]) {
+        push_error!(PrivateSymbolAccessed, Always, {
+            Some(range.clone())       => {"Field `{}` is private to module `{}`.", name, path[..(path.len() - 1)].join("::")},
+            Some(field_range.clone()) => {"Declared here. Add `pub` to make it accessible."}
         });
-        return Value::new(ValueType::Failed, self.range.clone());
+    }
+    let symbol = Symbol::new(
+        Value::new(typ.resolve(false).unknown_value(), field_range.clone()),
+        false,
+        *public,
+        field_range.clone()
+    );
+    ProgramInfo::get().add_reference(range.clone(), name, &symbol);
+}
+
+
+
+impl Atom {
+
+    pub fn check_contents(&self, scope : &mut Scope) -> Value {
+        use AtomType::*;
+        return match (&self.atom) {
+
+            Literal(lit) => lit.check_contents(scope),
+
+            Expression(expr) => Value::new(expr.check_contents(scope).value(), self.range.clone()),
+
+            If(ifs, els) => {
+                let mut values    = Vec::new();
+                // Whether the previous conditions could all be false.
+                let mut reachable = true;
+                // The branch that is always taken, if known.
+                let mut taken     = None;
+                for (i, (condition, block, range)) in ifs.iter().enumerate() {
+                    let constant = condition.check_condition(scope);
+                    let value    = block.check_contents(scope, String::from(if (i == 0) {"if"} else {"elif"}), Vec::new());
+                    if (! reachable) {
+                        push_warn!(BlockContents_Called, Never, {
+                            Some(range.clone()) => {"An earlier condition is always true."}
+                        });
+                    } else if let Some(constant) = constant {
+                        if (constant) {
+                            push_warn!(BlockContents_Called, Always, {
+                                Some(condition.range.clone()) => {"Condition is always true."}
+                            });
+                            reachable = false;
+                            taken     = Some(values.len());
+                        } else {
+                            push_warn!(BlockContents_Called, Never, {
+                                Some(condition.range.clone()) => {"Condition is always false."}
+                            });
+                        }
+                    }
+                    values.push(value);
+                }
+                let Some((els, range)) = els else {
+                    return Value::new(ValueType::Void, self.range.clone());
+                };
+                let value = els.check_contents(scope, String::from("else"), Vec::new());
+                if (! reachable) {
+                    push_warn!(BlockContents_Called, Never, {
+                        Some(range.clone()) => {"An earlier condition is always true."}
+                    });
+                }
+                values.push(value);
+                // Every branch that finishes must produce the same type.
+                let first = values.iter().position(|value| ! matches!(value.value_ref(), ValueType::Never)).unwrap_or(0);
+                let typ   = values[first].value_ref().type_of();
+                for value in &values[first + 1..] {
+                    let found = value.value_ref().type_of();
+                    if (! typ.accepts(&found)) {
+                        push_error!(InvalidTypeReceived, Always, {
+                            Some(value.range().clone())         => {"Expected `{}`, found `{}`.", typ, found},
+                            Some(values[first].range().clone()) => {"First branch is `{}`.", typ}
+                        });
+                        return Value::new(ValueType::Failed, self.range.clone());
+                    }
+                }
+                let taken = if (reachable) {None} else {taken};
+                Value::new(match (taken) {
+                    Some(i) => values.swap_remove(i).value(),
+                    None    => typ.unknown_value()
+                }, self.range.clone())
+            },
+
+            Call(path, path_range, args) => {
+                let args = args.iter().map(|arg| arg.check_contents(scope)).collect::<Vec<_>>();
+                if let Some(variant) = resolve_variant(scope, path, path_range) {
+                    let Ok((enum_path, _, (name, variant_range, variant_fields))) = variant else {
+                        return Value::new(ValueType::Failed, self.range.clone());
+                    };
+                    let VariantFields::Tuple(types) = &variant_fields else {
+                        push_error!(InvalidTypeReceived, Always, {
+                            Some(path_range.clone()) => {"Variant `{}` does not hold unnamed values.", name},
+                            Some(variant_range)      => {"Declared here."}
+                        });
+                        return Value::new(ValueType::Failed, self.range.clone());
+                    };
+                    if (args.len() != types.len()) {
+                        push_error!(InvalidArgumentCount, Always, {
+                            Some(self.range.clone())    => {"Expected {} value{}, found {}.", types.len(), if (types.len() != 1) {"s"} else {""}, args.len()},
+                            Some(variant_range.clone()) => {"Declared here."}
+                        });
+                    }
+                    let mut inference = generics::Inference::new(&enum_path);
+                    for (arg, typ) in args.iter().zip(types) {
+                        inference.unify(&typ.resolve(false), &arg.value_ref().type_of());
+                    }
+                    for (arg, typ) in args.iter().zip(types) {
+                        let expected = inference.apply(&typ.resolve(false));
+                        let found    = arg.value_ref().type_of();
+                        if (! expected.accepts(&found)) {
+                            push_error!(InvalidTypeReceived, Always, {
+                                Some(arg.range().clone()) => {"Expected `{}`, found `{}`.", expected, found},
+                                Some(typ.range.clone())   => {"Declared here."}
+                            });
+                        } else {
+                            check_bounds(arg, typ);
+                        }
+                    }
+                    inference.record(&self.range);
+                    let values = args.into_iter().map(|arg| arg.value()).collect();
+                    return Value::new(ValueType::Enum(enum_path, inference.args(), Some(name), Some(values)), self.range.clone());
+                }
+                let Some(symbol) = scope.resolve_path(path, path_range, true) else {
+                    return Value::new(ValueType::Failed, self.range.clone());
+                };
+                ProgramInfo::get().add_reference(path_range.clone(), &path[path.len() - 1], symbol);
+                if let ValueType::Closure(params, ret, _) = symbol.value().value_ref() {
+                    if (args.len() != params.len()) {
+                        push_error!(InvalidArgumentCount, Always, {
+                            Some(self.range.clone())     => {"Expected {} argument{}, found {}.", params.len(), if (params.len() != 1) {"s"} else {""}, args.len()},
+                            Some(symbol.range().clone()) => {"`{}` is `{}`.", path.join("::"), symbol.value().value_ref().type_of()}
+                        });
+                    }
+                    for (arg, expected) in args.iter().zip(params) {
+                        let found = arg.value_ref().type_of();
+                        if (! expected.accepts(&found)) {
+                            push_error!(InvalidTypeReceived, Always, {
+                                Some(arg.range().clone())    => {"Expected `{}`, found `{}`.", expected, found},
+                                Some(symbol.range().clone()) => {"`{}` is `{}`.", path.join("::"), symbol.value().value_ref().type_of()}
+                            });
+                        }
+                    }
+                    return Value::new(ret.unknown_value(), self.range.clone());
+                }
+                let ValueType::Function(function, params, ret, _) = symbol.value().value_ref() else {
+                    if (! matches!(symbol.value().value_ref(), ValueType::Failed)) {
+                        push_error!(InvalidTypeReceived, Always, {
+                            Some(path_range.clone())     => {"`{}` is not a function.", path.join("::")},
+                            Some(symbol.range().clone()) => {"Defined here."}
+                        });
+                    }
+                    return Value::new(ValueType::Failed, self.range.clone());
+                };
+                if (args.len() != params.len()) {
+                    push_error!(InvalidArgumentCount, Always, {
+                        Some(self.range.clone())     => {"Expected {} argument{}, found {}.", params.len(), if (params.len() != 1) {"s"} else {""}, args.len()},
+                        Some(symbol.range().clone()) => {"Defined here."}
+                    });
+                }
+                // The type arguments of a generic function are inferred from the arguments.
+                let mut inference = generics::Inference::new(&[symbol.range().0.clone(), vec![function.clone()]].concat());
+                for (arg, (_, _, typ)) in args.iter().zip(params) {
+                    inference.unify(&typ.resolve(false), &arg.value_ref().type_of());
+                }
+                for (arg, (param, param_range, typ)) in args.iter().zip(params) {
+                    let expected = inference.apply(&typ.resolve(false));
+                    let found    = arg.value_ref().type_of();
+                    if (! expected.accepts(&found)) {
+                        push_error!(InvalidTypeReceived, Always, {
+                            Some(arg.range().clone())   => {"Expected `{}`, found `{}`.", expected, found},
+                            Some(param_range.clone())   => {"Argument `{}` defined here.", param}
+                        });
+                    } else {
+                        check_bounds(arg, typ);
+                    }
+                }
+                if (inference.report_missing(&self.range, &path.join("::"))) {
+                    inference.record(&self.range);
+                }
+                Value::new(
+                    ret.as_ref().map_or(ValueType::Void, |ret| ret.value_of(&inference.apply(&ret.resolve(false)))),
+                    self.range.clone()
+                )
+            },
+
+            While(condition, block) => {
+                // The body may change any mutable variable before the condition is checked again.
+                scope.forget_mutable_constants();
+                let constant = condition.check_condition(scope);
+                match (constant) {
+                    Some(true)  => push_warn!(BlockContents_Called, Always, {
+                        Some(condition.range.clone()) => {"Condition is always true. Use `loop` instead."}
+                    }),
+                    Some(false) => push_warn!(BlockContents_Called, Never, {
+                        Some(condition.range.clone()) => {"Condition is always false."}
+                    }),
+                    None        => {}
+                }
+                let mut subscope = scope.enter_loop("while", false);
+                block.check_contents(&mut subscope, String::from("while"), Vec::new());
+                Value::new(ValueType::Void, self.range.clone())
+            },
+
+            Loop(block) => {
+                scope.forget_mutable_constants();
+                let mut subscope = scope.enter_loop("loop", true);
+                block.check_contents(&mut subscope, String::from("loop"), Vec::new());
+                let breaks = subscope.current_loop().unwrap().breaks.take().into_iter()
+                    .map(|value| check_escape(value, subscope.depth() + 1))
+                    .collect::<Vec<_>>();
+                // A loop which is never broken never finishes.
+                let Some(first) = breaks.first() else {
+                    return Value::new(ValueType::Never, self.range.clone());
+                };
+                // Every `break` must produce the same type.
+                let typ = first.value_ref().type_of();
+                for value in &breaks[1..] {
+                    let found = value.value_ref().type_of();
+                    if (! typ.accepts(&found)) {
+                        push_error!(InvalidTypeReceived, Always, {
+                            Some(value.range().clone()) => {"Expected `{}`, found `{}`.", typ, found},
+                            Some(first.range().clone()) => {"First `break` is `{}`.", typ}
+                        });
+                        return Value::new(ValueType::Failed, self.range.clone());
+                    }
+                }
+                Value::new(typ.unknown_value(), self.range.clone())
+            },
+
+            For(name, name_range, start, end, inclusive, block) => {
+                scope.forget_mutable_constants();
+                let start_value = start.check_contents(scope);
+                let end_value   = end.check_contents(scope);
+                let start_type  = start_value.value_ref().type_of();
+                let end_type    = end_value.value_ref().type_of();
+                let mut typ     = start_type.clone();
+                if (matches!(start_type, Type::Failed) || matches!(end_type, Type::Failed)) {
+                    typ = Type::Failed;
+                } else if (start_type != end_type || ! start_type.is_integer()) {
+                    push_error!(InvalidTypeReceived, Always, {
+                        Some(Range(self.range.0.clone(), start.range.1, end.range.2)) => {"Expected a range of integers, found `{}` and `{}`.", start_type, end_type},
+                        Some(start.range.clone())                                     => {"This is `{}`.", start_type},
+                        Some(end.range.clone())                                       => {"This is `{}`.", end_type}
+                    });
+                    typ = Type::Failed;
+                } else if let (Some(Some(first)), Some(Some(last))) = (start_value.value_ref().int_constant(), end_value.value_ref().int_constant()) {
+                    if (first > last || (first == last && ! inclusive)) {
+                        push_warn!(BlockContents_Called, Never, {
+                            Some(Range(self.range.0.clone(), start.range.1, end.range.2)) => {"Range is always empty."}
+                        });
+                    }
+                }
+                // The variable starts at the lowest start, and never passes the highest end.
+                let value = match ((start_value.value_ref().int_range(), end_value.value_ref().int_range())) {
+                    (Some(start_range), Some(end_range)) if (typ.is_integer()) => typ.int_value(IntRange {
+                        min : start_range.min.clone(),
+                        max : end_range.max.as_ref().map(|max| if (*inclusive) {max.clone()} else {max - 1})
+                    }),
+                    _ => typ.unknown_value()
+                };
+                let symbol = Symbol::new(
+                    Value::new(value, name_range.clone()),
+                    false,
+                    false,
+                    name_range.clone()
+                );
+                ProgramInfo::get().add_reference(name_range.clone(), name, &symbol);
+                let mut subscope = scope.enter_loop("for", false);
+                block.check_contents(&mut subscope, String::from("for"), vec![(name.clone(), symbol)]);
+                Value::new(ValueType::Void, self.range.clone())
+            },
+
+            Struct(path, path_range, fields) => {
+                let values = fields.iter().map(|(_, _, value)| value.check_contents(scope)).collect::<Vec<_>>();
+                if let Some(variant) = resolve_variant(scope, path, path_range) {
+                    let Ok((enum_path, _, (name, variant_range, variant_fields))) = variant else {
+                        return Value::new(ValueType::Failed, self.range.clone());
+                    };
+                    let VariantFields::Named(declared) = &variant_fields else {
+                        push_error!(InvalidTypeReceived, Always, {
+                            Some(path_range.clone()) => {"Variant `{}` does not hold named values.", name},
+                            Some(variant_range)      => {"Declared here."}
+                        });
+                        return Value::new(ValueType::Failed, self.range.clone());
+                    };
+                    // The fields of a variant are as public as the enum.
+                    let declared = declared.iter()
+                        .map(|(field, range, typ)| (field.clone(), range.clone(), true, typ.clone()))
+                        .collect::<Vec<_>>();
+                    let mut inference = generics::Inference::new(&enum_path);
+                    let known = self.check_fields(&format!("{}::{}", enum_path.join("::"), name), &enum_path, &declared, fields, values, &variant_range, &mut inference)
+                        .map(|known| known.into_iter().map(|(_, value)| value).collect());
+                    return Value::new(ValueType::Enum(enum_path, inference.args(), Some(name), known), self.range.clone());
+                }
+                let Some(symbol) = scope.resolve_path(path, path_range, true) else {
+                    return Value::new(ValueType::Failed, self.range.clone());
+                };
+                ProgramInfo::get().add_reference(path_range.clone(), &path[path.len() - 1], symbol);
+                let ValueType::StructType(struct_path, declared) = symbol.value().value_ref() else {
+                    if (! matches!(symbol.value().value_ref(), ValueType::Failed)) {
+                        push_error!(InvalidTypeReceived, Always, {
+                            Some(path_range.clone())     => {"`{}` is not a struct.", path.join("::")},
+                            Some(symbol.range().clone()) => {"Defined here."}
+                        });
+                    }
+                    return Value::new(ValueType::Failed, self.range.clone());
+                };
+                let mut inference = generics::Inference::new(struct_path);
+                let known = self.check_fields(&struct_path.join("::"), struct_path, declared, fields, values, symbol.range(), &mut inference);
+                Value::new(ValueType::Struct(struct_path.clone(), inference.args(), known), self.range.clone())
+            },
+
+            Array(values) => {
+                let (element, values) = check_elements(values, scope);
+                Value::new(ValueType::Array(element, values.len(), Some(values)), self.range.clone())
+            },
+
+            Tuple(values) => Value::new(
+                ValueType::Tuple(values.iter().map(|value| value.check_contents(scope).value()).collect()),
+                self.range.clone()
+            ),
+
+            List(values) => {
+                let (element, values) = check_elements(values, scope);
+                Value::new(ValueType::List(element, Some(values)), self.range.clone())
+            },
+
+            Closure(args, ret, block) => {
+                let types = args.iter().map(|(_, _, typ)| typ.resolve(true)).collect::<Vec<_>>();
+                // Without a return type, the closure returns whatever its body produces.
+                let expected = ret.as_ref().map_or(Type::Failed, |ret| ret.resolve(true));
+                let mut subscope = scope.enter_closure("closure", expected.clone(), ret.clone());
+                let args = args.iter()
+                    .map(|(arg, range, typ)| bind_argument(arg, range, typ.unknown_value()))
+                    .collect::<Vec<_>>();
+                let found = check_body(&mut subscope, &String::from("closure"), args, ret, &expected, block);
+                Value::new(ValueType::Closure(
+                    types,
+                    Box::new(if (ret.is_some()) {expected} else {found}),
+                    subscope.captures()
+                ), self.range.clone())
+            },
+
+            Match(value, arms) => self.check_match(value, arms, scope)
+
+        };
+    }
+
+    /// Check a `match`. Every value must be matched by some arm,
+    /// and every arm must be able to match something.
+    fn check_match(&self, value : &crate::parse::node::Expression, arms : &Vec<(Pattern, Block, bool, Range)>, scope : &mut Scope) -> Value {
+        let matched  = value.check_contents(scope);
+        let typ      = matched.value_ref().type_of();
+        // The variant or `bool` that the value always is, if known.
+        let constant = match (matched.value_ref()) {
+            ValueType::Enum(_, _, Some(variant), _) => Some(variant.clone()),
+            ValueType::Bool(Some(value))            => Some(value.to_string()),
+            _                                       => None
+        };
+        let mut rows   = Vec::<Vec<PatternShape>>::new();
+        let mut values = Vec::new();
+        for (pattern, block, _, _) in arms {
+            let mut bindings = Vec::new();
+            pattern.check_contents(scope, &typ, &mut bindings);
+            let shape    = PatternShape::of(pattern, &typ);
+            let mismatch = constant.as_ref().zip(pattern.key()).filter(|(constant, key)| *constant != key);
+            if let Some((constant, _)) = mismatch {
+                push_warn!(BlockContents_Called, Never, {
+                    Some(pattern.range.clone())   => {"This arm can never match."},
+                    Some(matched.range().clone()) => {"This is always `{}`.", constant}
+                });
+            } else if (! matches::is_useful(&rows, &[shape.clone()], &[typ.clone()])) {
+                // Point at a single earlier arm if it is the reason.
+                let earlier = rows.iter().position(|row| ! matches::is_useful(&[row.clone()], &[shape.clone()], &[typ.clone()]));
+                match (earlier) {
+                    Some(earlier) => push_warn!(BlockContents_Called, Never, {
+                        Some(pattern.range.clone())         => {"This arm can never match."},
+                        Some(arms[earlier].0.range.clone()) => {"Everything it matches was already matched here."}
+                    }),
+                    None => push_warn!(BlockContents_Called, Never, {
+                        Some(pattern.range.clone()) => {"This arm can never match. Everything it matches was already matched by earlier arms."}
+                    })
+                }
+            }
+            rows.push(vec![shape]);
+            values.push(block.check_contents(scope, String::from("match"), bindings));
+        }
+        let exhaustive = ! matches::is_useful(&rows, &[PatternShape::Any], &[typ.clone()]);
+        if (! exhaustive && ! matches!(typ, Type::Failed | Type::Never)) {
+            if (matches!(typ, Type::Tuple(_) | Type::Array(_, _))) {
+                push_error!(NonExhaustiveMatch, Always, {
+                    Some(value.range.clone()) => {"Some values of `{}` are not matched. Add a `_` arm to match them.", typ}
+                });
+            } else if let Some(constructors) = matches::constructors(&typ) {
+                let missing = constructors.iter()
+                    .filter(|(name, fields)| matches::is_useful(&rows, &[PatternShape::Constructor(name.clone(), vec![PatternShape::Any; fields.len()])], &[typ.clone()]))
+                    .map(|(name, _)| format!("`{}`", name))
+                    .collect::<Vec<_>>();
+                push_error!(NonExhaustiveMatch, Always, {
+                    Some(value.range.clone()) => {"Missing {}{} {}.",
+                        if (matches!(typ, Type::Enum(_, _))) {"variant"} else {"value"},
+                        if (missing.len() != 1) {"s"} else {""},
+                        missing.join(", ")
+                    }
+                });
+            } else {
+                push_error!(NonExhaustiveMatch, Always, {
+                    Some(value.range.clone()) => {"Missing a `_` arm, since `{}` has too many values to list.", typ}
+                });
+            }
+        }
+        // Every arm that finishes must produce the same type.
+        let Some(first) = values.iter().position(|value| ! matches!(value.value_ref(), ValueType::Never)) else {
+            return Value::new(ValueType::Never, self.range.clone());
+        };
+        let result = values[first].value_ref().type_of();
+        for value in &values[first + 1..] {
+            let found = value.value_ref().type_of();
+            if (! result.accepts(&found)) {
+                push_error!(InvalidTypeReceived, Always, {
+                    Some(value.range().clone())         => {"Expected `{}`, found `{}`.", result, found},
+                    Some(values[first].range().clone()) => {"First arm is `{}`.", result}
+                });
+                return Value::new(ValueType::Failed, self.range.clone());
+            }
+        }
+        return Value::new(result.unknown_value(), self.range.clone());
+    }
+
+    /// Check the fields given to a struct literal against the fields of `owner`,
+    /// which is declared in the module of `path`.
+    /// The type arguments of a generic struct or enum are inferred into `inference`.
+    /// Returns the field values if every field is valid.
+    fn check_fields(
+        &self,
+        owner     : &String,
+        path      : &Vec<String>,
+        declared  : &Vec<(String, Range, bool, TypeDescriptor)>,
+        fields    : &Vec<(String, Range, crate::parse::node::Expression)>,
+        values    : Vec<Value>,
+        defined   : &Range,
+        inference : &mut generics::Inference
+    ) -> Option<Vec<(String, ValueType)>> {
+        for ((name, _, _), value) in fields.iter().zip(&values) {
+            if let Some((_, _, _, typ)) = declared.iter().find(|(field, _, _, _)| field == name) {
+                inference.unify(&typ.resolve(false), &value.value_ref().type_of());
+            }
+        }
+        inference.record(&self.range);
+        let mut known = Some(Vec::new());
+        for (i, ((name, range, _), value)) in fields.iter().zip(values).enumerate() {
+            if let Some((_, first, _)) = fields[..i].iter().find(|(other, _, _)| other == name) {
+                push_error!(DuplicateSymbol, Always, {
+                    Some(first.clone()) => {"Field `{}` already given here.", name},
+                    Some(range.clone()) => {"Given again here."}
+                });
+                known = None;
+                continue;
+            }
+            let Some(field) = declared.iter().find(|(field, _, _, _)| field == name) else {
+                push_error!(UnknownSymbol, Always, {
+                    Some(range.clone())   => {"`{}` has no field `{}`.", owner, name},
+                    Some(defined.clone()) => {"Defined here."}
+                });
+                known = None;
+                continue;
+            };
+            access_field(path, range, field);
+            let expected = inference.apply(&field.3.resolve(false));
+            let found    = value.value_ref().type_of();
+            if (! expected.accepts(&found)) {
+                push_error!(InvalidTypeReceived, Always, {
+                    Some(value.range().clone()) => {"Expected `{}`, found `{}`.", expected, found},
+                    Some(field.1.clone())       => {"Field `{}` declared here.", name}
+                });
+                known = None;
+                continue;
+            }
+            if (! check_bounds(&value, &field.3)) {
+                known = None;
+                continue;
+            }
+            if let Some(known) = &mut known {
+                known.push((name.clone(), value.value()));
+            }
+        }
+        let missing = declared.iter()
+            .filter(|(field, _, _, _)| ! fields.iter().any(|(name, _, _)| name == field))
+            .map(|(field, _, _, _)| format!("`{}`", field))
+            .collect::<Vec<_>>();
+        if (! missing.is_empty()) {
+            push_error!(MissingStructField, Always, {
+                Some(self.range.clone()) => {"Missing field{} {}.", if (missing.len() != 1) {"s"} else {""}, missing.join(", ")},
+                Some(defined.clone())    => {"`{}` defined here.", owner}
+            });
+            known = None;
+        }
+        return known;
+    }
+
+}
+
+
+
+impl Expression {
+
+    /// Check a condition, which must be a `bool`.
+    /// Returns the value of the condition if it is known at check time.
+    fn check_condition(&self, scope : &mut Scope) -> Option<bool> {
+        let value = self.check_contents(scope);
+        return match (value.value_ref()) {
+            ValueType::Bool(constant) => *constant,
+            ValueType::Failed         => None,
+            value                     => {
+                push_error!(InvalidTypeReceived, Always, {
+                    Some(self.range.clone()) => {"Expected `bool`, found `{}`.", value.type_of()}
+                });
+                None
+            }
+        };
     }
 
 }
 
 
 
+impl Literal {
+
+    pub fn check_contents(&self, scope : &mut Scope) -> Value {
+        use LiteralType::*;
+        return Value::new(match (&self.lit) {
+
+            Int(int, suffix) => {
+                let typ = match (suffix.as_str()) {
+                    "u" => Type::UInt,
+                    "b" => Type::Byte,
+                    _   => Type::Int
+                };
+                let value = BigInt::from_str(int).ok().map_or_else(IntRange::any, IntRange::exact);
+                if (value.leaves(&typ.int_limits()).is_some()) {
+                    push_error!(ArithmeticOverflow, Always, {
+                        Some(self.range.clone()) => {"`{}` does not fit in {}.", int, int_type_name(&typ)}
+                    });
+                    typ.unknown_value()
+                } else {
+                    typ.int_value(value)
+                }
+            },
+
+            Float(int, dec, suffix) => {
+                let typ = if (suffix == "u") {Type::UFloat} else {Type::Float};
+                typ.float_value(BigFloat::parse(&format!("{}.{}", int, dec)))
+            },
+
+            Bool(value) => ValueType::Bool(Some(*value)),
+
+            String(_, value) | RawString(_, value) => ValueType::String(Some(value.clone())),
+
+            Char(_, value) => ValueType::Char(Some(*value)),
+
+            Identifier(name) => self.check_path(&vec![name.clone()], scope),
+
+            Path(path) => {
+                match (resolve_variant(scope, path, &self.range)) {
+                    Some(Ok((enum_path, _, (name, variant_range, fields)))) => {
+                        if (matches!(fields, VariantFields::Unit)) {
+                            // Nothing is given to infer the type arguments from, so any fit.
+                            let args = vec![Type::Failed; ProgramInfo::get().generics_of(&enum_path).len()];
+                            ValueType::Enum(enum_path, args, Some(name), Some(Vec::new()))
+                        } else {
+                            push_error!(InvalidTypeReceived, Always, {
+                                Some(self.range.clone()) => {"Variant `{}` holds values, but none were given.", name},
+                                Some(variant_range)      => {"Declared here."}
+                            });
+                            ValueType::Failed
+                        }
+                    },
+                    Some(Err(())) => ValueType::Failed,
+                    None          => self.check_path(path, scope)
+                }
+            }
+
+        }, self.range.clone());
+    }
+
+    /// Get the value of the symbol at the path.
+    fn check_path(&self, path : &Vec<String>, scope : &mut Scope) -> ValueType {
+        let Some(symbol) = scope.resolve_path(path, &self.range, true) else {
+            return ValueType::Failed;
+        };
+        ProgramInfo::get().add_reference(self.range.clone(), &path[path.len() - 1], symbol);
+        let kind = match (symbol.value().value_ref()) {
+            ValueType::StructType(_, _) => "a struct",
+            ValueType::EnumType(_, _)   => "an enum",
+            value                       => {return value.clone();}
+        };
+        push_error!(InvalidTypeReceived, Always, {
+            Some(self.range.clone())     => {"`{}` is {}, not a value.", path.join("::"), kind},
+            Some(symbol.range().clone()) => {"Defined here."}
+        });
+        return ValueType::Failed;
+    }
+
+}
+
+
+
+impl Pattern {
+
+    /// Check that the pattern can match a value of type `typ`,
+    /// and add the variables that it creates to `bindings`.
+    fn check_contents(&self, scope : &mut Scope, typ : &Type, bindings : &mut Vec<(String, Symbol)>) {
+        match (&self.pat) {
+
+            PatternType::Wildcard => {},
+
+            PatternType::Binding(name) => bind_pattern(name, &self.range, typ, bindings),
+
+            PatternType::Literal(lit) => {
+                let found = lit.check_contents(scope).value_ref().type_of();
+                if (! typ.accepts(&found)) {
+                    push_error!(InvalidTypeReceived, Always, {
+                        Some(self.range.clone()) => {"Expected `{}`, found `{}`.", typ, found}
+                    });
+                }
+            },
+
+            PatternType::Variant(path, path_range, fields) => {
+                let variant = resolve_variant(scope, path, path_range);
+                let Some(Ok((enum_path, _, (name, variant_range, declared)))) = variant else {
+                    if (variant.is_none()) {
+                        push_error!(UnknownSymbol, Always, {
+                            Some(path_range.clone()) => {"`{}` is not an enum variant.", path.join("::")}
+                        });
+                    }
+                    fields.check_contents(scope, &self.range, None, bindings);
+                    return;
+                };
+                // The type arguments come from the value being matched.
+                let args  = match (typ) {
+                    Type::Enum(path, args) if (*path == enum_path) => args.clone(),
+                    _                                              => vec![Type::Failed; ProgramInfo::get().generics_of(&enum_path).len()]
+                };
+                let found = Type::Enum(enum_path, args);
+                if (! typ.accepts(&found)) {
+                    push_error!(InvalidTypeReceived, Always, {
+                        Some(self.range.clone()) => {"Expected `{}`, found `{}`.", typ, found}
+                    });
+                }
+                fields.check_contents(scope, &self.range, Some((&name, &variant_range, &declared, &found)), bindings);
+            },
+
+            PatternType::Tuple(patterns) => {
+                let types = match (typ) {
+                    Type::Tuple(types) => types.clone(),
+                    Type::Failed       => vec![Type::Failed; patterns.len()],
+                    _                  => {
+                        push_error!(InvalidTypeReceived, Always, {
+                            Some(self.range.clone()) => {"Expected `{}`, found a tuple.", typ}
+                        });
+                        vec![Type::Failed; patterns.len()]
+                    }
+                };
+                check_element_patterns(scope, &self.range, patterns, &types, bindings);
+            },
+
+            PatternType::Array(patterns) => {
+                let types = match (typ) {
+                    Type::Array(element, length) => vec![(**element).clone(); *length],
+                    Type::Failed                 => vec![Type::Failed; patterns.len()],
+                    _                            => {
+                        push_error!(InvalidTypeReceived, Always, {
+                            Some(self.range.clone()) => {"Expected `{}`, found an array.{}", typ, if (matches!(typ, Type::List(_))) {" Lists can not be destructured, as their length is not known."} else {""}}
+                        });
+                        vec![Type::Failed; patterns.len()]
+                    }
+                };
+                check_element_patterns(scope, &self.range, patterns, &types, bindings);
+            }
+
+        }
+    }
+
+    /// The variant or `bool` value that this pattern matches, if it only matches one.
+    fn key(&self) -> Option<String> {
+        return match (&self.pat) {
+            PatternType::Variant(path, _, _)                                   => Some(path[path.len() - 1].clone()),
+            PatternType::Literal(Literal { lit : LiteralType::Bool(value), .. }) => Some(value.to_string()),
+            _                                                                  => None
+        };
+    }
+
+}
+
+impl VariantPatterns {
+
+    /// Check the patterns given to the fields of a variant.
+    /// If `declared` is `None`, the variant could not be found,
+    /// but the variables are still created so that they are not reported again.
+    /// Otherwise it holds the variant, and the enum type that it is matched as.
+    fn check_contents(&self, scope : &mut Scope, range : &Range, declared : Option<(&String, &Range, &VariantFields, &Type)>, bindings : &mut Vec<(String, Symbol)>) {
+        let Some((variant, variant_range, declared, owner)) = declared else {
+            match (self) {
+                VariantPatterns::Unit            => {},
+                VariantPatterns::Tuple(patterns) => patterns.iter().for_each(|pattern| {pattern.check_contents(scope, &Type::Failed, bindings);}),
+                VariantPatterns::Named(fields)   => {
+                    for (field, range, pattern) in fields {
+                        match (pattern) {
+                            Some(pattern) => {pattern.check_contents(scope, &Type::Failed, bindings);},
+                            None          => bind_pattern(field, range, &Type::Failed, bindings)
+                        }
+                    }
+                }
+            }
+            return;
+        };
+        match ((self, declared)) {
+
+            (VariantPatterns::Unit, VariantFields::Unit) => {},
+
+            (VariantPatterns::Tuple(patterns), VariantFields::Tuple(types)) => {
+                if (patterns.len() != types.len()) {
+                    push_error!(InvalidArgumentCount, Always, {
+                        Some(range.clone())         => {"Expected {} value{}, found {}.", types.len(), if (types.len() != 1) {"s"} else {""}, patterns.len()},
+                        Some(variant_range.clone()) => {"Declared here."}
+                    });
+                }
+                for (i, pattern) in patterns.iter().enumerate() {
+                    let typ = types.get(i).map_or(Type::Failed, |typ| generics::field_type(owner, typ));
+                    pattern.check_contents(scope, &typ, bindings);
+                }
+            },
+
+            (VariantPatterns::Named(fields), VariantFields::Named(declared)) => {
+                for (field, range, pattern) in fields {
+                    let typ = if let Some((_, declared_range, typ)) = declared.iter().find(|(other, _, _)| other == field) {
+                        let typ    = generics::field_type(owner, typ);
+                        let symbol = Symbol::new(Value::new(typ.unknown_value(), declared_range.clone()), false, true, declared_range.clone());
+                        ProgramInfo::get().add_reference(range.clone(), field, &symbol);
+                        typ
+                    } else {
+                        push_error!(UnknownSymbol, Always, {
+                            Some(range.clone())         => {"Variant `{}` has no field `{}`.", variant, field},
+                            Some(variant_range.clone()) => {"Declared here."}
+                        });
+                        Type::Failed
+                    };
+                    match (pattern) {
+                        Some(pattern) => pattern.check_contents(scope, &typ, bindings),
+                        None          => bind_pattern(field, range, &typ, bindings)
+                    }
+                }
+            },
+
+            (_, declared) => {
+                push_error!(InvalidTypeReceived, Always, {
+                    Some(range.clone()) => {"Variant `{}` {}.", variant, match (declared) {
+                        VariantFields::Unit     => "does not hold any values",
+                        VariantFields::Tuple(_) => "holds unnamed values",
+                        VariantFields::Named(_) => "holds named values"
+                    }},
+                    Some(variant_range.clone()) => {"Declared here."}
+                });
+                self.check_contents(scope, range, None, bindings);
+            }
+
+        }
+    }
+
+}
+
+/// Check the patterns given to the elements of a tuple or array.
+fn check_element_patterns(scope : &mut Scope, range : &Range, patterns : &Vec<Pattern>, types : &Vec<Type>, bindings : &mut Vec<(String, Symbol)>) {
+    if (patterns.len() != types.len()) {
+        push_error!(InvalidArgumentCount, Always, {
+            Some(range.clone()) => {"Expected {} value{}, found {}.", types.len(), if (types.len() != 1) {"s"} else {""}, patterns.len()}
+        });
+    }
+    for (i, pattern) in patterns.iter().enumerate() {
+        pattern.check_contents(scope, types.get(i).unwrap_or(&Type::Failed), bindings);
+    }
+}
+
+/// Add a variable created by a pattern.
+fn bind_pattern(name : &String, range : &Range, typ : &Type, bindings : &mut Vec<(String, Symbol)>) {
+    let symbol = Symbol::new(
+        Value::new(typ.unknown_value(), range.clone()),
+        false,
+        false,
+        range.clone()
+    );
+    ProgramInfo::get().add_reference(range.clone(), name, &symbol);
+    bindings.push((name.clone(), symbol));
+}
+
+
+
+impl TypeDescriptor {
+
+    /// Get the type as it was written.
+    pub fn name(&self) -> String {
+        use TypeDescriptorParts::*;
+        let name = match (&self.parts) {
+            BuiltIn(name)       => name.clone(),
+            Custom(path)        => path.join("::"),
+            Tuple(types)        => format!("({}{})",
+                types.iter().map(|typ| typ.name()).collect::<Vec<_>>().join(", "),
+                if (types.len() == 1) {","} else {""}
+            ),
+            List(typ)           => format!("list[{}]", typ.name()),
+            Array(typ, length)  => format!("[{}; {}]", typ.name(), length),
+            Function(args, ret) => format!("|{}|{}",
+                args.iter().map(|arg| arg.name()).collect::<Vec<_>>().join(", "),
+                ret.as_ref().map_or_else(String::new, |ret| format!(" -> {}", ret.name()))
+            ),
+            Generic(typ, args)  => format!("{}<{}>",
+                typ.name(),
+                args.iter().map(|arg| arg.name()).collect::<Vec<_>>().join(", ")
+            )
+        };
+        if (self.constr.is_empty()) {
+            return name;
+        }
+        let mut constr = self.constr.iter().collect::<Vec<_>>();
+        constr.sort_by_key(|(_, lit)| lit.range.1);
+        return format!("{}<{}>", name, constr.iter()
+            .map(|(constr, lit)| match (&lit.lit) {
+                LiteralType::Int(int, _) => format!("{} = {}", constr, int),
+                _                        => format!("{} = _", constr)
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+        );
+    }
+
+    /// Get the type that this descriptor refers to.
+    /// Builtin names are checked first, then type parameters, then the path is
+    /// looked up from the root scope of the module it is in.
+    /// If `report` is set, push an error if it could not be found,
+    /// or if its constraints are not valid, and store the type for later uses.
+    /// Otherwise, the type stored by the `expand_types` pass is used if there is one.
+    pub fn resolve(&self, report : bool) -> Type {
+        if (! report) {
+            if let Some(typ) = ProgramInfo::get().type_at(&self.range) {
+                return typ.clone();
+            }
+        }
+        let typ = self.resolve_with(&Vec::new(), &self.range, report);
+        if (report) {
+            self.check_constraints(&typ);
+            ProgramInfo::get().add_type(self.range.clone(), typ.clone());
+        }
+        return typ;
+    }
+
+    /// Get the values that an integer of this type is allowed to have,
+    /// from its `min` and `max` constraints.
+    pub fn bounds(&self) -> IntRange {
+        let get = |name : &str| match (self.constr.get(name).map(|lit| &lit.lit)) {
+            Some(LiteralType::Int(int, _)) => BigInt::from_str(int).ok(),
+            _                              => None
+        };
+        return IntRange {
+            min : get("min"),
+            max : get("max")
+        };
+    }
+
+    /// Get a value of this type, without a known constant.
+    /// Integers can have any value within the bounds of the type.
+    pub fn unknown_value(&self) -> ValueType {
+        return self.value_of(&self.resolve(false));
+    }
+
+    /// Get a value of `typ`, which this type resolved to once its type parameters were replaced,
+    /// without a known constant.
+    pub fn value_of(&self, typ : &Type) -> ValueType {
+        if (typ.is_integer()) {
+            return typ.int_value(self.bounds());
+        }
+        return typ.unknown_value();
+    }
+
+    /// Report constraints which are not `min` or `max`, or which are not integers.
+    fn check_constraints(&self, typ : &Type) {
+        if (self.constr.is_empty() || matches!(typ, Type::Failed)) {
+            return;
+        }
+        if (! typ.is_integer()) {
+            push_error!(InvalidTypeReceived, Always, {
+                Some(self.range.clone()) => {"`{}` can not be constrained.", typ},
+                None                     => {"Only integer types have a `min` and `max`."}
+            });
+            return;
+        }
+        for (name, lit) in &self.constr {
+            if (name != "min" && name != "max") {
+                push_error!(UnknownSymbol, Always, {
+                    Some(lit.range.clone()) => {"`{}` has no constraint `{}`.", typ, name},
+                    None                    => {"Only `min` and `max` are supported."}
+                });
+            } else if (! matches!(lit.lit, LiteralType::Int(_, _))) {
+                push_error!(InvalidTypeReceived, Always, {
+                    Some(lit.range.clone()) => {"Expected an integer for `{}`.", name}
+                });
+            }
+        }
+        let bounds = self.bounds();
+        if let (Some(min), Some(max)) = (&bounds.min, &bounds.max) {
+            if (min > max) {
+                push_error!(Bound_Broken, Always, {
+                    Some(self.range.clone()) => {"Minimum `{}` is greater than maximum `{}`.", min, max},
+                    None                     => {"No value can be within these bounds."}
+                });
+            }
+        }
+    }
+
+    /// Get the type that this descriptor refers to, given the type arguments `args`.
+    /// `range` covers the type and its arguments.
+    fn resolve_with(&self, args : &Vec<TypeDescriptor>, range : &Range, report : bool) -> Type {
+        use TypeDescriptorParts::*;
+        let path = match (&self.parts) {
+            BuiltIn(name) => {
+                if let Some(typ) = Type::builtin(name) {
+                    self.check_no_args(args, range, report);
+                    return typ;
+                }
+                if (Type::UNSUPPORTED_BUILTINS.contains(&name.as_str())) {
+                    if (report) {
+                        push_error!(UnknownSymbol, Always, {
+                            Some(self.range.clone()) => {"Builtin type `{}` can not be used here yet.", name}
+                        });
+                    }
+                    return Type::Failed;
+                }
+                // Type parameters hide types with the same name.
+                if let Some((owner, generic)) = ProgramInfo::get().generic_at(&self.range, name) {
+                    let path = [owner.as_slice(), &[name.clone()]].concat();
+                    if (report) {
+                        let symbol = Symbol::new(Value::new(ValueType::Param(path.clone()), generic.range.clone()), false, false, generic.range.clone());
+                        ProgramInfo::get().add_reference(self.range.clone(), name, &symbol);
+                    }
+                    self.check_no_args(args, range, report);
+                    return Type::Param(path);
+                }
+                vec![name.clone()]
+            },
+            Custom(path) => path.clone(),
+            Generic(typ, args) => {return typ.resolve_with(args, &self.range, report);},
+            Tuple(types)       => {return Type::Tuple(types.iter().map(|typ| typ.resolve(report)).collect());},
+            List(typ)          => {return Type::List(Box::new(typ.resolve(report)));},
+            Array(typ, length) => {return Type::Array(Box::new(typ.resolve(report)), *length);},
+            Function(args, ret) => {
+                return Type::Function(
+                    args.iter().map(|arg| arg.resolve(report)).collect(),
+                    Box::new(ret.as_ref().map_or(Type::Void, |ret| ret.resolve(report)))
+                );
+            }
+        };
+        let Some(scope)  = ProgramInfo::get().scope_of(&self.range.0) else {
+            return Type::Failed;
+        };
+        let Some(symbol) = scope.resolve_path(&path, &self.range, report) else {
+            return Type::Failed;
+        };
+        if (report) {
+            ProgramInfo::get().add_reference(self.range.clone(), &path[path.len() - 1], symbol);
+        }
+        let (ValueType::StructType(path, _) | ValueType::EnumType(path, _)) = symbol.value().value_ref() else {
+            if (report && ! matches!(symbol.value().value_ref(), ValueType::Failed)) {
+                push_error!(UnknownSymbol, Always, {
+                    Some(self.range.clone())     => {"`{}` is not a type.", path.join("::")},
+                    Some(symbol.range().clone()) => {"Defined here."}
+                });
+            }
+            return Type::Failed;
+        };
+        let generics  = ProgramInfo::get().generics_of(path);
+        let mut types = args.iter().map(|arg| arg.resolve(report)).collect::<Vec<_>>();
+        if (types.len() != generics.len()) {
+            if (report) {
+                push_error!(InvalidArgumentCount, Always, {
+                    Some(range.clone())          => {"Expected {} type argument{}, found {}.", generics.len(), if (generics.len() != 1) {"s"} else {""}, types.len()},
+                    Some(symbol.range().clone()) => {"`{}` defined here.", path.join("::")}
+                });
+            }
+            types.resize(generics.len(), Type::Failed);
+        }
+        if (report) {
+            generics::record_instance(path, types.clone(), range);
+        }
+        return match (symbol.value().value_ref()) {
+            ValueType::StructType(_, _) => Type::Struct(path.clone(), types),
+            _                           => Type::Enum(path.clone(), types)
+        };
+    }
+
+    /// Report type arguments given to a type which does not take any.
+    fn check_no_args(&self, args : &Vec<TypeDescriptor>, range : &Range, report : bool) {
+        if (report && ! args.is_empty()) {
+            push_error!(InvalidArgumentCount, Always, {
+                Some(range.clone()) => {"`{}` does not take type arguments.", self.name()}
+            });
+        }
+    }
+}
+
+
+
 impl Block {
 
-    pub fn check_contents(&self, scope : &mut Scope, name : String) -> Value {
+    pub fn check_contents(&self, scope : &mut Scope, name : String, init : Vec<(String, Symbol)>) -> Value {
         let mut subscope = scope.enter(name);
+        for (name, symbol) in init {
+            subscope.init_symbol(name, symbol);
+        }
         let mut ret      = ValueType::Void;
-        for stmt in &self.stmts {
+        // The first statement which never finishes, if any.
+        let mut diverged = None;
+        for (i, stmt) in self.stmts.iter().enumerate() {
             ret = stmt.check_contents(&mut subscope).value();
+            if (diverged.is_none() && matches!(ret, ValueType::Never)) {
+                diverged = Some(i);
+            }
+        }
+        if let Some(i) = diverged {
+            if (i + 1 < self.stmts.len()) {
+                let first = &self.stmts[i + 1].range;
+                let last  = &self.stmts[self.stmts.len() - 1].range;
+                push_warn!(UnreachableCode, Always, {
+                    Some(Range(first.0.clone(), first.1, last.2)) => {"This is never run."},
+                    Some(self.stmts[i].range.clone())             => {"Because this never finishes."}
+                });
+            }
+            return Value::new(ValueType::Never, self.range.clone());
+        }
+        if (! self.retlast) {
+            return Value::new(ValueType::Void, self.range.clone());
         }
-        return Value::new(if (self.retlast) {
-            ret
-        } else {ValueType::Void}, self.range.clone());
+        // Variables declared in the block end with it.
+        let last = self.stmts.last().map_or(self.range.clone(), |stmt| stmt.range.clone());
+        return Value::new(check_escape(Value::new(ret, last), subscope.depth()).value(), self.range.clone());
     }
 
 }
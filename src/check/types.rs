@@ -1,10 +1,25 @@
-use crate::parse::node::{
-    Range,
-    TypeDescriptor,
-    Block
+use std::fmt::{
+    self,
+    Display,
+    Formatter
 };
 
+use num_bigint::BigInt;
+use num_bigfloat::BigFloat;
 
+use crate::{
+    parse::node::{
+        Range,
+        TypeDescriptor,
+        Block,
+        VariantFields,
+        Method
+    },
+    notes::NoteOccurance
+};
+
+
+#[derive(Clone)]
 pub(crate) struct Value {
     value : ValueType,
     range : Range
@@ -23,6 +38,10 @@ impl Value {
         return self.value;
     }
 
+    pub(crate) fn value_ref(&self) -> &ValueType {
+        return &self.value;
+    }
+
     pub(crate) fn range(&self) -> &Range {
         return &self.range;
     }
@@ -30,13 +49,558 @@ impl Value {
 }
 
 
+/// A value found while checking.
+/// Constant values are stored if they are known at check time.
+/// Integers store the range of values that they can have instead.
 #[allow(unused)]
+#[derive(Clone)]
 pub(crate) enum ValueType {
     Failed,
 
     Void,
+    /// Produced by code which never finishes, such as `return`.
+    Never,
+
+    Byte(IntRange),
+    Int(IntRange),
+    UInt(IntRange),
+    Float(Option<BigFloat>),
+    UFloat(Option<BigFloat>),
+    Bool(Option<bool>),
+    Char(Option<char>),
+    String(Option<String>),
 
     ModuleAccess(Vec<String>),
-    Function(String, Vec<(String, TypeDescriptor)>, Option<TypeDescriptor>, Block)
+    /// Created by `use`. Points to another symbol, which is found when accessed.
+    Alias(Vec<String>),
+    Function(String, Vec<(String, Range, TypeDescriptor)>, Option<TypeDescriptor>, Block),
+    /// A function value, such as a closure or an argument of a function type.
+    /// Holds its argument types, its return type, and the mutable variables
+    /// declared outside of it that it captures (Name, Declared Range, Scope Depth).
+    Closure(Vec<Type>, Box<Type>, Vec<(String, Range, usize)>),
+    /// A struct declaration. Holds its path and its fields (Name, Name Range, Public, Type).
+    StructType(Vec<String>, Vec<(String, Range, bool, TypeDescriptor)>),
+    /// An instance of the struct at the path, with its type arguments.
+    /// Field values are stored if it was built at check time.
+    Struct(Vec<String>, Vec<Type>, Option<Vec<(String, ValueType)>>),
+    /// An enum declaration. Holds its path and its variants (Name, Name Range, Fields).
+    EnumType(Vec<String>, Vec<(String, Range, VariantFields)>),
+    /// An instance of the enum at the path, with its type arguments. The variant is stored if it is known.
+    /// Field values are stored if it was built at check time.
+    Enum(Vec<String>, Vec<Type>, Option<String>, Option<Vec<ValueType>>),
+    /// A trait declaration. Holds its path and the methods that it requires.
+    TraitType(Vec<String>, Vec<Method>),
+    /// An array, holding its element type and length. Elements are stored if they are known.
+    Array(Type, usize, Option<Vec<ValueType>>),
+    /// A tuple, holding the value of each element.
+    Tuple(Vec<ValueType>),
+    /// A list, holding its element type. Elements are stored if they are known.
+    List(Type, Option<Vec<ValueType>>),
+    /// A value of the type parameter at the path, inside of the declaration that it belongs to.
+    Param(Vec<String>)
+
+}
+
+impl ValueType {
+
+    /// Get the constant of an integer value.
+    /// Returns `None` if this is not an integer.
+    pub(crate) fn int_constant(&self) -> Option<Option<&BigInt>> {
+        return self.int_range().map(|range| range.constant());
+    }
+
+    /// Get the range of values of an integer.
+    /// Returns `None` if this is not an integer.
+    pub(crate) fn int_range(&self) -> Option<&IntRange> {
+        use ValueType::*;
+        return match (self) {
+            Byte(range) | Int(range) | UInt(range) => Some(range),
+            _                                      => None
+        };
+    }
+
+    /// Get the type of this value.
+    pub(crate) fn type_of(&self) -> Type {
+        use ValueType::*;
+        return match (self) {
+            Failed                    => Type::Failed,
+            Void                      => Type::Void,
+            Never                     => Type::Never,
+            Byte(_)                   => Type::Byte,
+            Int(_)                    => Type::Int,
+            UInt(_)                   => Type::UInt,
+            Float(_)                  => Type::Float,
+            UFloat(_)                 => Type::UFloat,
+            Bool(_)                   => Type::Bool,
+            Char(_)                   => Type::Char,
+            String(_)                 => Type::String,
+            ModuleAccess(_)           => Type::Module,
+            Alias(_)                  => Type::Failed,
+            Function(_, args, ret, _) => Type::Function(
+                args.iter().map(|(_, _, typ)| typ.resolve(false)).collect(),
+                Box::new(ret.as_ref().map_or(Type::Void, |ret| ret.resolve(false)))
+            ),
+            Closure(args, ret, _)     => Type::Function(args.clone(), ret.clone()),
+            StructType(_, _)          => Type::Failed,
+            Struct(path, args, _)     => Type::Struct(path.clone(), args.clone()),
+            EnumType(_, _)            => Type::Failed,
+            Enum(path, args, _, _)    => Type::Enum(path.clone(), args.clone()),
+            TraitType(_, _)           => Type::Failed,
+            Array(typ, length, _)     => Type::Array(Box::new(typ.clone()), *length),
+            Tuple(values)             => Type::Tuple(values.iter().map(|value| value.type_of()).collect()),
+            List(typ, _)              => Type::List(Box::new(typ.clone())),
+            Param(path)               => Type::Param(path.clone())
+        };
+    }
+
+}
+
+
+/// A resolved type.
+#[derive(Clone, PartialEq, Debug)]
+pub(crate) enum Type {
+    /// Checking failed somewhere else. Matches every other type
+    /// so that the error isn't reported multiple times.
+    Failed,
+
+    Void,
+    /// The type of code which never finishes. Accepted everywhere.
+    Never,
+    Byte,
+    Int,
+    UInt,
+    Float,
+    UFloat,
+    Bool,
+    Char,
+    String,
+
+    Module,
+    /// A function which takes the argument types and returns the last type.
+    Function(Vec<Type>, Box<Type>),
+    /// An instance of the struct declared at the path, with its type arguments.
+    Struct(Vec<String>, Vec<Type>),
+    /// An instance of the enum declared at the path, with its type arguments.
+    Enum(Vec<String>, Vec<Type>),
+    /// A fixed number of elements of the same type.
+    Array(Box<Type>, usize),
+    /// A fixed number of elements of any type.
+    Tuple(Vec<Type>),
+    /// Any number of elements of the same type.
+    List(Box<Type>),
+    /// A type parameter of a generic declaration, at the path of the
+    /// declaration followed by its name. Only equal to itself.
+    Param(Vec<String>)
+
+}
+
+impl Type {
+
+    /// Builtins that exist, but can not be used as a type by name yet.
+    pub(crate) const UNSUPPORTED_BUILTINS : [&'static str; 3] = ["ptr", "struct", "trait"];
+
+    /// Get the builtin type with the given name, if any.
+    pub(crate) fn builtin(name : &str) -> Option<Self> {
+        return Some(match (name) {
+            "void"   => Self::Void,
+            "byte"   => Self::Byte,
+            "int"    => Self::Int,
+            "uint"   => Self::UInt,
+            "float"  => Self::Float,
+            "ufloat" => Self::UFloat,
+            "bool"   => Self::Bool,
+            "char"   => Self::Char,
+            "string" => Self::String,
+            _        => {return None;}
+        });
+    }
+
+    /// Whether this is one of the number types.
+    pub(crate) fn is_numeric(&self) -> bool {
+        return matches!(self, Self::Byte | Self::Int | Self::UInt | Self::Float | Self::UFloat);
+    }
+
+    /// Whether this is one of the integer types.
+    pub(crate) fn is_integer(&self) -> bool {
+        return matches!(self, Self::Byte | Self::Int | Self::UInt);
+    }
+
+    /// Whether a value of type `other` can be used where this type is expected.
+    pub(crate) fn accepts(&self, other : &Type) -> bool {
+        return match ((self, other)) {
+            (Self::Function(args, ret), Self::Function(other_args, other_ret)) => {
+                args.len() == other_args.len()
+                    && args.iter().zip(other_args).all(|(arg, other_arg)| other_arg.accepts(arg))
+                    && ret.accepts(other_ret)
+            },
+            (Self::Array(typ, length), Self::Array(other_typ, other_length)) => length == other_length && typ.accepts(other_typ),
+            (Self::List(typ), Self::List(other_typ))                         => typ.accepts(other_typ),
+            (Self::Tuple(types), Self::Tuple(other_types))                   => {
+                types.len() == other_types.len() && types.iter().zip(other_types).all(|(typ, other_typ)| typ.accepts(other_typ))
+            },
+            (Self::Struct(path, args), Self::Struct(other_path, other_args))
+                | (Self::Enum(path, args), Self::Enum(other_path, other_args)) => {
+                path == other_path && args.len() == other_args.len() && args.iter().zip(other_args).all(|(arg, other_arg)| arg.accepts(other_arg))
+            },
+            _ => matches!(self, Self::Failed) || matches!(other, Self::Failed | Self::Never) || self == other
+        };
+    }
+
+    /// Get a value of this type, without a known constant.
+    pub(crate) fn unknown_value(&self) -> ValueType {
+        return match (self) {
+            Self::Void                => ValueType::Void,
+            Self::Never               => ValueType::Never,
+            Self::Byte                => ValueType::Byte(IntRange::any()),
+            Self::Int                 => ValueType::Int(IntRange::any()),
+            Self::UInt                => ValueType::UInt(IntRange::any()),
+            Self::Float               => ValueType::Float(None),
+            Self::UFloat              => ValueType::UFloat(None),
+            Self::Bool                => ValueType::Bool(None),
+            Self::Char                => ValueType::Char(None),
+            Self::String              => ValueType::String(None),
+            Self::Struct(path, args)  => ValueType::Struct(path.clone(), args.clone(), None),
+            Self::Enum(path, args)    => ValueType::Enum(path.clone(), args.clone(), None, None),
+            Self::Param(path)         => ValueType::Param(path.clone()),
+            Self::Function(args, ret) => ValueType::Closure(args.clone(), ret.clone(), Vec::new()),
+            Self::Array(typ, length)  => ValueType::Array((**typ).clone(), *length, None),
+            Self::Tuple(types)        => ValueType::Tuple(types.iter().map(|typ| typ.unknown_value()).collect()),
+            Self::List(typ)           => ValueType::List((**typ).clone(), None),
+            _                         => ValueType::Failed
+        };
+    }
+
+    /// Replace each type parameter at one of `params` with the type argument at the same index.
+    /// Parameters without an argument become `Failed`, so that they match anything.
+    pub(crate) fn substitute(&self, params : &[Vec<String>], args : &[Type]) -> Self {
+        let all = |types : &Vec<Type>| types.iter().map(|typ| typ.substitute(params, args)).collect();
+        return match (self) {
+            Self::Param(path)          => match (params.iter().position(|param| param == path)) {
+                Some(i) => args.get(i).cloned().unwrap_or(Self::Failed),
+                None    => self.clone()
+            },
+            Self::Struct(path, types)  => Self::Struct(path.clone(), all(types)),
+            Self::Enum(path, types)    => Self::Enum(path.clone(), all(types)),
+            Self::Function(types, ret) => Self::Function(all(types), Box::new(ret.substitute(params, args))),
+            Self::Array(typ, length)   => Self::Array(Box::new(typ.substitute(params, args)), *length),
+            Self::Tuple(types)         => Self::Tuple(all(types)),
+            Self::List(typ)            => Self::List(Box::new(typ.substitute(params, args))),
+            _                          => self.clone()
+        };
+    }
+
+    /// Whether this type contains `Failed` anywhere, meaning that some part of it is not known.
+    pub(crate) fn is_partial(&self) -> bool {
+        return match (self) {
+            Self::Failed                                    => true,
+            Self::Struct(_, types) | Self::Enum(_, types)
+                | Self::Tuple(types)                        => types.iter().any(|typ| typ.is_partial()),
+            Self::Function(types, ret)                      => types.iter().any(|typ| typ.is_partial()) || ret.is_partial(),
+            Self::Array(typ, _) | Self::List(typ)           => typ.is_partial(),
+            _                                               => false
+        };
+    }
+
+    /// Get a value of this integer type, which is somewhere in `range`.
+    pub(crate) fn int_value(&self, range : IntRange) -> ValueType {
+        return match (self) {
+            Self::Byte => ValueType::Byte(range),
+            Self::Int  => ValueType::Int(range),
+            Self::UInt => ValueType::UInt(range),
+            _          => ValueType::Failed
+        };
+    }
+
+    /// Get the values that an integer of this type can hold while running.
+    pub(crate) fn int_limits(&self) -> IntRange {
+        let (min, max) = match (self) {
+            Self::Byte => (BigInt::from(u8::MIN), BigInt::from(u8::MAX)),
+            Self::Int  => (BigInt::from(i64::MIN), BigInt::from(i64::MAX)),
+            Self::UInt => (BigInt::from(u64::MIN), BigInt::from(u64::MAX)),
+            _          => return IntRange::any()
+        };
+        return IntRange {
+            min : Some(min),
+            max : Some(max)
+        };
+    }
+
+    /// Get a value of this float type.
+    pub(crate) fn float_value(&self, value : Option<BigFloat>) -> ValueType {
+        return match (self) {
+            Self::Float  => ValueType::Float(value),
+            Self::UFloat => ValueType::UFloat(value),
+            _            => ValueType::Failed
+        };
+    }
+
+}
+
+impl Display for Type {
+
+    fn fmt(&self, f : &mut Formatter) -> fmt::Result {
+        let list = |types : &Vec<Type>| types.iter()
+            .map(|typ| typ.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        return match (self) {
+            Self::Failed                => write!(f, "?"),
+            Self::Void                  => write!(f, "void"),
+            Self::Never                 => write!(f, "never"),
+            Self::Byte                  => write!(f, "byte"),
+            Self::Int                   => write!(f, "int"),
+            Self::UInt                  => write!(f, "uint"),
+            Self::Float                 => write!(f, "float"),
+            Self::UFloat                => write!(f, "ufloat"),
+            Self::Bool                  => write!(f, "bool"),
+            Self::Char                  => write!(f, "char"),
+            Self::String                => write!(f, "string"),
+            Self::Module                => write!(f, "mod"),
+            Self::Struct(path, args)
+                | Self::Enum(path, args) => {
+                write!(f, "{}", path.join("::"))?;
+                if (args.is_empty()) {Ok(())} else {write!(f, "<{}>", list(args))}
+            },
+            Self::Param(path)           => write!(f, "{}", path[path.len() - 1]),
+            Self::Array(typ, length)    => write!(f, "[{}; {}]", typ, length),
+            Self::Tuple(types)          => write!(f, "({}{})", list(types), if (types.len() == 1) {","} else {""}),
+            Self::List(typ)             => write!(f, "list[{}]", typ),
+            Self::Function(args, ret)   => {
+                write!(f, "|{}|", list(args))?;
+                if (matches!(**ret, Self::Void)) {Ok(())} else {write!(f, " -> {}", ret)}
+            }
+        };
+    }
+
+}
+
+
+/// The values that an integer can have, found while checking.
+/// An end which is `None` is unbounded. Constants have both ends equal.
+#[derive(Clone, PartialEq, Debug)]
+pub(crate) struct IntRange {
+    pub(crate) min : Option<BigInt>,
+    pub(crate) max : Option<BigInt>
+}
+
+impl IntRange {
+
+    /// Any integer.
+    pub(crate) fn any() -> Self {
+        return Self {
+            min : None,
+            max : None
+        };
+    }
+
+    /// Exactly `value`.
+    pub(crate) fn exact(value : BigInt) -> Self {
+        return Self {
+            min : Some(value.clone()),
+            max : Some(value)
+        };
+    }
+
+    /// Get the value, if only one is in the range.
+    pub(crate) fn constant(&self) -> Option<&BigInt> {
+        return match ((&self.min, &self.max)) {
+            (Some(min), Some(max)) if (min == max) => Some(min),
+            _                                      => None
+        };
+    }
+
+    /// Get the range of the result of an arithmetic operator, applied to any values in `self` and `other`.
+    /// Operators that ranges are not tracked through give any integer.
+    pub(crate) fn apply(&self, op : &str, other : &Self) -> Self {
+        let both = |l : &Option<BigInt>, r : &Option<BigInt>, f : fn(&BigInt, &BigInt) -> BigInt| l.as_ref().zip(r.as_ref()).map(|(l, r)| f(l, r));
+        return match (op) {
+            "+" => Self {
+                min : both(&self.min, &other.min, |l, r| l + r),
+                max : both(&self.max, &other.max, |l, r| l + r)
+            },
+            "-" => Self {
+                min : both(&self.min, &other.max, |l, r| l - r),
+                max : both(&self.max, &other.min, |l, r| l - r)
+            },
+            "*" => {
+                // Without every end, the sign of the result is not known.
+                let (Some(l_min), Some(l_max), Some(r_min), Some(r_max)) = (&self.min, &self.max, &other.min, &other.max) else {
+                    return Self::any();
+                };
+                let products = [l_min * r_min, l_min * r_max, l_max * r_min, l_max * r_max];
+                Self {
+                    min : products.iter().min().cloned(),
+                    max : products.iter().max().cloned()
+                }
+            },
+            // Division truncates, so it keeps the order of values when the divisor is positive.
+            "/" => match (other.constant()) {
+                Some(r) if (*r > BigInt::from(0)) => Self {
+                    min : self.min.as_ref().map(|l| l / r),
+                    max : self.max.as_ref().map(|l| l / r)
+                },
+                _ => Self::any()
+            },
+            // The remainder is smaller than the divisor, and has the sign of the dividend.
+            "%" => match ((&other.min, &other.max)) {
+                (Some(r_min), Some(r_max)) if (*r_min > BigInt::from(0)) => {
+                    let largest = r_max - 1;
+                    let zero    = BigInt::from(0);
+                    Self {
+                        min : if (self.min.as_ref().is_some_and(|min| *min >= zero)) {Some(zero.clone())} else {Some(-&largest)},
+                        max : if (self.max.as_ref().is_some_and(|max| *max <= zero)) {Some(zero)} else {Some(largest)}
+                    }
+                },
+                _ => Self::any()
+            },
+            // Without a sign bit, the result has no bits that a non-negative operand does not have.
+            "&" => {
+                let non_negative = [self, other].into_iter()
+                    .filter(|range| range.min.as_ref().is_some_and(|min| *min >= BigInt::from(0)))
+                    .collect::<Vec<_>>();
+                if (non_negative.is_empty()) {
+                    return Self::any();
+                }
+                Self {
+                    min : Some(BigInt::from(0)),
+                    max : non_negative.iter().filter_map(|range| range.max.clone()).min()
+                }
+            },
+            // The result of non-negative operands has every bit of both, and no higher bits than either.
+            "|" => {
+                let (Some(l_min), Some(r_min)) = (&self.min, &other.min) else {
+                    return Self::any();
+                };
+                if (*l_min < BigInt::from(0) || *r_min < BigInt::from(0)) {
+                    return Self::any();
+                }
+                Self {
+                    min : Some(l_min.max(r_min).clone()),
+                    max : both(&self.max, &other.max, |l, r| (BigInt::from(1) << l.max(r).bits()) - 1)
+                }
+            },
+            // Shifting by a constant multiplies or divides by a power of two, which keeps the order of values.
+            // Bits shifted past the end of the type are not followed.
+            "<<" | ">>" => match (other.constant().and_then(|r| usize::try_from(r).ok())) {
+                Some(r) => {
+                    let shift = |value : &Option<BigInt>| value.as_ref().map(|value| if (op == "<<") {value << r} else {value >> r});
+                    Self {
+                        min : shift(&self.min),
+                        max : shift(&self.max)
+                    }
+                },
+                None => Self::any()
+            },
+            _ => Self::any()
+        };
+    }
+
+    /// Get the range of the negation of any value in this range.
+    pub(crate) fn negate(&self) -> Self {
+        return Self {
+            min : self.max.as_ref().map(|max| -max),
+            max : self.min.as_ref().map(|min| -min)
+        };
+    }
+
+    /// Find out how often a value in this range is outside of `bounds`.
+    /// Returns `None` if it never is.
+    pub(crate) fn leaves(&self, bounds : &Self) -> Option<NoteOccurance> {
+        let below = |value : &Option<BigInt>| bounds.min.as_ref().is_some_and(|min| value.as_ref().map_or(true, |value| value < min));
+        let above = |value : &Option<BigInt>| bounds.max.as_ref().is_some_and(|max| value.as_ref().map_or(true, |value| value > max));
+        // `None` is unbounded, so an unbounded end is never entirely outside.
+        let outside = self.max.as_ref().is_some_and(|_| below(&self.max)) || self.min.as_ref().is_some_and(|_| above(&self.min));
+        return if (outside) {
+            Some(NoteOccurance::Always)
+        } else if (below(&self.min) || above(&self.max)) {
+            Some(NoteOccurance::Sometimes)
+        } else {
+            None
+        };
+    }
+
+}
+
+impl Display for IntRange {
+
+    fn fmt(&self, f : &mut Formatter) -> fmt::Result {
+        if let Some(value) = self.constant() {
+            return write!(f, "{}", value);
+        }
+        if let Some(min) = &self.min {
+            write!(f, "{}", min)?;
+        }
+        write!(f, "..")?;
+        if let Some(max) = &self.max {
+            write!(f, "={}", max)?;
+        }
+        return Ok(());
+    }
+
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(min : i64, max : i64) -> IntRange {
+        return IntRange {
+            min : Some(BigInt::from(min)),
+            max : Some(BigInt::from(max))
+        };
+    }
+
+    fn exact(value : i64) -> IntRange {
+        return IntRange::exact(BigInt::from(value));
+    }
+
+    #[test]
+    fn remainder() {
+        assert_eq!(range(0, 100).apply("%", &exact(10)), range(0, 9));
+        assert_eq!(range(-100, 0).apply("%", &exact(10)), range(-9, 0));
+        assert_eq!(range(-100, 100).apply("%", &exact(10)), range(-9, 9));
+        assert_eq!(IntRange::any().apply("%", &range(1, 4)), range(-3, 3));
+        assert_eq!(range(0, 100).apply("%", &range(-1, 4)), IntRange::any());
+    }
+
+    #[test]
+    fn and() {
+        assert_eq!(IntRange::any().apply("&", &exact(15)), range(0, 15));
+        assert_eq!(range(0, 7).apply("&", &range(0, 100)), range(0, 7));
+        assert_eq!(range(-5, 5).apply("&", &range(-5, 5)), IntRange::any());
+        assert_eq!(
+            range(0, 7).apply("&", &IntRange { min : Some(BigInt::from(0)), max : None }),
+            range(0, 7)
+        );
+    }
+
+    #[test]
+    fn or() {
+        assert_eq!(range(0, 5).apply("|", &range(2, 8)), range(2, 15));
+        assert_eq!(exact(0).apply("|", &exact(0)), exact(0));
+        assert_eq!(range(-1, 5).apply("|", &range(0, 5)), IntRange::any());
+    }
+
+    #[test]
+    fn shift_right() {
+        assert_eq!(range(0, 255).apply(">>", &exact(4)), range(0, 15));
+        assert_eq!(range(-16, 16).apply(">>", &exact(2)), range(-4, 4));
+        assert_eq!(range(0, 255).apply(">>", &range(0, 4)), IntRange::any());
+    }
+
+    #[test]
+    fn shift_left() {
+        assert_eq!(range(0, 15).apply("<<", &exact(4)), range(0, 240));
+        assert_eq!(range(-3, 3).apply("<<", &exact(1)), range(-6, 6));
+        assert_eq!(range(0, 15).apply("<<", &exact(-1)), IntRange::any());
+    }
+
+    #[test]
+    fn leaves() {
+        assert!(matches!(range(0, 5).leaves(&range(0, 10)), None));
+        assert!(matches!(range(5, 15).leaves(&range(0, 10)), Some(NoteOccurance::Sometimes)));
+        assert!(matches!(range(11, 15).leaves(&range(0, 10)), Some(NoteOccurance::Always)));
+    }
 
 }
@@ -1,4 +1,7 @@
-use std::process::exit;
+use std::{
+    fs::write,
+    process::exit
+};
 
 use clap::{
     Parser,
@@ -8,16 +11,31 @@ use relative_path::RelativePathBuf;
 
 use crate::{
     notes::{
+        MessageFormat,
         explain,
+        dump,
+        is_json,
+        set_message_format,
         push_error
     },
     parse::{
+        node::Range,
         get_all_modules,
+        module_path,
+        source,
         config
     },
     reset,
     scope::ProgramInfo,
-    helper::AbsolutePathBuf
+    run::{
+        self,
+        value::RunValue
+    },
+    lsp,
+    helper::{
+        AbsolutePathBuf,
+        diff_lines
+    }
 };
 
 
@@ -56,7 +74,10 @@ enum Command {
     Check {
         /// The path containing the entry script (main.vsv).
         /// If none is given, it will use the current working directory.
-        path : Option<RelativePathBuf>
+        path           : Option<RelativePathBuf>,
+        /// How warnings and errors are printed.
+        #[arg(long, value_enum, default_value = "human")]
+        message_format : MessageFormat
     },
 
     /// Check if the program can be compiled,
@@ -64,17 +85,47 @@ enum Command {
     Build {
         /// The path containing the entry script (main.vsv).
         /// If none is given, it will use the current working directory.
-        path : Option<RelativePathBuf>
+        path           : Option<RelativePathBuf>,
+        /// How warnings and errors are printed.
+        #[arg(long, value_enum, default_value = "human")]
+        message_format : MessageFormat
     },
 
     /// Check if the program can be compiled,
     /// build it, then run it.
     Run {
+        /// The path containing the entry script (main.vsv).
+        /// If none is given, it will use the current working directory.
+        path           : Option<RelativePathBuf>,
+        /// How warnings and errors are printed.
+        #[arg(long, value_enum, default_value = "human")]
+        message_format : MessageFormat
+    },
+
+    /// Format the source code of every module.
+    Fmt {
+        /// The path containing the entry script (main.vsv).
+        /// If none is given, it will use the current working directory.
+        path           : Option<RelativePathBuf>,
+        /// Don't write anything, and fail if any module is not formatted.
+        #[arg(long)]
+        check          : bool,
+        /// How warnings and errors are printed.
+        #[arg(long, value_enum, default_value = "human")]
+        message_format : MessageFormat
+    },
+
+    /// Print the module dependency graph in DOT format.
+    Graph {
         /// The path containing the entry script (main.vsv).
         /// If none is given, it will use the current working directory.
         path : Option<RelativePathBuf>
     },
 
+    /// Start a language server, which communicates
+    /// with an editor over stdin and stdout.
+    Lsp,
+
     /// Show the documentation for a certain error.
     Explain {
         /// The hexadecimal error code.
@@ -94,9 +145,12 @@ impl Cli {
             Some(Version)        => {Cli::version()},
             Some(Info)           => {Cli::info()}
             Some(Explain {code}) => {Cli::explain(code)},
-            Some(Check   {path}) => {Cli::check(path)},
-            Some(Build   {path}) => {Cli::build(path)},
-            Some(Run     {path}) => {Cli::run(path)}
+            Some(Check   {path, message_format}) => {set_message_format(message_format); Cli::check(path)},
+            Some(Build   {path, message_format}) => {set_message_format(message_format); Cli::build(path)},
+            Some(Run     {path, message_format}) => {set_message_format(message_format); Cli::run(path)},
+            Some(Fmt     {path, check, message_format}) => {set_message_format(message_format); Cli::fmt(path, check)},
+            Some(Graph   {path}) => {Cli::graph(path)},
+            Some(Lsp)            => {exit(lsp::run())}
         }
     }
 
@@ -152,9 +206,15 @@ impl Cli {
             }
         }.unwrap();
 
+        // Declarations and statements which failed to parse are skipped.
+        // Check the rest anyway, so that every error is reported at once.
         attempt!{
+            recover if ProgramInfo::get().all_parsed();
             "Parsing";
-            get_all_modules(None, &path, vec![String::from("main")]);
+            {
+                get_all_modules(None, &path, vec![String::from("main")]);
+                ProgramInfo::get().check_module_graph();
+            };
             config
         };
 
@@ -167,6 +227,110 @@ impl Cli {
     }
 
 
+    fn fmt(path : Option<RelativePathBuf>, check : bool) {
+
+        let path = path.unwrap_or_else(|| RelativePathBuf::absolute_from("."))
+            .absolute();
+
+        attempt!{
+            start;
+            "Preparing";
+            {
+                reset();
+                config::read(&path)
+            }
+        };
+
+        attempt!{
+            "Parsing";
+            {
+                get_all_modules(None, &path, vec![String::from("main")]);
+                ProgramInfo::get().check_module_graph();
+            }
+        };
+
+        attempt!{
+            end;
+            "Formatting";
+            {
+                let     info     = ProgramInfo::get();
+                let mut programs = info.programs();
+                programs.sort_by(|(a, _), (b, _)| a.cmp(b));
+                for (module, program) in programs {
+                    let script    = info.script_of(module);
+                    let formatted = source(program, script);
+                    if (formatted == *script) {continue;}
+                    if (check) {
+                        Cli::fmt_report(module, script, &formatted);
+                    } else if let Err(error) = write(module_path(&path, module).as_str(), formatted) {
+                        push_error!(ModuleWriteFailed, Always, {
+                            None => {"Module `{}` could not be written : {}", module.join("::"), error}
+                        });
+                    }
+                }
+            }
+        };
+
+    }
+
+    /// Push an error showing the lines of a module that would change when formatted.
+    fn fmt_report(module : &Vec<String>, script : &String, formatted : &String) {
+        let hunks = diff_lines(script, formatted, 2);
+        let Some(first) = hunks.first() else {
+            // Only the line endings are different.
+            push_error!(ModuleUnformatted, Always, {
+                None => {"Module `{}` is not formatted.", module.join("::")}
+            });
+            return;
+        };
+        let start = script.split_inclusive('\n').take(first.old_start).map(|line| line.len()).sum::<usize>();
+        let end   = start + script[start..].find('\n').unwrap_or(script.len() - start);
+        let diff  = hunks.iter()
+            .map(|hunk| format!("\x1b[36m@@ -{} +{} @@{}",
+                hunk.old_start + 1,
+                hunk.new_start + 1,
+                hunk.lines.iter()
+                    .map(|(kind, line)| format!("\n         {}{} {}",
+                        match (kind) {'-' => "\x1b[31m", '+' => "\x1b[32m", _ => "\x1b[0m"},
+                        kind,
+                        line
+                    ))
+                    .collect::<Vec<_>>()
+                    .join("")
+            ))
+            .collect::<Vec<_>>()
+            .join("\n         ");
+        push_error!(ModuleUnformatted, Always, {
+            Some(Range(module.clone(), start, end)) => {"Module `{}` is not formatted.", module.join("::")},
+            None                                    => {"{}", diff}
+        });
+    }
+
+
+    fn graph(path : Option<RelativePathBuf>) {
+
+        let path = path.unwrap_or_else(|| RelativePathBuf::absolute_from("."))
+            .absolute();
+
+        // Notes go to stderr so that the graph can be piped into a file.
+        // The graph is printed even if loading failed, to help find the problem.
+        reset();
+        config::read(&path);
+        get_all_modules(None, &path, vec![String::from("main")]);
+        ProgramInfo::get().check_module_graph();
+        let result = dump(0, true, false, None);
+        print!("{}", ProgramInfo::get().module_graph_dot());
+        match (result) {
+            Ok(text)  => {eprint!("{}", text);},
+            Err(text) => {
+                eprint!("{}", text);
+                exit(1);
+            }
+        }
+
+    }
+
+
     fn build(path : Option<RelativePathBuf>) {
         Cli::check(path);
 
@@ -181,15 +345,20 @@ impl Cli {
 
 
     fn run(path : Option<RelativePathBuf>) {
-        Cli::build(path);
+        Cli::check(path);
 
-        attempt!{
+        let value = attempt!{
             end;
             "Running";
-            push_error!(InternalError, Always, {
-                None => {"Todo : Run"}
-            })
+            run::run()
         };
+        match (value) {
+            Some(RunValue::Void) | None => {},
+            Some(_) if (is_json())      => {},
+            Some(value)                 => {
+                println!(" \x1b[37m\x1b[2m=>\x1b[0m \x1b[96mReturned\x1b[0m \x1b[36m{}\x1b[0m.", value);
+            }
+        }
     }
 
 }
@@ -199,23 +368,34 @@ impl Cli {
 /// Print a title, run a function, and report any warnings and/or errors.
 /// If any errors were emitted, exit the program.
 macro attempt {
-    {$title:expr; $expr:expr $(; $config:ident)?} => {$crate::cli::attempt!{false, false; $title; $expr $(; $config)?}},
-    {start; $title:expr; $expr:expr $(; $config:ident)?} => {$crate::cli::attempt!{true, false; $title; $expr $(; $config)?}},
-    {end; $title:expr; $expr:expr $(; $config:ident)?} => {$crate::cli::attempt!{false, true; $title; $expr $(; $config)?}},
-    {start, end; $title:expr; $expr:expr $(; $config:ident)?} => {$crate::cli::attempt!{true, true; $title; $expr $(; $config)?}},
-    {$start:ident, $end:ident; $title:expr; $expr:expr $(; $config:ident)?} => {{
-        if (! $start) {$crate::cli::printw!("\n");}
-        $crate::cli::printw!(" \x1b[37m\x1b[2m=>\x1b[0m \x1b[96m{}\x1b[0m\x1b[36m\x1b[2m...\x1b[0m", $title);
-        let v = $expr;
-        match ($crate::notes::dump(4 + $title.len() + 13, $end, $crate::cli::attempt!{~cfg = $($config)?})) {
+    {$title:expr; $expr:expr $(; $config:ident)?} => {$crate::cli::attempt!{false, false, false; $title; $expr $(; $config)?}},
+    {start; $title:expr; $expr:expr $(; $config:ident)?} => {$crate::cli::attempt!{true, false, false; $title; $expr $(; $config)?}},
+    {end; $title:expr; $expr:expr $(; $config:ident)?} => {$crate::cli::attempt!{false, true, false; $title; $expr $(; $config)?}},
+    {start, end; $title:expr; $expr:expr $(; $config:ident)?} => {$crate::cli::attempt!{true, true, false; $title; $expr $(; $config)?}},
+    // Continue to the next step after errors if `$recover` is true once `$expr` has run.
+    {recover if $recover:expr; $title:expr; $expr:expr $(; $config:ident)?} => {$crate::cli::attempt!{false, false, $recover; $title; $expr $(; $config)?}},
+    {$start:ident, $end:ident, $recover:expr; $title:expr; $expr:expr $(; $config:ident)?} => {{
+        // In JSON mode, only the notes themselves are printed.
+        let human = ! $crate::notes::is_json();
+        if (human && ! $start) {$crate::cli::printw!("\n");}
+        if (human) {$crate::cli::printw!(" \x1b[37m\x1b[2m=>\x1b[0m \x1b[96m{}\x1b[0m\x1b[36m\x1b[2m...\x1b[0m", $title);}
+        let v       = $expr;
+        let recover = $recover;
+        match ($crate::notes::dump(4 + $title.len() + 13, $end, recover, $crate::cli::attempt!{~cfg = $($config)?})) {
             Ok(text) => {
-                $crate::cli::printw!(" [\x1b[32m\x1b[1mSUCCESS\x1b[0m]\n");
-                $crate::cli::printw!("{}", text);
+                if (human) {
+                    $crate::cli::printw!(" [\x1b[32m\x1b[1mSUCCESS\x1b[0m]\n");
+                    $crate::cli::printw!("{}", text);
+                }
             },
             Err(text) => {
-                $crate::cli::printw!(" [\x1b[31m\x1b[1mFAILURE\x1b[0m]\n");
-                $crate::cli::printw!("{}", text);
-                $crate::cli::exit(1);
+                if (human) {
+                    $crate::cli::printw!(" [\x1b[31m\x1b[1mFAILURE\x1b[0m]\n");
+                    $crate::cli::printw!("{}", text);
+                }
+                if (! recover) {
+                    $crate::cli::exit(1);
+                }
             }
         };
         v
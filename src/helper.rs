@@ -20,3 +20,62 @@ impl AbsolutePathBuf for RelativePathBuf {
         return PathBuf::from(self.as_str());
     }
 }
+
+
+/// A group of changed lines between two texts,
+/// with some unchanged lines around it.
+pub(crate) struct DiffHunk<'l> {
+    /// Index of the first line of the hunk in the old text.
+    pub(crate) old_start : usize,
+    /// Index of the first line of the hunk in the new text.
+    pub(crate) new_start : usize,
+    /// Each line, prefixed with ` `, `-`, or `+`.
+    pub(crate) lines     : Vec<(char, &'l str)>
+}
+
+/// Find the lines that changed between two texts,
+/// keeping `context` unchanged lines around each change.
+pub(crate) fn diff_lines<'l>(old : &'l str, new : &'l str, context : usize) -> Vec<DiffHunk<'l>> {
+    let old = old.lines().collect::<Vec<_>>();
+    let new = new.lines().collect::<Vec<_>>();
+    // Longest common subsequence of lines, from the end of both texts.
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if (old[i] == new[j]) {lcs[i + 1][j + 1] + 1} else {lcs[i + 1][j].max(lcs[i][j + 1])};
+        }
+    }
+    // Walk the table to get every line of both texts, with their positions.
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while (i < old.len() || j < new.len()) {
+        if (i < old.len() && j < new.len() && old[i] == new[j]) {
+            lines.push((' ', old[i], i, j));
+            i += 1; j += 1;
+        } else if (i < old.len() && (j >= new.len() || lcs[i + 1][j] >= lcs[i][j + 1])) {
+            lines.push(('-', old[i], i, j));
+            i += 1;
+        } else {
+            lines.push(('+', new[j], i, j));
+            j += 1;
+        }
+    }
+    // Group the changes into hunks.
+    let mut hunks : Vec<(usize, usize)> = Vec::new();
+    for (k, (kind, _, _, _)) in lines.iter().enumerate() {
+        if (*kind == ' ') {continue;}
+        let start = k.saturating_sub(context);
+        let end   = (k + context + 1).min(lines.len());
+        match (hunks.last_mut()) {
+            Some(last) if (last.1 >= start) => {last.1 = end;},
+            _                               => {hunks.push((start, end));}
+        }
+    }
+    return hunks.into_iter()
+        .map(|(start, end)| DiffHunk {
+            old_start : lines[start].2,
+            new_start : lines[start].3,
+            lines     : lines[start..end].iter().map(|(kind, line, _, _)| (*kind, *line)).collect()
+        })
+        .collect();
+}
@@ -0,0 +1,263 @@
+//! Minimal JSON values, used for machine-readable output
+//! and by the language server.
+
+
+use std::fmt::{
+    self,
+    Display,
+    Formatter
+};
+
+
+/// A JSON value.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    /// Keys are kept in insertion order.
+    Object(Vec<(String, Json)>)
+}
+
+impl Json {
+
+    /// Create an object from key value pairs.
+    pub(crate) fn object<S : Into<String>>(pairs : Vec<(S, Json)>) -> Self {
+        return Self::Object(pairs.into_iter().map(|(key, value)| (key.into(), value)).collect());
+    }
+
+    /// Create a string value.
+    pub(crate) fn string<S : Into<String>>(value : S) -> Self {
+        return Self::String(value.into());
+    }
+
+    /// Get a value from an object by key.
+    pub(crate) fn get(&self, key : &str) -> Option<&Json> {
+        let Self::Object(pairs) = self else {return None};
+        return pairs.iter().find(|(k, _)| k == key).map(|(_, value)| value);
+    }
+
+    pub(crate) fn as_str(&self) -> Option<&str> {
+        let Self::String(value) = self else {return None};
+        return Some(value);
+    }
+
+    pub(crate) fn as_usize(&self) -> Option<usize> {
+        let Self::Number(value) = self else {return None};
+        return if (*value >= 0.0 && value.fract() == 0.0) {Some(*value as usize)} else {None};
+    }
+
+    pub(crate) fn as_array(&self) -> Option<&Vec<Json>> {
+        let Self::Array(values) = self else {return None};
+        return Some(values);
+    }
+
+    /// Parse a JSON document.
+    pub(crate) fn parse(text : &str) -> Result<Json, String> {
+        let mut parser = JsonParser {
+            chars : text.chars().collect(),
+            pos   : 0
+        };
+        let value = parser.value()?;
+        parser.whitespace();
+        if (parser.pos < parser.chars.len()) {
+            return Err(format!("Unexpected `{}` at {}", parser.chars[parser.pos], parser.pos));
+        }
+        return Ok(value);
+    }
+
+}
+
+impl From<usize> for Json {
+    fn from(value : usize) -> Self {
+        return Self::Number(value as f64);
+    }
+}
+
+impl Display for Json {
+
+    fn fmt(&self, f : &mut Formatter) -> fmt::Result {
+        return match (self) {
+            Self::Null          => write!(f, "null"),
+            Self::Bool(value)   => write!(f, "{}", value),
+            Self::Number(value) => {
+                if (value.fract() == 0.0 && value.abs() < 1e15) {
+                    write!(f, "{}", *value as i64)
+                } else {
+                    write!(f, "{}", value)
+                }
+            },
+            Self::String(value) => write_string(f, value),
+            Self::Array(values) => {
+                write!(f, "[")?;
+                for (i, value) in values.iter().enumerate() {
+                    if (i > 0) {write!(f, ",")?;}
+                    write!(f, "{}", value)?;
+                }
+                write!(f, "]")
+            },
+            Self::Object(pairs) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in pairs.iter().enumerate() {
+                    if (i > 0) {write!(f, ",")?;}
+                    write_string(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                write!(f, "}}")
+            }
+        };
+    }
+
+}
+
+/// Write a string with quotes, escaping any characters that need it.
+fn write_string(f : &mut Formatter, value : &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for ch in value.chars() {
+        match (ch) {
+            '"'  => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            ch if (ch < ' ') => write!(f, "\\u{:04x}", ch as u32)?,
+            ch   => write!(f, "{}", ch)?
+        }
+    }
+    return write!(f, "\"");
+}
+
+
+/// Recursive descent parser for `Json::parse`.
+struct JsonParser {
+    chars : Vec<char>,
+    pos   : usize
+}
+
+impl JsonParser {
+
+    fn whitespace(&mut self) {
+        while (self.pos < self.chars.len() && matches!(self.chars[self.pos], ' ' | '\t' | '\r' | '\n')) {
+            self.pos += 1;
+        }
+    }
+
+    fn next(&mut self) -> Result<char, String> {
+        let Some(ch) = self.chars.get(self.pos) else {
+            return Err(String::from("Unexpected end of input"));
+        };
+        self.pos += 1;
+        return Ok(*ch);
+    }
+
+    fn expect(&mut self, expected : &str) -> Result<(), String> {
+        for ch in expected.chars() {
+            if (self.next()? != ch) {
+                return Err(format!("Expected `{}` at {}", expected, self.pos - 1));
+            }
+        }
+        return Ok(());
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        self.whitespace();
+        return match (self.chars.get(self.pos)) {
+            Some('n') => self.expect("null").map(|_| Json::Null),
+            Some('t') => self.expect("true").map(|_| Json::Bool(true)),
+            Some('f') => self.expect("false").map(|_| Json::Bool(false)),
+            Some('"') => self.string().map(Json::String),
+            Some('[') => {
+                self.pos += 1;
+                let mut values = Vec::new();
+                self.whitespace();
+                if (self.chars.get(self.pos) == Some(&']')) {
+                    self.pos += 1;
+                    return Ok(Json::Array(values));
+                }
+                loop {
+                    values.push(self.value()?);
+                    self.whitespace();
+                    match (self.next()?) {
+                        ',' => {},
+                        ']' => {break;},
+                        ch  => {return Err(format!("Unexpected `{}` at {}", ch, self.pos - 1));}
+                    }
+                }
+                Ok(Json::Array(values))
+            },
+            Some('{') => {
+                self.pos += 1;
+                let mut pairs = Vec::new();
+                self.whitespace();
+                if (self.chars.get(self.pos) == Some(&'}')) {
+                    self.pos += 1;
+                    return Ok(Json::Object(pairs));
+                }
+                loop {
+                    self.whitespace();
+                    let key = self.string()?;
+                    self.whitespace();
+                    self.expect(":")?;
+                    pairs.push((key, self.value()?));
+                    self.whitespace();
+                    match (self.next()?) {
+                        ',' => {},
+                        '}' => {break;},
+                        ch  => {return Err(format!("Unexpected `{}` at {}", ch, self.pos - 1));}
+                    }
+                }
+                Ok(Json::Object(pairs))
+            },
+            Some(ch) if (*ch == '-' || ch.is_ascii_digit()) => {
+                let start = self.pos;
+                while (self.pos < self.chars.len() && matches!(self.chars[self.pos], '0'..='9' | '-' | '+' | '.' | 'e' | 'E')) {
+                    self.pos += 1;
+                }
+                let number = self.chars[start..self.pos].iter().collect::<String>();
+                number.parse().map(Json::Number).map_err(|_| format!("Invalid number `{}` at {}", number, start))
+            },
+            Some(ch) => Err(format!("Unexpected `{}` at {}", ch, self.pos)),
+            None     => Err(String::from("Unexpected end of input"))
+        };
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect("\"")?;
+        let mut value = String::new();
+        loop {
+            match (self.next()?) {
+                '"'  => {return Ok(value);},
+                '\\' => match (self.next()?) {
+                    'n' => {value.push('\n');},
+                    'r' => {value.push('\r');},
+                    't' => {value.push('\t');},
+                    'b' => {value.push('\u{8}');},
+                    'f' => {value.push('\u{c}');},
+                    'u' => {
+                        let mut code = self.hex()?;
+                        // Characters outside of the basic plane are written as two escapes.
+                        if ((0xD800..0xDC00).contains(&code) && self.chars.get(self.pos) == Some(&'\\')) {
+                            self.expect("\\u")?;
+                            code = 0x10000 + ((code - 0xD800) << 10) + (self.hex()?.wrapping_sub(0xDC00) & 0x3FF);
+                        }
+                        value.push(char::from_u32(code).unwrap_or('\u{FFFD}'));
+                    },
+                    ch  => {value.push(ch);}
+                },
+                ch   => {value.push(ch);}
+            }
+        }
+    }
+
+    fn hex(&mut self) -> Result<u32, String> {
+        let mut code = 0;
+        for _ in 0..4 {
+            let ch = self.next()?;
+            code = code * 16 + ch.to_digit(16).ok_or_else(|| format!("Invalid escape at {}", self.pos - 1))?;
+        }
+        return Ok(code);
+    }
+
+}
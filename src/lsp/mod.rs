@@ -0,0 +1,410 @@
+//! Language server, spoken over stdin and stdout.
+//! Started by `vesuvius lsp`.
+
+
+mod protocol;
+
+use std::{
+    collections::{
+        HashMap,
+        HashSet
+    },
+    io::{
+        stdin,
+        stdout,
+        Write
+    },
+    panic::{
+        catch_unwind,
+        AssertUnwindSafe
+    },
+    path::Path
+};
+
+use relative_path::RelativePathBuf;
+
+use crate::{
+    parse::{
+        node::{
+            Range,
+            DeclarationType
+        },
+        get_all_modules,
+        module_path,
+        set_unsaved,
+        config
+    },
+    notes::{
+        CompilationNote,
+        take_notes,
+        strip_ansi
+    },
+    scope::{
+        self,
+        ProgramInfo
+    },
+    json::Json,
+    lsp::protocol::{
+        read_message,
+        write_message,
+        uri_to_path,
+        path_to_uri,
+        offset,
+        range
+    }
+};
+
+
+/// JSON-RPC error codes.
+const PARSE_ERROR      : i32 = -32700;
+const INVALID_REQUEST  : i32 = -32600;
+const METHOD_NOT_FOUND : i32 = -32601;
+const INVALID_PARAMS   : i32 = -32602;
+
+/// Language server symbol kinds.
+const SYMBOL_MODULE    : usize = 2;
+const SYMBOL_ENUM      : usize = 10;
+const SYMBOL_INTERFACE : usize = 11;
+const SYMBOL_FUNCTION  : usize = 12;
+const SYMBOL_OBJECT    : usize = 19;
+const SYMBOL_STRUCT    : usize = 23;
+
+
+/// Run the language server until the client exits.
+/// Returns the code that the process should exit with.
+pub(crate) fn run() -> i32 {
+    let     stdin  = stdin();
+    let mut input  = stdin.lock();
+    let mut server = Server {
+        output    : Box::new(stdout()),
+        open      : HashSet::new(),
+        checked   : None,
+        published : HashSet::new(),
+        shutdown  : false
+    };
+    while let Some(message) = read_message(&mut input) {
+        match (message) {
+            Ok(message) => {
+                if let Some(code) = server.handle(message) {
+                    return code;
+                }
+            },
+            Err(error) => {server.respond(Json::Null, Err((PARSE_ERROR, error)));}
+        }
+    }
+    return 1;
+}
+
+
+struct Server {
+    output    : Box<dyn Write>,
+    /// Paths of the documents which are open in the client.
+    open      : HashSet<String>,
+    /// The project which was checked last.
+    checked   : Option<RelativePathBuf>,
+    /// Paths which diagnostics were last published to.
+    published : HashSet<String>,
+    /// Whether the client asked the server to shut down.
+    shutdown  : bool
+}
+
+impl Server {
+
+    /// Handle a single message from the client.
+    /// Returns `Some` with an exit code if the server should stop.
+    fn handle(&mut self, message : Json) -> Option<i32> {
+        let id     = message.get("id").cloned();
+        let params = message.get("params").cloned().unwrap_or(Json::Null);
+        let Some(method) = message.get("method").and_then(|method| method.as_str()) else {
+            // Responses to requests from the server are not needed.
+            if (id.is_some() && message.get("result").is_none() && message.get("error").is_none()) {
+                self.respond(id.unwrap(), Err((INVALID_REQUEST, String::from("Missing method"))));
+            }
+            return None;
+        };
+        match (method) {
+
+            "initialize" => {
+                self.respond(id?, Ok(Json::object(vec![
+                    ("capabilities", Json::object(vec![
+                        ("textDocumentSync", Json::object(vec![
+                            ("openClose", Json::Bool(true)),
+                            ("change",    Json::from(1)),     // Full text
+                            ("save",      Json::Bool(true))
+                        ])),
+                        ("definitionProvider",     Json::Bool(true)),
+                        ("hoverProvider",          Json::Bool(true)),
+                        ("documentSymbolProvider", Json::Bool(true))
+                    ])),
+                    ("serverInfo", Json::object(vec![
+                        ("name",    Json::string(env!("CARGO_PKG_NAME"))),
+                        ("version", Json::string(env!("CARGO_PKG_VERSION")))
+                    ]))
+                ])));
+            },
+
+            "shutdown" => {
+                self.shutdown = true;
+                self.respond(id?, Ok(Json::Null));
+            },
+
+            "exit" => {
+                return Some(if (self.shutdown) {0} else {1});
+            },
+
+            "textDocument/didOpen" | "textDocument/didChange" | "textDocument/didSave" | "textDocument/didClose" => {
+                let Some(path) = document_path(&params) else {return None};
+                match (method) {
+                    "textDocument/didOpen" => {
+                        let text = params.get("textDocument").and_then(|doc| doc.get("text")).and_then(|text| text.as_str());
+                        set_unsaved(&RelativePathBuf::from(path.as_str()), text.map(String::from));
+                        self.open.insert(path.clone());
+                    },
+                    "textDocument/didChange" => {
+                        // Only full text changes are supported.
+                        let text = params.get("contentChanges")
+                            .and_then(|changes| changes.as_array())
+                            .and_then(|changes| changes.last())
+                            .and_then(|change| change.get("text"))
+                            .and_then(|text| text.as_str());
+                        set_unsaved(&RelativePathBuf::from(path.as_str()), text.map(String::from));
+                    },
+                    "textDocument/didClose" => {
+                        set_unsaved(&RelativePathBuf::from(path.as_str()), None);
+                        self.open.remove(&path);
+                    },
+                    _ => {}
+                }
+                self.check(project_root(&path));
+            },
+
+            "textDocument/definition" | "textDocument/hover" | "textDocument/documentSymbol" => {
+                let id = id?;
+                let Some(path) = document_path(&params) else {
+                    self.respond(id, Err((INVALID_PARAMS, String::from("Missing `textDocument`"))));
+                    return None;
+                };
+                let root = project_root(&path);
+                if (self.checked.as_ref() != Some(&root)) {
+                    self.check(root.clone());
+                }
+                let module = module_of(&root, &path);
+                let result = match (method) {
+                    "textDocument/definition" => self.definition(&root, &module, &params),
+                    "textDocument/hover"      => self.hover(&module, &params),
+                    _                         => self.document_symbols(&module)
+                };
+                self.respond(id, Ok(result.unwrap_or(Json::Null)));
+            },
+
+            _ => {
+                // Unknown notifications are ignored.
+                if let Some(id) = id {
+                    self.respond(id, Err((METHOD_NOT_FOUND, format!("Unknown method `{}`", method))));
+                }
+            }
+
+        }
+        return None;
+    }
+
+    fn respond(&mut self, id : Json, result : Result<Json, (i32, String)>) {
+        let result = match (result) {
+            Ok(result)            => ("result", result),
+            Err((code, message))  => ("error", Json::object(vec![
+                ("code",    Json::Number(code as f64)),
+                ("message", Json::string(message))
+            ]))
+        };
+        write_message(&mut self.output, &Json::object(vec![
+            ("jsonrpc", Json::string("2.0")),
+            ("id",      id),
+            result
+        ]));
+    }
+
+    fn notify(&mut self, method : &str, params : Json) {
+        write_message(&mut self.output, &Json::object(vec![
+            ("jsonrpc", Json::string("2.0")),
+            ("method",  Json::string(method)),
+            ("params",  params)
+        ]));
+    }
+
+
+    /// Parse and check a project, then publish the notes as diagnostics.
+    fn check(&mut self, root : RelativePathBuf) {
+        // A bug in the compiler should not stop the server.
+        let _ = catch_unwind(AssertUnwindSafe(|| {
+            scope::reset();
+            config::read(&root);
+            get_all_modules(None, &root, vec![String::from("main")]);
+            ProgramInfo::get().check_module_graph();
+            // Declarations and statements which failed to parse are skipped,
+            // but modules which could not be parsed at all can not be checked.
+            if (ProgramInfo::get().all_parsed()) {
+                ProgramInfo::get().check_modules();
+            }
+        }));
+        let notes = take_notes();
+        self.checked = Some(root.clone());
+
+        let mut diagnostics : HashMap<String, Vec<Json>> = HashMap::new();
+        for path in &self.open {
+            diagnostics.insert(path.clone(), Vec::new());
+        }
+        for note in &notes {
+            let (module, diagnostic) = diagnostic(&root, note);
+            diagnostics.entry(module_path(&root, &module).to_string()).or_default().push(diagnostic);
+        }
+        for path in self.published.drain().collect::<Vec<_>>() {
+            diagnostics.entry(path).or_default();
+        }
+        let mut paths = diagnostics.keys().cloned().collect::<Vec<_>>();
+        paths.sort();
+        for path in paths {
+            let diagnostics = diagnostics.remove(&path).unwrap();
+            if (! diagnostics.is_empty()) {
+                self.published.insert(path.clone());
+            }
+            self.notify("textDocument/publishDiagnostics", Json::object(vec![
+                ("uri",         Json::string(path_to_uri(&path))),
+                ("diagnostics", Json::Array(diagnostics))
+            ]));
+        }
+    }
+
+
+    fn definition(&self, root : &RelativePathBuf, module : &Vec<String>, params : &Json) -> Option<Json> {
+        let info      = ProgramInfo::get();
+        let offset    = offset(info.try_script_of(module)?, params.get("position")?)?;
+        let reference = info.reference_at(module, offset)?;
+        let target    = &reference.target;
+        return Some(Json::object(vec![
+            ("uri",   Json::string(path_to_uri(module_path(root, &target.0).as_str()))),
+            ("range", range(info.try_script_of(&target.0).map_or("", |script| script), target))
+        ]));
+    }
+
+    fn hover(&self, module : &Vec<String>, params : &Json) -> Option<Json> {
+        let info      = ProgramInfo::get();
+        let script    = info.try_script_of(module)?;
+        let reference = info.reference_at(module, offset(script, params.get("position")?)?)?;
+        return Some(Json::object(vec![
+            ("contents", Json::object(vec![
+                ("kind",  Json::string("markdown")),
+                ("value", Json::string(format!("```vesuvius\n{}\n```", reference.hover)))
+            ])),
+            ("range", range(script, &reference.range))
+        ]));
+    }
+
+    fn document_symbols(&self, module : &Vec<String>) -> Option<Json> {
+        let info         = ProgramInfo::get();
+        let script       = info.try_script_of(module)?;
+        let (_, program) = info.programs().into_iter().find(|(path, _)| *path == module)?;
+        let mut symbols  = Vec::new();
+        for decl in &program.decls {
+            let full = Range(module.clone(), decl.start(), decl.range.2);
+            let (name, detail, kind, selection) = match (&decl.decl) {
+                DeclarationType::Module(parts, range) => (parts.join("::"), String::new(), SYMBOL_MODULE, range),
+                DeclarationType::Function(name, range, _, args, ret, _) => (
+                    name.clone(),
+                    format!("({}){}",
+                        args.iter()
+                            .map(|(arg, _, typ)| format!("{} : {}", arg, typ.name()))
+                            .collect::<Vec<_>>()
+                            .join(", "),
+                        ret.as_ref().map_or_else(String::new, |ret| format!(" -> {}", ret.name()))
+                    ),
+                    SYMBOL_FUNCTION,
+                    range
+                ),
+                DeclarationType::Struct(name, range, _, _) => (name.clone(), String::new(), SYMBOL_STRUCT, range),
+                DeclarationType::Enum(name, range, _, _)   => (name.clone(), String::new(), SYMBOL_ENUM, range),
+                DeclarationType::Trait(name, range, _)     => (name.clone(), String::new(), SYMBOL_INTERFACE, range),
+                DeclarationType::Impl(path, range, typ, _) => (
+                    format!("impl {} for {}", path.join("::"), typ.name()),
+                    String::new(),
+                    SYMBOL_OBJECT,
+                    range
+                ),
+                DeclarationType::Use(..) | DeclarationType::Error(_) => {continue;}
+            };
+            symbols.push(Json::object(vec![
+                ("name",           Json::string(name)),
+                ("detail",         Json::string(detail)),
+                ("kind",           Json::from(kind)),
+                ("range",          range(script, &full)),
+                ("selectionRange", range(script, selection))
+            ]));
+        }
+        return Some(Json::Array(symbols));
+    }
+
+}
+
+
+/// Convert a note to a diagnostic, and get the module that it belongs to.
+/// Notes without a location are put at the start of the `main` module.
+fn diagnostic(root : &RelativePathBuf, note : &CompilationNote) -> (Vec<String>, Json) {
+    let info    = ProgramInfo::get();
+    let primary = note.details().iter().position(|(range, _)| range.is_some());
+    let (module, location) = match (primary.and_then(|i| note.details()[i].0.as_ref())) {
+        Some(location) => (location.0.clone(), location.clone()),
+        None           => (vec![String::from("main")], Range(vec![String::from("main")], 0, 0))
+    };
+    let script  = info.try_script_of(&module).map_or("", |script| script);
+    let mut message = format!("{}.", strip_ansi(&note.title()));
+    let mut related = Vec::new();
+    for (i, (range, text)) in note.details().iter().enumerate() {
+        match (range) {
+            Some(range) if (Some(i) != primary) => {
+                related.push(Json::object(vec![
+                    ("location", Json::object(vec![
+                        ("uri",   Json::string(path_to_uri(module_path(root, &range.0).as_str()))),
+                        ("range", self::range(info.try_script_of(&range.0).map_or("", |script| script), range))
+                    ])),
+                    ("message", Json::string(strip_ansi(text)))
+                ]));
+            },
+            _ => {
+                message += "\n";
+                message += &strip_ansi(text);
+            }
+        }
+    }
+    return (module, Json::object(vec![
+        ("range",              range(script, &location)),
+        ("severity",           Json::from(if (note.is_error()) {1} else {2})),
+        ("code",               Json::string(note.code())),
+        ("source",             Json::string(env!("CARGO_PKG_NAME"))),
+        ("message",            Json::string(message)),
+        ("relatedInformation", Json::Array(related))
+    ]));
+}
+
+
+/// Get the path of the document that a request is about.
+fn document_path(params : &Json) -> Option<String> {
+    return uri_to_path(params.get("textDocument")?.get("uri")?.as_str()?);
+}
+
+/// Find the project that a file belongs to.
+/// This is the closest directory containing a `config.vsv.ron` file,
+/// or the directory of the file if there is none.
+fn project_root(path : &str) -> RelativePathBuf {
+    let path = Path::new(path);
+    let root = path.ancestors().skip(1)
+        .find(|dir| dir.join("config.vsv.ron").is_file())
+        .or_else(|| path.parent())
+        .unwrap_or(Path::new("."));
+    return RelativePathBuf::from(&*root.to_string_lossy());
+}
+
+/// Get the module path of a file in a project.
+fn module_of(root : &RelativePathBuf, path : &str) -> Vec<String> {
+    let path = path.strip_prefix(root.as_str()).unwrap_or(path);
+    let path = path.strip_suffix(".vsv").unwrap_or(path);
+    return path.split(['/', '\\']).filter(|part| ! part.is_empty()).map(String::from).collect();
+}
@@ -0,0 +1,127 @@
+//! Message framing, and conversions between
+//! `Range`s and language server positions.
+
+
+use std::io::{
+    BufRead,
+    Write
+};
+
+use crate::{
+    parse::node::Range,
+    json::Json
+};
+
+
+/// Read a message from the client.
+/// Returns `None` if the input was closed.
+pub(crate) fn read_message(input : &mut impl BufRead) -> Option<Result<Json, String>> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if (input.read_line(&mut line).ok()? == 0) {
+            return None;
+        }
+        let line = line.trim_end();
+        if (line.is_empty()) {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if (name.eq_ignore_ascii_case("Content-Length")) {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+    let Some(length) = length else {
+        return Some(Err(String::from("Missing `Content-Length` header")));
+    };
+    let mut body = vec![0; length];
+    input.read_exact(&mut body).ok()?;
+    return Some(match (String::from_utf8(body)) {
+        Ok(body)   => Json::parse(&body),
+        Err(error) => Err(error.to_string())
+    });
+}
+
+/// Write a message to the client.
+pub(crate) fn write_message(output : &mut impl Write, message : &Json) {
+    let body = message.to_string();
+    let _ = write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body);
+    let _ = output.flush();
+}
+
+
+/// Get the file path of a `file://` URI.
+pub(crate) fn uri_to_path(uri : &str) -> Option<String> {
+    let path      = uri.strip_prefix("file://")?;
+    let mut bytes = Vec::new();
+    let mut iter  = path.bytes();
+    while let Some(byte) = iter.next() {
+        if (byte == b'%') {
+            let hex = [iter.next()?, iter.next()?];
+            bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else {
+            bytes.push(byte);
+        }
+    }
+    let path = String::from_utf8(bytes).ok()?;
+    // Windows paths are written as `/C:/...`.
+    return Some(match (path.as_bytes()) {
+        [b'/', drive, b':', ..] if (drive.is_ascii_alphabetic()) => String::from(&path[1..]),
+        _                                                        => path
+    });
+}
+
+/// Get the `file://` URI of a file path.
+pub(crate) fn path_to_uri(path : &str) -> String {
+    let mut uri = String::from(if (path.starts_with('/')) {"file://"} else {"file:///"});
+    for byte in path.bytes() {
+        if (byte.is_ascii_alphanumeric() || b"/-_.~:".contains(&byte)) {
+            uri.push(byte as char);
+        } else if (byte == b'\\') {
+            uri.push('/');
+        } else {
+            uri += &format!("%{:02X}", byte);
+        }
+    }
+    return uri;
+}
+
+
+/// Convert a byte offset to a position.
+/// Positions count lines from zero, and characters in UTF-16 code units.
+pub(crate) fn position(script : &str, offset : usize) -> Json {
+    let offset     = offset.min(script.len());
+    let before     = &script[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    return Json::object(vec![
+        ("line",      Json::from(before.matches('\n').count())),
+        ("character", Json::from(before[line_start..].encode_utf16().count()))
+    ]);
+}
+
+/// Convert a position to a byte offset.
+pub(crate) fn offset(script : &str, position : &Json) -> Option<usize> {
+    let line      = position.get("line")?.as_usize()?;
+    let character = position.get("character")?.as_usize()?;
+    let mut start = 0;
+    for _ in 0..line {
+        start += script[start..].find('\n')? + 1;
+    }
+    let mut units = 0;
+    for (i, ch) in script[start..].char_indices() {
+        if (units >= character || ch == '\n') {
+            return Some(start + i);
+        }
+        units += ch.len_utf16();
+    }
+    return Some(script.len());
+}
+
+/// Convert a `Range` in a script to a language server range.
+pub(crate) fn range(script : &str, range : &Range) -> Json {
+    return Json::object(vec![
+        ("start", position(script, range.1)),
+        ("end",   position(script, range.2))
+    ]);
+}
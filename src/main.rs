@@ -20,7 +20,10 @@ pub (crate) mod notes;
 pub (crate) mod scope;
 pub (crate) mod parse;
 pub (crate) mod check;
+pub (crate) mod run;
 pub (crate) mod helper;
+pub (crate) mod json;
+pub (crate) mod lsp;
 
 use clap::Parser;
 
@@ -8,7 +8,8 @@ use crate::{
         node::Range,
         config::Config
     },
-    scope::ProgramInfo
+    scope::ProgramInfo,
+    json::Json
 };
 
 
@@ -34,6 +35,31 @@ pub(crate) mod global {
     #[dynamic]
     pub(crate) static mut COMPILATION_NOTES : Vec<CompilationNote> = Vec::new();
 
+    /// How notes are printed when dumped.
+    #[dynamic]
+    pub(crate) static mut MESSAGE_FORMAT : super::MessageFormat = super::MessageFormat::Human;
+
+}
+
+
+/// How notes are printed when dumped.
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub(crate) enum MessageFormat {
+    /// Coloured text, for reading in a terminal.
+    Human,
+    /// One JSON object per note, per line, for tools.
+    Json
+}
+
+/// Set how notes are printed when dumped.
+pub(crate) fn set_message_format(format : MessageFormat) {
+    *global::MESSAGE_FORMAT.write() = format;
+}
+
+/// Whether notes are printed as JSON instead of text.
+/// Progress messages should not be printed if this is set.
+pub(crate) fn is_json() -> bool {
+    return *global::MESSAGE_FORMAT.read() == MessageFormat::Json;
 }
 
 
@@ -96,12 +122,126 @@ enum_named!{ErrorType {
     /// A symbol that already existed was attempted to be overwritten in a scope that doesn't allow it.
     DuplicateSymbol,
     /// A value was attempted to be modified, but it crossed either the min or max value.
-    Bound_Broken
+    /// 
+    /// Bounds are declared on integer types, such as `int<min = 0, max = 100>`.
+    /// The range of values that an integer can have is followed through arithmetic.
+    /// This is only an error if the whole range is out of bounds. If only part of it
+    /// is, the `Bound_Broken` warning is reported instead.
+    /// 
+    /// Also reported for an index past the end of an array, list or tuple.
+    Bound_Broken,
+
+    /* ADDED */
+    // Codes are the position of each variant, so new variants are only ever
+    // added to the end, whichever step they are reported in.
+
+    /// A function was called with too many or too few arguments.
+    InvalidArgumentCount,
+    /// A number was divided by zero.
+    /// 
+    /// Reported while checking if the divisor is always zero,
+    /// and while running otherwise.
+    DivisionByZero,
+    /// The result of an integer operation did not fit in its type.
+    /// 
+    /// Reported while checking for literals and constant results which do not
+    /// fit, and for shift amounts which are always out of range. Reported while
+    /// running otherwise.
+    ArithmeticOverflow,
+    /// Too many function calls were nested while running.
+    /// 
+    /// This usually happens when a function calls itself
+    /// without ever stopping.
+    StackOverflow,
+    /// No `#[entry]` header was found in any module. The program
+    /// needs a function to start at.
+    MissingEntryHeader,
+    /// An `#[entry]` header was put on something that the program
+    /// can not start at.
+    /// 
+    /// Only functions without any arguments can be entry points.
+    InvalidEntryHeader,
+    /// A private symbol was attempted to be accessed from another module.
+    /// 
+    /// Symbols are private unless they are declared with `pub`.
+    PrivateSymbolAccessed,
+    /// A module declares itself, or one of the modules that declared it.
+    /// 
+    /// Each `mod` declaration in the cycle is shown.
+    CircularModuleDeclaration,
+    /// `vesuvius fmt --check` found a module which is not formatted.
+    /// 
+    /// Run `vesuvius fmt` to format it.
+    ModuleUnformatted,
+    /// `vesuvius fmt` could not write a formatted module back to its file.
+    ModuleWriteFailed,
+    /// A string or character literal contained an escape sequence that
+    /// does not exist, or a character literal did not contain exactly
+    /// one character.
+    /// 
+    /// Valid escape sequences are `\n`, `\t`, `\r`, `\0`, `\\`, `\"`,
+    /// `\'`, and `\u{...}` with 1 to 6 hex digits.
+    InvalidLiteral,
+    /// A value was assigned to a variable which was not declared as mutable.
+    /// 
+    /// Variables can only be assigned to if they are declared with `let mut`.
+    AssignmentToImmutable,
+    /// `break` or `continue` was used outside of a loop, or `break` was
+    /// given a value in a loop that can not produce one.
+    /// 
+    /// Only `loop` can be broken with a value. `while` and `for` loops
+    /// are always `void`.
+    InvalidLoopControl,
+    /// A function with a return type has a path which does not return a value.
+    /// 
+    /// The last statement of the function must produce a value, unless
+    /// every path before it ends with `return`.
+    MissingReturn,
+    /// A struct or enum contains itself through its fields, without any indirection.
+    /// 
+    /// Such a type would need infinite space to store.
+    RecursiveType,
+    /// A struct literal did not give a value to every field of the struct.
+    MissingStructField,
+    /// A `match` does not have an arm for every value that it can be given.
+    /// 
+    /// Each missing enum variant is listed. Types with too many values to
+    /// list, such as `int`, need a `_` arm.
+    /// 
+    /// Also reported for a `let` pattern which does not match every value.
+    NonExhaustiveMatch,
+    /// A trait was implemented for the same type more than once, or a method
+    /// was called that more than one trait gives to the type.
+    /// 
+    /// The call could not tell which `impl` to use.
+    ConflictingImpl,
+    /// An `impl` did not give a body to every method of its trait.
+    MissingTraitMethod,
+    /// A closure which captures a mutable variable was returned, or stored
+    /// somewhere that lives longer than the variable.
+    /// 
+    /// Closures share mutable variables with the scope that declared them,
+    /// so they can not be used after that scope ends.
+    EscapingCapture,
+    /// A type argument of a generic function could not be inferred from the
+    /// values that it was given.
+    /// 
+    /// A type parameter which is not used by any argument can never be inferred.
+    UninferredTypeArgument,
+    /// A type was given to a type parameter, but it does not implement
+    /// every trait that the parameter is bound by.
+    UnsatisfiedBound
 
 }}
 
+/// The code of the first warning. Warnings start at a fixed code instead
+/// of continuing on from `ErrorType`, so adding an error never changes the
+/// code of a warning.
+const WARN_BASE : usize = 0x1000;
+const _ : () = assert!(ErrorType::MAX <= WARN_BASE, "error codes run into the warning codes");
+
 // Different warning types, with the formatting functions auto generated.
-enum_named!{WarnType += ErrorType {
+enum_named!{WarnType /=/ WARN_BASE {
 
     /* INTERNAL */
 
@@ -121,10 +261,47 @@ enum_named!{WarnType += ErrorType {
     /// The contents of the given block are either always or never called.
     /// 
     /// This will usually show up in if statements, if the condition is always or never true.
-    BlockContents_Called
+    BlockContents_Called,
+
+    /* ADDED */
+    // New variants are only ever added to the end, so that
+    // existing codes keep their positions.
+
+    /// A symbol was imported with `use`, but never used.
+    UnusedImport,
+    /// The same module is declared with `mod` by more than one module.
+    ModuleDeclaredMultipleTimes,
+    /// Statements come after a `return`, `break` or `continue`,
+    /// so they are never run.
+    UnreachableCode,
+    /// A value given to an integer type with bounds can be outside of them,
+    /// but is not always.
+    /// 
+    /// The range of values that it can have is followed through arithmetic,
+    /// so the value might not really be able to leave the bounds. Values which
+    /// are always outside of the bounds are reported as an error instead.
+    Bound_Broken
 }}
 
 
+/// Remove ansi escape codes from some text.
+pub(crate) fn strip_ansi(text : &str) -> String {
+    let mut result = String::new();
+    let mut chars  = text.chars();
+    while let Some(ch) = chars.next() {
+        if (ch == '\x1b') {
+            // Skip until the end of the escape code.
+            while let Some(ch) = chars.next() {
+                if (ch.is_ascii_alphabetic()) {break;}
+            }
+        } else {
+            result.push(ch);
+        }
+    }
+    return result;
+}
+
+
 /// Get documentation for a certain note code.
 pub(crate) fn explain(id : usize) -> Option<String> {
     try_explain!(ErrorType, id, Error);
@@ -133,8 +310,7 @@ pub(crate) fn explain(id : usize) -> Option<String> {
 }
 macro try_explain {
     ($enm:ident, $id:expr, $typ:ident) => {
-        if ($id < $enm::MAX) {
-            let var  = $enm::from_id($id).unwrap();
+        if let Some(var) = $enm::from_id($id) {
             let doc  = var.doc()
                 .unwrap_or_else(|| String::from("\x1b[37m\x1b[2m\x1b[3mNo documentation found for this note code.\x1b[0m"))
                 .split("\n").map(|x| String::from("   ") + x)
@@ -150,11 +326,25 @@ macro try_explain {
 
 
 /// Dump all of the queued notes into a string, and remove them from the queue.
-pub(crate) fn dump(mut line_len : usize, finish : bool, config : Option<&Config>) -> Result<String, String> {
+/// Returns `Err` if any error has been queued, including by earlier dumps.
+/// If `recover` is set, the step continues after errors, so they do not print the final message.
+pub(crate) fn dump(mut line_len : usize, finish : bool, recover : bool, config : Option<&Config>) -> Result<String, String> {
     let mut final_text = String::new();
 
     let mut notes        = global::COMPILATION_NOTES.write();
     let mut notes_dumped = global::COMPILATION_NOTES_DUMPED.write();
+    if (is_json()) {
+        let errors = notes.iter().chain(notes_dumped.iter()).filter(|note| note.is_error()).count();
+        for note in notes.iter() {
+            println!("{}", note.to_json());
+        }
+        notes_dumped.append(&mut notes);
+        return if (errors > 0) {
+            Err(final_text)
+        } else {
+            Ok(final_text)
+        };
+    }
     let mut counts = (
         0, // Warn
         0  // Error
@@ -169,14 +359,14 @@ pub(crate) fn dump(mut line_len : usize, finish : bool, config : Option<&Config>
         use NoteType::*;
         match (note_dumped.note) {
             Warn  (_) => {counts.0 += 1},
-            Error (_) => {counts.0 += 1}
+            Error (_) => {counts.1 += 1}
         }
     }
     // Print a line after the last note.
     final_text += &format!("\x1b[90m{}\x1b[0m\n", "─".repeat(line_len));
     // Print finished or failed, with the number of each note type.
     let (warns, errors) = counts;
-    if (finish || errors > 0) {
+    if (finish || (errors > 0 && ! recover)) {
         // Finished or failed.
         let (mut finished, mut finished_len) = if (errors > 0) {
             (String::from("\x1b[31m\x1b[1mFailed\x1b[0m"), 6)
@@ -191,7 +381,7 @@ pub(crate) fn dump(mut line_len : usize, finish : bool, config : Option<&Config>
             with.push(format!("\x1b[33m{}\x1b[0m", message));
         }
         if (errors > 0) {
-            let message   = format!("{} error{}", warns, if (warns != 1) {"s"} else {""});
+            let message   = format!("{} error{}", errors, if (errors != 1) {"s"} else {""});
             finished_len += message.len();
             with.push(format!("\x1b[31m{}\x1b[0m", message));
         }
@@ -233,6 +423,13 @@ pub(crate) fn dump(mut line_len : usize, finish : bool, config : Option<&Config>
 }
 
 
+/// Remove all of the queued notes without printing them.
+/// Used by the language server, which reports them itself.
+pub(crate) fn take_notes() -> Vec<CompilationNote> {
+    return global::COMPILATION_NOTES.write().drain(..).collect();
+}
+
+
 /// Add a note to the queue, which will be dumped
 /// after the compilation step is complete. Used by
 /// `push_error!` and `push_warn!`.
@@ -254,6 +451,27 @@ macro _push_note {
     }}
 }
 
+/// Add a note to the queue, with details that are
+/// only known at runtime, such as every module in
+/// a cycle. Most notes should use `push_error!` or
+/// `push_warn!` instead.
+pub macro push_note_details {
+    ($typ:expr, $occur:expr, $details:expr) => {{
+        use $crate::notes::*;
+        let mut lock = global::COMPILATION_NOTES.write();
+        let     note = CompilationNote {
+            source : if (cfg!(debug_assertions)) {
+                // If in debug env, Get the location of the call.
+                Some((line!(), column!(), String::from(module_path!())))
+            } else {None},
+            occurance : $occur,
+            note      : $typ,
+            details   : $details
+        };
+        lock.push(note);
+    }}
+}
+
 /// Add an error to the queue which will be dumped
 /// after the compilation step is complete.
 /// 
@@ -298,6 +516,55 @@ pub(crate) struct CompilationNote {
     details   : Vec<(Option<Range>, String)>
 }
 impl CompilationNote {
+    pub(crate) fn is_error(&self) -> bool {
+        return matches!(self.note, NoteType::Error(_));
+    }
+    /// Get the hexadecimal note code, padded with zeros.
+    pub(crate) fn code(&self) -> String {
+        let (id, id_len) = match (&self.note) {
+            NoteType::Warn(warn)   => (warn.id(), warn.id_len()),
+            NoteType::Error(error) => (error.id(), error.id_len())
+        };
+        return format!("{:0>id_len$}", id);
+    }
+    /// Get the title of the note, including how often it occurs.
+    pub(crate) fn title(&self) -> String {
+        return match (&self.note) {
+            NoteType::Warn(warn)   => warn.fmt(Some(&self.occurance)),
+            NoteType::Error(error) => error.fmt(Some(&self.occurance))
+        };
+    }
+    pub(crate) fn details(&self) -> &Vec<(Option<Range>, String)> {
+        return &self.details;
+    }
+    /// Get the note as a single line JSON object.
+    fn to_json(&self) -> Json {
+        return Json::object(vec![
+            ("code",      Json::string(self.code())),
+            ("severity",  Json::string(if (self.is_error()) {"error"} else {"warning"})),
+            ("occurance", Json::string(self.occurance.name().to_lowercase())),
+            ("title",     Json::string(self.title())),
+            ("details",   Json::Array(self.details.iter().map(|(range, message)| Json::object(vec![
+                ("message", Json::string(strip_ansi(message))),
+                ("range",   if let Some(range) = range {
+                    let linecol = range.to_linecolumn(ProgramInfo::get().script_of(&range.0));
+                    Json::object(vec![
+                        ("module", Json::Array(range.0.iter().map(|part| Json::string(part)).collect())),
+                        ("start",  Json::object(vec![
+                            ("offset", Json::from(range.1)),
+                            ("line",   Json::from(linecol.1.0)),
+                            ("column", Json::from(linecol.1.1))
+                        ])),
+                        ("end",    Json::object(vec![
+                            ("offset", Json::from(range.2)),
+                            ("line",   Json::from(linecol.2.0)),
+                            ("column", Json::from(linecol.2.1))
+                        ]))
+                    ])
+                } else {Json::Null})
+            ])).collect()))
+        ]);
+    }
     /// Format the note, which can be printed when the note queue is dumped.
     fn fmt(&self, counts : &mut (u64, u64)) -> (String, usize) {
         let text = self.note.fmt(&self.occurance, &self.details, counts);
@@ -412,7 +679,7 @@ impl NoteType {
                                 }
                                 continue;
                             }
-                            let line     = script.lines().nth(l).unwrap();
+                            let line     = script.lines().nth(l).unwrap_or("");
                             let line_pad = (l + 1).to_string().len();
                             if (line_pad > lines_pad) {
                                 lines_pad = line_pad;
@@ -464,7 +731,7 @@ impl NoteType {
                                         } else {String::new()}
                                     )
                                 // If the detail is multi line, add a marker showing the end of the detail, with a line from the sol.
-                                } else {format!(" {}┘", "─".repeat(range.2.1 - 2))}
+                                } else {format!(" {}┘", "─".repeat(range.2.1.saturating_sub(2)))}
                             ),
                             // Message Prefix
                             format!("└─{}─┴──", "─".repeat(lines_pad))
@@ -518,9 +785,6 @@ macro_rules! enum_named {
     {$name:ident {$($(#[doc = $doc:literal])* $variant:ident),*}} => {
         $crate::notes::enum_named!{$name /=/ 0 {$($(#[doc = $doc])* $variant),*}}
     };
-    {$name:ident += $addto:ident {$($(#[doc = $doc:literal])* $variant:ident),*}} => {
-        $crate::notes::enum_named!{$name /=/ $addto::MAX {$($(#[doc = $doc])* $variant),*}}
-    };
     {$name:ident /=/ $($addto:tt)::+ {$($(#[doc = $doc:literal])* $variant:ident),*}} => {
         /// An auto-generated enum.
         #[allow(non_camel_case_types)]
@@ -1,3 +1,11 @@
+use std::{
+    collections::{
+        HashMap,
+        BTreeMap
+    },
+    cell::RefCell
+};
+
 use peg::{
     parser,
     error::ParseError,
@@ -7,12 +15,163 @@ use peg::{
 use crate::parse::node::*;
 
 
-pub(crate) fn parse(text : String, module : &Vec<String>) -> Result<Program, ParseError<LineCol>> {
-    return parser::parse(&text, module);
+/// Parse a script. Declarations and statements which fail to parse are skipped,
+/// and the reasons that they failed are returned with the program, followed by
+/// any problems with string and character literals.
+/// If the parser can not recover, the error is returned instead.
+pub(crate) fn parse(text : String, module : &Vec<String>) -> Result<(Program, Vec<ParseError<LineCol>>, Vec<(Range, String)>), ParseError<LineCol>> {
+    // Comments found while parsing, by start position. The same comment may be
+    // found multiple times when the parser backtracks, so they are deduplicated.
+    let comments       = RefCell::new(BTreeMap::new());
+    // Skipped declarations and statements, by start position.
+    let skipped        = RefCell::new(BTreeMap::new());
+    // Invalid escape sequences and character literals, by start position.
+    let literal_errors = RefCell::new(BTreeMap::new());
+    let mut program    = parser::parse(&text, module, &comments, &skipped, &literal_errors)?;
+    program.comments = comments.into_inner().into_iter()
+        .map(|(start, (end, comment))| {
+            let comment : &str = comment;
+            let trimmed  = comment.trim_end_matches(['\n', '\r']);
+            let own_line = text[..start].rsplit('\n').next().unwrap().trim().is_empty();
+            Comment {
+                text     : String::from(trimmed),
+                own_line,
+                range    : Range(module.clone(), start, end - (comment.len() - trimmed.len()))
+            }
+        })
+        .collect();
+    // If a declaration was skipped, anything that was skipped inside of it
+    // was only tried before the parser backtracked.
+    let skipped = skipped.into_inner().into_iter().collect::<Vec<_>>();
+    let mut errors = Vec::new();
+    for (i, (start, (end, kind))) in skipped.iter().enumerate() {
+        if (skipped[..i].iter().any(|(other_start, (other_end, _))| other_start <= start && end <= other_end)) {
+            continue;
+        }
+        errors.push(skipped_error(&text, module, *start, *end, *kind));
+    }
+    let literal_errors = literal_errors.into_inner().into_iter()
+        .map(|(start, (end, message))| (Range(module.clone(), start, end), message))
+        .collect();
+    return Ok((program, errors, literal_errors));
+}
+
+/// Combine the operands of one precedence level, from left to right.
+fn binary_operations(module : &Vec<String>, left : Expression, ops : Vec<(&str, Expression)>) -> Expression {
+    let mut left = left;
+    for (op, right) in ops {
+        let range = Range(module.clone(), left.range.1, right.range.2);
+        left = Expression {
+            expr : ExpressionType::binary(op, left, right),
+            range
+        };
+    }
+    return left;
+}
+
+/// Parse a skipped declaration or statement on its own, to find out why it failed.
+fn skipped_error(text : &str, module : &Vec<String>, start : usize, end : usize, kind : Skipped) -> ParseError<LineCol> {
+    let comments       = RefCell::new(BTreeMap::new());
+    let skipped        = RefCell::new(BTreeMap::new());
+    let literal_errors = RefCell::new(BTreeMap::new());
+    // Include whatever ended the declaration or statement.
+    let end            = text[end..].chars().next().map_or(end, |ch| end + ch.len_utf8());
+    let result         = match (kind) {
+        Skipped::Declaration => parser::declaration_alone(&text[start..end], module, &comments, &skipped, &literal_errors).map(|_| ()),
+        Skipped::Statement   => parser::statement_alone(&text[start..end], module, &comments, &skipped, &literal_errors).map(|_| ()),
+        Skipped::Semicolon   => parser::semicolon(&text[start..end], module, &comments, &skipped, &literal_errors)
+    };
+    // It failed in context, so it should fail on its own too.
+    let mut error = match (result) {
+        Err(error) => error,
+        Ok(_)      => parser::unknown_error("", module, &comments, &skipped, &literal_errors).unwrap_err()
+    };
+    error.location.offset += start;
+    return error;
+}
+
+/// Replace the escape sequences in the contents of a string or character literal,
+/// which start at `start` in the script. Invalid escape sequences are recorded
+/// in `literal_errors`, and replaced with `U+FFFD`.
+fn unescape(text : &str, start : usize, literal_errors : &RefCell<BTreeMap<usize, (usize, String)>>) -> String {
+    let mut value = String::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, ch)) = chars.next() {
+        if (ch != '\\') {
+            value.push(ch);
+            continue;
+        }
+        let escaped = match (chars.next()) {
+            Some((_, 'n'))  => Ok('\n'),
+            Some((_, 't'))  => Ok('\t'),
+            Some((_, 'r'))  => Ok('\r'),
+            Some((_, '0'))  => Ok('\0'),
+            Some((_, '\\')) => Ok('\\'),
+            Some((_, '"'))  => Ok('"'),
+            Some((_, '\'')) => Ok('\''),
+            Some((_, 'u'))  => {
+                if (matches!(chars.peek(), Some((_, '{')))) {
+                    chars.next();
+                    let mut digits = String::new();
+                    let mut closed = false;
+                    while let Some((_, ch)) = chars.peek() {
+                        if (*ch == '}') {
+                            chars.next();
+                            closed = true;
+                            break;
+                        }
+                        if (! ch.is_ascii_hexdigit()) {
+                            break;
+                        }
+                        digits.push(*ch);
+                        chars.next();
+                    }
+                    if (! closed) {
+                        Err(String::from("Unicode escapes must be closed with `}`."))
+                    } else if (digits.is_empty() || digits.len() > 6) {
+                        Err(String::from("Unicode escapes must have 1 to 6 hex digits."))
+                    } else {
+                        let code = u32::from_str_radix(&digits, 16).unwrap();
+                        char::from_u32(code).ok_or_else(|| format!("`{:X}` is not a unicode character.", code))
+                    }
+                } else {
+                    Err(String::from("Unicode escapes must be written as `\\u{...}`."))
+                }
+            },
+            Some((_, ch))   => Err(format!("Unknown escape sequence `\\{}`.", ch)),
+            None            => Err(String::from("Escape sequence is missing a character."))
+        };
+        match (escaped) {
+            Ok(ch)       => {value.push(ch);},
+            Err(message) => {
+                let end = chars.peek().map_or(text.len(), |(j, _)| *j);
+                literal_errors.borrow_mut().insert(start + i, (start + end, message));
+                value.push('\u{FFFD}');
+            }
+        }
+    }
+    return value;
+}
+
+/// Something which follows an atom in an expression.
+enum Postfix {
+    /// A field, or a method call with its arguments and the end of its brackets.
+    Field(String, Range, Option<(Vec<Expression>, usize)>),
+    /// An index, with the end of its brackets.
+    Index(Expression, usize)
+}
+
+/// The kinds of nodes which can be skipped if they fail to parse.
+#[derive(Clone, Copy)]
+enum Skipped {
+    Declaration,
+    Statement,
+    /// A declaration which parsed, but was not followed by a `;`.
+    Semicolon
 }
 
 
-parser! {grammar parser(module : &Vec<String>) for str {
+parser! {grammar parser(module : &Vec<String>, comments : &RefCell<BTreeMap<usize, (usize, &'input str)>>, skipped : &RefCell<BTreeMap<usize, (usize, Skipped)>>, literal_errors : &RefCell<BTreeMap<usize, (usize, String)>>) for str {
 
     // Debug peg stuff
     pub(crate) rule parse() -> Program = traced(<program()>)
@@ -28,16 +187,83 @@ parser! {grammar parser(module : &Vec<String>) for str {
         }
 
     rule program() -> Program
-        = _ decls:(decl:declaration() _ ";" _ {decl})* ![_]
+        = _ decls:(decl:declaration_or_skip() _ {decl})* ![_]
             {Program {
-                decls
+                decls,
+                comments : Vec::new()
             }}
 
+    /// Used to find out why a skipped declaration failed to parse.
+    pub(crate) rule declaration_alone() -> Declaration
+        = _ decl:declaration() _ ";" _ {decl}
+
+    /// Used if a skipped declaration or statement somehow parses on its own.
+    pub(crate) rule unknown_error()
+        = expected!("valid syntax")
+
+    /// Used to report a missing `;` after a declaration.
+    pub(crate) rule semicolon()
+        = ";"
+
+    rule declaration_or_skip() -> Declaration
+        = decl:declaration() _ ";" {decl}
+        // The `;` was left out, but the next declaration starts here.
+        / decl:declaration() &(declaration_start() / ![_])
+            {
+                skipped.borrow_mut().insert(decl.range.2, (decl.range.2, Skipped::Semicolon));
+                decl
+            }
+        // Keep the headers and name of the declaration if they can be found,
+        // so that it is not reported as missing too.
+        / skip_start:position!() headers:(header:declaration_header() _ {header})* vis:declaration_visibility()
+          start:position!() name:skipped_declaration_name()? (skip_declaration() ";"? / ";") end:position!()
+            {
+                skipped.borrow_mut().insert(skip_start, (end, Skipped::Declaration));
+                Declaration {
+                    headers,
+                    vis,
+                    decl    : DeclarationType::Error(name),
+                    range   : Range(module.clone(), start, end)
+                }
+            }
+        // Nothing is left after the headers or name, so skip everything.
+        / start:position!() (skip_declaration() ";"? / ";") end:position!()
+            {
+                skipped.borrow_mut().insert(start, (end, Skipped::Declaration));
+                Declaration {
+                    headers : Vec::new(),
+                    vis     : DeclarationVisibility {
+                        vis   : DeclarationVisibilityType::Private,
+                        range : Range(module.clone(), start, start)
+                    },
+                    decl    : DeclarationType::Error(None),
+                    range   : Range(module.clone(), start, end)
+                }
+            }
+
+    rule declaration_start()
+        = declaration_header() / ("pub" / "priv" / "mod" / "use" / "fn" / "struct" / "enum" / "trait" / "impl") !ident_char()
+
+    rule skipped_declaration_name() -> (String, String, Range)
+        = keyword:$("fn" / "struct" / "enum" / "trait") __ start:position!() name:ident() end:position!()
+            {(String::from(keyword), name, Range(module.clone(), start, end))}
+
+    /// Skip to the end of the declaration, which is the next `;` outside of any braces.
+    rule skip_declaration()
+        = (___() / skip_quoted() / skip_braces() / [^ ';' | '{'])+
+    rule skip_braces()
+        = "{" (___() / skip_quoted() / skip_braces() / [^ '{' | '}'])* ("}" / ![_])
+    /// Skip a string or character literal, so that any `;` or braces inside of it are ignored.
+    rule skip_quoted()
+        = "r" hashes:$("#"*) "\"" (!raw_string_end(hashes.len()) [_])* (raw_string_end(hashes.len()) / ![_])
+        / "\"" ("\\" [_] / [^ '"' | '\\'])* ("\"" / ![_])
+        / "'" ("\\" [_] / [^ '\'' | '\\' | '\n'])* "'"?
+
 
     rule declaration() -> Declaration
         = headers:(header:declaration_header() _ {header})* _
           vis:declaration_visibility()
-          start:position!() decl:(declaration_module() / declaration_function()) end:position!() _
+          start:position!() decl:(declaration_module() / declaration_use() / declaration_function() / declaration_struct() / declaration_enum() / declaration_trait() / declaration_impl()) end:position!() _
             {Declaration {
                 headers,
                 vis,
@@ -72,15 +298,149 @@ parser! {grammar parser(module : &Vec<String>) for str {
         = "mod" __ start:position!() parts:((part:ident() _ {part}) ++ "::") end:position!()
             {DeclarationType::Module(parts, Range(module.clone(), start, end))}
 
+    rule declaration_use() -> DeclarationType
+        = "use" __ base:(start:position!() part:ident() end:position!() _ "::" _ {(part, Range(module.clone(), start, end))})+ items:(
+              "{" _ items:((item:declaration_use_item() _ {item}) ++ ("," _)) ("," _)? "}"
+                {(items, true)}
+            / item:declaration_use_item()
+                {(vec![item], false)}
+        )
+            {DeclarationType::Use(base, items.0, items.1)}
+
+    /// The item that is imported may be a keyword, such as `use std::consts::true;`.
+    rule declaration_use_item() -> (String, Range, Option<(String, Range)>)
+        = start:position!() name:(ident() / keyword:$(reserved()) {String::from(keyword)}) end:position!()
+          alias:(__ "as" __ alias_start:position!() alias:ident() alias_end:position!() {(alias, Range(module.clone(), alias_start, alias_end))})?
+            {(name, Range(module.clone(), start, end), alias)}
+
     rule declaration_function() -> DeclarationType
-        = "fn" __ start:position!() name:ident() end:position!() _ /* TODO : Arguments and return */ block:block()
-            {DeclarationType::Function(name, Range(module.clone(), start, end), Vec::new(), None, block)}
+        = "fn" __ start:position!() name:ident() end:position!() _
+          generics:(generics:generics() _ {generics})?
+          args:("(" _ args:((arg:declaration_function_argument() _ {arg}) ** ("," _)) ("," _)? ")" _ {args})?
+          ret:("->" _ ret:type_descriptor() _ {ret})?
+          block:block()
+            {DeclarationType::Function(name, Range(module.clone(), start, end), generics.unwrap_or_else(Vec::new), args.unwrap_or_else(Vec::new), ret, block)}
+
+    rule generics() -> Vec<Generic>
+        = "<" _ generics:((generic:generic() _ {generic}) ++ ("," _)) ("," _)? ">"
+            {generics}
+
+    rule generic() -> Generic
+        = start:position!() name:ident() end:position!()
+          bounds:(_ ":" _ bounds:((bound_start:position!() bound:(ident() ++ (_ "::" _)) bound_end:position!() {(bound, Range(module.clone(), bound_start, bound_end))}) ++ (_ "+" _)) {bounds})?
+            {Generic {
+                name,
+                range  : Range(module.clone(), start, end),
+                bounds : bounds.unwrap_or_else(Vec::new)
+            }}
+
+    rule declaration_function_argument() -> (String, Range, TypeDescriptor)
+        = start:position!() name:ident() end:position!() _ ":" _ typ:type_descriptor()
+            {(name, Range(module.clone(), start, end), typ)}
+
+    rule declaration_struct() -> DeclarationType
+        = "struct" __ start:position!() name:ident() end:position!() _
+          generics:(generics:generics() _ {generics})?
+          "{" _ fields:((field:declaration_struct_field() _ {field}) ** ("," _)) ("," _)? "}"
+            {DeclarationType::Struct(name, Range(module.clone(), start, end), generics.unwrap_or_else(Vec::new), fields)}
+
+    rule declaration_struct_field() -> (DeclarationVisibility, String, Range, TypeDescriptor)
+        = vis:declaration_visibility() start:position!() name:ident() end:position!() _ ":" _ typ:type_descriptor()
+            {(vis, name, Range(module.clone(), start, end), typ)}
+
+    rule declaration_enum() -> DeclarationType
+        = "enum" __ start:position!() name:ident() end:position!() _
+          generics:(generics:generics() _ {generics})?
+          "{" _ variants:((variant:declaration_enum_variant() _ {variant}) ** ("," _)) ("," _)? "}"
+            {DeclarationType::Enum(name, Range(module.clone(), start, end), generics.unwrap_or_else(Vec::new), variants)}
+
+    rule declaration_enum_variant() -> (String, Range, VariantFields)
+        = start:position!() name:ident() end:position!() fields:(
+              _ "(" _ types:((typ:type_descriptor() _ {typ}) ** ("," _)) ("," _)? ")"
+                {VariantFields::Tuple(types)}
+            / _ "{" _ fields:((field:declaration_function_argument() _ {field}) ** ("," _)) ("," _)? "}"
+                {VariantFields::Named(fields)}
+            / {VariantFields::Unit}
+        )
+            {(name, Range(module.clone(), start, end), fields)}
+
+    rule declaration_trait() -> DeclarationType
+        = "trait" __ start:position!() name:ident() end:position!() _
+          "{" _ methods:(method:method(false) _ ";" _ {method})* "}"
+            {DeclarationType::Trait(name, Range(module.clone(), start, end), methods)}
+
+    rule declaration_impl() -> DeclarationType
+        = "impl" __ start:position!() path:(ident() ++ (_ "::" _)) end:position!() __ "for" __ typ:type_descriptor() _
+          "{" _ methods:(method:method(true) _ ";" _ {method})* "}"
+            {DeclarationType::Impl(path, Range(module.clone(), start, end), typ, methods)}
+
+    /// A method in a trait or an impl. Only methods in impls have a body.
+    rule method(body : bool) -> Method
+        = start:position!() "fn" __ name_start:position!() name:ident() name_end:position!() _
+          "(" _ receiver_start:position!() "self" !ident_char() receiver_end:position!() _
+          args:("," _ arg:declaration_function_argument() _ {arg})* ("," _)? ")"
+          ret:(_ "->" _ ret:type_descriptor() {ret})?
+          block:(allow(body) _ block:block() {Some(block)} / allow(! body) {None}) end:position!()
+            {Method {
+                name,
+                name_range : Range(module.clone(), name_start, name_end),
+                receiver   : Range(module.clone(), receiver_start, receiver_end),
+                args,
+                ret,
+                block,
+                range      : Range(module.clone(), start, end)
+            }}
+
+
 
+    /// Used to find out why a skipped statement failed to parse.
+    pub(crate) rule statement_alone() -> Statement
+        = _ stmt:statement() _ (";" / "}") {stmt}
 
+    rule statement_or_skip() -> Statement
+        = stmt:statement() &(_ (";" / "}")) {stmt}
+        // Keep the name of a `let` if it can be found,
+        // so that uses of it are not reported as unknown.
+        / start:position!() name:skipped_statement_name() skip_statement()? end:position!()
+            {
+                skipped.borrow_mut().insert(start, (end, Skipped::Statement));
+                Statement {
+                    stmt  : StatementType::Error(Some(name)),
+                    range : Range(module.clone(), start, end)
+                }
+            }
+        / start:position!() skip_statement() end:position!()
+            {
+                skipped.borrow_mut().insert(start, (end, Skipped::Statement));
+                Statement {
+                    stmt  : StatementType::Error(None),
+                    range : Range(module.clone(), start, end)
+                }
+            }
+
+    rule skipped_statement_name() -> (bool, String, Range)
+        = "let" __ mutable:("mut" __)? start:position!() name:ident() end:position!()
+            {(mutable.is_some(), name, Range(module.clone(), start, end))}
+
+    /// Skip to the end of the statement, which is the next `;` or `}` outside of any braces.
+    rule skip_statement()
+        = (___() / skip_quoted() / skip_braces() / [^ ';' | '{' | '}'])+
 
     rule statement() -> Statement
-        = start:position!() stmt:("let" __ start_name:position!() name:ident() end_name:position!() _ "=" _ value:expression()
-            {StatementType::InitVar(name, Range(module.clone(), start_name, end_name), value)}
+        = start:position!() stmt:("let" __ mutable:("mut" __)? start_name:position!() name:ident() end_name:position!() _ typ:(":" _ typ:type_descriptor() _ {typ})? "=" _ value:expression()
+            {StatementType::InitVar(mutable.is_some(), name, Range(module.clone(), start_name, end_name), typ, value)}
+        / "let" __ mutable:("mut" __)? !reserved() pattern:pattern() _ "=" _ value:expression()
+            {StatementType::Destructure(mutable.is_some(), pattern, value)}
+        / "let" __ ("mut" __)? reserved()
+            {? Err("a name which is not reserved")}
+        / start_name:position!() name:ident() end_name:position!() _ op:$(("<<" / ">>" / ['+' | '-' | '*' | '/' | '%' | '&' | '|' | '^'])?) "=" !"=" _ value:expression()
+            {StatementType::Assign(name, Range(module.clone(), start_name, end_name), if (op.is_empty()) {None} else {Some(String::from(op))}, value)}
+        / "break" !ident_char() value:(_ value:expression() {value})?
+            {StatementType::Break(value)}
+        / "continue" !ident_char()
+            {StatementType::Continue}
+        / "return" !ident_char() value:(_ value:expression() {value})?
+            {StatementType::Return(value)}
         / expr:expression()
             {StatementType::Expression(expr)}
         ) end:position!() {Statement {
@@ -91,85 +451,127 @@ parser! {grammar parser(module : &Vec<String>) for str {
 
 
     rule expression() -> Expression
-        = expr:expression_compare()
+        = expr:expression_or(true)
             {expr}
 
+    /// An expression which can not contain a struct literal outside of any brackets,
+    /// so that `for i in 0..n {}` does not read `n {}` as a struct.
+    rule expression_no_struct() -> Expression
+        = expr:expression_or(false)
+            {expr}
 
-    rule expression_compare() -> Expression
-    = left:expression_addition() _ ops:(op:$("==" / "!=" / ">" / ">=" / "<" / "<=") _ right:expression_addition() _ {(op, right)})*
-        {
-            let mut left = left;
-            for (op, right) in ops {
-                let range = Range(module.clone(), left.range.1, right.range.2);
-                left = Expression {
-                    expr : match (op) {
-                        "==" => ExpressionType::EqualsOperation(Box::new(left), Box::new(right)),
-                        "!=" => ExpressionType::NotEqualsOperation(Box::new(left), Box::new(right)),
-                        ">"  => ExpressionType::GreaterOperation(Box::new(left), Box::new(right)),
-                        ">=" => ExpressionType::GreaterEqualsOperation(Box::new(left), Box::new(right)),
-                        "<"  => ExpressionType::LessOperation(Box::new(left), Box::new(right)),
-                        "<=" => ExpressionType::LessEqualsOperation(Box::new(left), Box::new(right)),
-                        _    => panic!("INTERNAL ERROR")
-                    },
-                    range
-                }
-            }
-            left
-        }
 
-    rule expression_addition() -> Expression
-    = left:expression_multiply() _ ops:(op:$("+" / "-") _ right:expression_multiply() _ {(op, right)})*
-        {
-            let mut left = left;
-            for (op, right) in ops {
-                let range = Range(module.clone(), left.range.1, right.range.2);
-                left = Expression {
-                    expr : match (op) {
-                        "+" => ExpressionType::AdditionOperation(Box::new(left), Box::new(right)),
-                        "-" => ExpressionType::SubtractionOperation(Box::new(left), Box::new(right)),
-                        _   => panic!("INTERNAL ERROR")
-                    },
-                    range
-                }
-            }
-            left
-        }
+    rule expression_or(structs : bool) -> Expression
+        = left:expression_and(structs) ops:(_ op:$("||") _ right:expression_and(structs) {(op, right)})*
+            {binary_operations(module, left, ops)}
+
+    rule expression_and(structs : bool) -> Expression
+        = left:expression_compare(structs) ops:(_ op:$("&&") _ right:expression_compare(structs) {(op, right)})*
+            {binary_operations(module, left, ops)}
+
+    rule expression_compare(structs : bool) -> Expression
+        = left:expression_bitor(structs) ops:(_ op:$("==" / "!=" / ">=" / ">" / "<=" / "<") _ right:expression_bitor(structs) {(op, right)})*
+            {binary_operations(module, left, ops)}
+
+    rule expression_bitor(structs : bool) -> Expression
+        = left:expression_bitxor(structs) ops:(_ op:$("|" !"|") _ right:expression_bitxor(structs) {(op, right)})*
+            {binary_operations(module, left, ops)}
+
+    rule expression_bitxor(structs : bool) -> Expression
+        = left:expression_bitand(structs) ops:(_ op:$("^") _ right:expression_bitand(structs) {(op, right)})*
+            {binary_operations(module, left, ops)}
+
+    rule expression_bitand(structs : bool) -> Expression
+        = left:expression_shift(structs) ops:(_ op:$("&" !"&") _ right:expression_shift(structs) {(op, right)})*
+            {binary_operations(module, left, ops)}
 
-    rule expression_multiply() -> Expression
-        = left:atom() _ ops:(op:$("*" / "/") _ right:atom() _ {(op, right)})*
+    rule expression_shift(structs : bool) -> Expression
+        = left:expression_addition(structs) ops:(_ op:$("<<" / ">>") _ right:expression_addition(structs) {(op, right)})*
+            {binary_operations(module, left, ops)}
+
+    rule expression_addition(structs : bool) -> Expression
+        = left:expression_multiply(structs) ops:(_ op:$("+" / "-") _ right:expression_multiply(structs) {(op, right)})*
+            {binary_operations(module, left, ops)}
+
+    rule expression_multiply(structs : bool) -> Expression
+        = left:expression_unary(structs) ops:(_ op:$("*" / "/" / "%") _ right:expression_unary(structs) {(op, right)})*
+            {binary_operations(module, left, ops)}
+
+    rule expression_unary(structs : bool) -> Expression
+        = start:position!() op:$("!" / "-") _ expr:expression_unary(structs) end:position!()
+            {Expression {
+                expr  : match (op) {
+                    "!" => ExpressionType::NotOperation(Box::new(expr)),
+                    "-" => ExpressionType::NegateOperation(Box::new(expr)),
+                    _   => panic!("INTERNAL ERROR")
+                },
+                range : Range(module.clone(), start, end)
+            }}
+        / expr:expression_field(structs)
+            {expr}
+
+    rule expression_field(structs : bool) -> Expression
+        = atom:atom(structs) postfixes:(_ postfix:expression_postfix() {postfix})*
             {
-                let     left_range = left.range.clone();
-                let mut left       = Expression {
-                    expr  : ExpressionType::Atom(left),
-                    range : left_range
+                let mut expr = Expression {
+                    range : atom.range.clone(),
+                    expr  : ExpressionType::Atom(atom)
                 };
-                for (op, right) in ops {
-                    let right_range = right.range.clone();
-                    let right       = Expression {
-                        expr  : ExpressionType::Atom(right),
-                        range : right_range
-                    };
-                    let range = Range(module.clone(), left.range.1, right.range.2);
-                    left = Expression {
-                        expr : match (op) {
-                            "*" => ExpressionType::MultiplicationOperation(Box::new(left), Box::new(right)),
-                            "/" => ExpressionType::DivisionOperation(Box::new(left), Box::new(right)),
-                            _   => panic!("INTERNAL PANIC")
+                for postfix in postfixes {
+                    expr = match (postfix) {
+                        Postfix::Field(name, name_range, Some((args, end))) => Expression {
+                            range : Range(module.clone(), expr.range.1, end),
+                            expr  : ExpressionType::MethodCall(Box::new(expr), name, name_range, args)
                         },
-                        range
-                    }
+                        Postfix::Field(name, name_range, None) => Expression {
+                            range : Range(module.clone(), expr.range.1, name_range.2),
+                            expr  : ExpressionType::Field(Box::new(expr), name, name_range)
+                        },
+                        Postfix::Index(index, end) => Expression {
+                            range : Range(module.clone(), expr.range.1, end),
+                            expr  : ExpressionType::Index(Box::new(expr), Box::new(index))
+                        }
+                    };
                 }
-                left
+                expr
             }
 
+    /// A field of a struct or tuple, a method call, or an index.
+    rule expression_postfix() -> Postfix
+        = "." _ start:position!() name:(ident() / index:$(['0'..='9']+) {String::from(index)}) end:position!()
+          args:(_ "(" _ args:((arg:expression() _ {arg}) ** ("," _)) ("," _)? ")" args_end:position!() {(args, args_end)})?
+            {Postfix::Field(name, Range(module.clone(), start, end), args)}
+        / "[" _ index:expression() _ "]" end:position!()
+            {Postfix::Index(index, end)}
+
 
 
 
-    rule atom() -> Atom
+    rule atom(structs : bool) -> Atom
         = start:position!() atom:("(" _ expr:expression() _ ")"
             {AtomType::Expression(Box::new(expr))}
         / atom:atom_if()
             {atom}
+        / atom:atom_match()
+            {atom}
+        / atom:atom_while()
+            {atom}
+        / atom:atom_loop()
+            {atom}
+        / atom:atom_for()
+            {atom}
+        / "(" _ first:expression() _ "," _ rest:((value:expression() _ {value}) ** ("," _)) ("," _)? ")"
+            {AtomType::Tuple([vec![first], rest].concat())}
+        / "[" _ values:((value:expression() _ {value}) ** ("," _)) ("," _)? "]"
+            {AtomType::Array(values)}
+        / "list" !ident_char() _ "[" _ values:((value:expression() _ {value}) ** ("," _)) ("," _)? "]"
+            {AtomType::List(values)}
+        / atom:atom_call()
+            {atom}
+        / atom:atom_closure()
+            {atom}
+        / allow(structs) atom:atom_struct()
+            {atom}
         / lit:literal()
             {AtomType::Literal(lit)}
         ) end:position!() {Atom {
@@ -178,9 +580,9 @@ parser! {grammar parser(module : &Vec<String>) for str {
         }}
     
     rule atom_if() -> AtomType
-        = ifstart:position!() "if" _ "(" _ ifcondi:expression() _ ")" _ ifblock:block() ifend:position!() _
-          elf:(elifstart:position!() "elif" _ "(" _ elifcondi:expression() _ ")" _ elifblock:block() elifend:position!() _ {(Box::new(elifcondi), elifblock, Range(module.clone(), elifstart, elifend))})*
-          els:(elsestart:position!() "else" _ elseblock:block() elseend:position!() {(elseblock, Range(module.clone(), elsestart, elseend))})?
+        = ifstart:position!() "if" _ "(" _ ifcondi:expression() _ ")" _ ifblock:block() ifend:position!()
+          elf:(_ elifstart:position!() "elif" _ "(" _ elifcondi:expression() _ ")" _ elifblock:block() elifend:position!() {(Box::new(elifcondi), elifblock, Range(module.clone(), elifstart, elifend))})*
+          els:(_ elsestart:position!() "else" _ elseblock:block() elseend:position!() {(elseblock, Range(module.clone(), elsestart, elseend))})?
             {
                 let mut ifs  = vec![(Box::new(ifcondi), ifblock, Range(module.clone(), ifstart, ifend))];
                 let mut elf = elf;
@@ -192,22 +594,229 @@ parser! {grammar parser(module : &Vec<String>) for str {
             }
 
 
+    rule atom_match() -> AtomType
+        = "match" _ "(" _ value:expression() _ ")" _
+          "{" _ arms:((arm:atom_match_arm() _ {arm}) ** ("," _)) ("," _)? "}"
+            {AtomType::Match(Box::new(value), arms)}
+
+    rule atom_match_arm() -> (Pattern, Block, bool, Range)
+        = start:position!() pattern:pattern() _ "=>" _ body:(
+              block:block()
+                {(block, true)}
+            / expr:expression()
+                {(Block {
+                    range   : expr.range.clone(),
+                    stmts   : vec![Statement {
+                        range : expr.range.clone(),
+                        stmt  : StatementType::Expression(expr)
+                    }],
+                    retlast : true
+                }, false)}
+        ) end:position!()
+            {(pattern, body.0, body.1, Range(module.clone(), start, end))}
+
+
+    rule atom_while() -> AtomType
+        = "while" _ "(" _ condition:expression() _ ")" _ block:block()
+            {AtomType::While(Box::new(condition), block)}
+
+    rule atom_loop() -> AtomType
+        = "loop" _ block:block()
+            {AtomType::Loop(block)}
+
+    rule atom_for() -> AtomType
+        = "for" __ start_name:position!() name:ident() end_name:position!() __ "in" __
+          start:expression() _ ".." inclusive:"="? _ end:expression_no_struct() _ block:block()
+            {AtomType::For(
+                name,
+                Range(module.clone(), start_name, end_name),
+                Box::new(start),
+                Box::new(end),
+                inclusive.is_some(),
+                block
+            )}
+
+
+    rule atom_call() -> AtomType
+        = start:position!() path:(ident() ++ (_ "::" _)) end:position!() _
+          "(" _ args:((arg:expression() _ {arg}) ** ("," _)) ("," _)? ")"
+            {AtomType::Call(path, Range(module.clone(), start, end), args)}
+
+
+    rule atom_closure() -> AtomType
+        = "|" _ args:((arg:declaration_function_argument() _ {arg}) ** ("," _)) ("," _)? "|" _
+          ret:("->" _ ret:type_descriptor() _ {ret})?
+          block:block()
+            {AtomType::Closure(args, ret, block)}
+
+
+    rule atom_struct() -> AtomType
+        = start:position!() path:(ident() ++ (_ "::" _)) end:position!() _
+          "{" _ fields:((field:atom_struct_field() _ {field}) ** ("," _)) ("," _)? "}"
+            {AtomType::Struct(path, Range(module.clone(), start, end), fields)}
+
+    rule atom_struct_field() -> (String, Range, Expression)
+        = start:position!() name:ident() end:position!() _ ":" _ value:expression()
+            {(name, Range(module.clone(), start, end), value)}
+
+    /// Only matches if `allowed` is set.
+    rule allow(allowed : bool)
+        = quiet!{{? if (allowed) {Ok(())} else {Err("")}}}
+
+
     rule literal() -> Literal
-        = start:position!() lit:(ident:ident()
-            {LiteralType::Identifier(ident)}
-        / int:['0'..='9']+ dec:("." b:['0'..='9']+ {b})?
+        = start:position!() lit:("true" !ident_char()
+            {LiteralType::Bool(true)}
+        / "false" !ident_char()
+            {LiteralType::Bool(false)}
+        / "r" hashes:$("#"*) "\"" value:$((!raw_string_end(hashes.len()) [_])*) raw_string_end(hashes.len())
+            {LiteralType::RawString(hashes.len(), String::from(value))}
+        / "\"" text_start:position!() text:$(("\\" [_] / [^ '"' | '\\'])*) "\""
+            {LiteralType::String(String::from(text), unescape(text, text_start, literal_errors))}
+        / "'" text_start:position!() text:$(("\\" [_] / [^ '\'' | '\\' | '\n'])*) "'"
+            {
+                let value = unescape(text, text_start, literal_errors).chars().collect::<Vec<_>>();
+                if (value.len() != 1) {
+                    literal_errors.borrow_mut().insert(text_start - 1, (text_start + text.len() + 1,
+                        String::from("Character literals must contain exactly one character.")
+                    ));
+                }
+                LiteralType::Char(String::from(text), value.first().copied().unwrap_or('\u{FFFD}'))
+            }
+        / path:(ident() ++ (_ "::" _))
+            {if (path.len() == 1) {
+                LiteralType::Identifier(path[0].clone())
+            } else {
+                LiteralType::Path(path)
+            }}
+        / int:['0'..='9']+ dec:("." b:['0'..='9']+ {b})? suffix_start:position!() suffix:$(['u' | 'b']?)
             {if let Some(dec) = dec {
-                LiteralType::Float(int.into_iter().collect(), dec.into_iter().collect())
+                if (suffix == "b") {
+                    literal_errors.borrow_mut().insert(suffix_start, (suffix_start + 1,
+                        String::from("Float literals can only have the `u` suffix.")
+                    ));
+                }
+                LiteralType::Float(int.into_iter().collect(), dec.into_iter().collect(), String::from(suffix))
             } else {
-                LiteralType::Int(int.into_iter().collect())
+                LiteralType::Int(int.into_iter().collect(), String::from(suffix))
             }}
         ) end:position!() {Literal {
             lit,
             range : Range(module.clone(), start, end)
         }}
 
+    rule pattern() -> Pattern
+        = start:position!() pat:(
+              "_" !ident_char()
+                {PatternType::Wildcard}
+            / path_start:position!() path:(ident() **<2,> (_ "::" _)) path_end:position!() fields:(
+                  _ "(" _ patterns:((pattern:pattern() _ {pattern}) ** ("," _)) ("," _)? ")"
+                    {VariantPatterns::Tuple(patterns)}
+                / _ "{" _ fields:((field:pattern_field() _ {field}) ** ("," _)) ("," _)? "}"
+                    {VariantPatterns::Named(fields)}
+                / {VariantPatterns::Unit}
+            )
+                {PatternType::Variant(path, Range(module.clone(), path_start, path_end), fields)}
+            / "(" _ first:pattern() _ "," _ rest:((pattern:pattern() _ {pattern}) ** ("," _)) ("," _)? ")"
+                {PatternType::Tuple([vec![first], rest].concat())}
+            / "[" _ patterns:((pattern:pattern() _ {pattern}) ** ("," _)) ("," _)? "]"
+                {PatternType::Array(patterns)}
+            / lit:literal()
+                {if let LiteralType::Identifier(name) = lit.lit {
+                    PatternType::Binding(name)
+                } else {
+                    PatternType::Literal(lit)
+                }}
+        ) end:position!()
+            {Pattern {
+                pat,
+                range : Range(module.clone(), start, end)
+            }}
+
+    rule pattern_field() -> (String, Range, Option<Pattern>)
+        = start:position!() name:ident() end:position!() pattern:(_ ":" _ pattern:pattern() {pattern})?
+            {(name, Range(module.clone(), start, end), pattern)}
+
+    rule raw_string_end(hashes : usize)
+        = "\"" "#"*<{hashes}>
+
+    rule type_descriptor() -> TypeDescriptor
+        = start:position!() "|" _ args:((arg:type_descriptor() _ {arg}) ** ("," _)) ("," _)? "|"
+          ret:(_ "->" _ ret:type_descriptor() {Box::new(ret)})? end:position!()
+            {TypeDescriptor {
+                parts  : TypeDescriptorParts::Function(args, ret),
+                constr : HashMap::new(),
+                range  : Range(module.clone(), start, end)
+            }}
+        / start:position!() parts:(
+              "(" _ first:type_descriptor() _ "," _ rest:((typ:type_descriptor() _ {typ}) ** ("," _)) ("," _)? ")"
+                {TypeDescriptorParts::Tuple([vec![first], rest].concat())}
+            / "[" _ element:type_descriptor() _ ";" _ length:$(['0'..='9']+) _ "]"
+                {? length.parse().map(|length| TypeDescriptorParts::Array(Box::new(element), length)).or(Err("array length"))}
+            / "list" !ident_char() _ "[" _ element:type_descriptor() _ "]"
+                {TypeDescriptorParts::List(Box::new(element))}
+        ) end:position!()
+            {TypeDescriptor {
+                parts,
+                constr : HashMap::new(),
+                range  : Range(module.clone(), start, end)
+            }}
+        / start:position!() typ:type_descriptor_named() _ "<" _
+          constr:((constr:type_constraint() _ {constr}) ++ ("," _)) ("," _)? ">" end:position!()
+            {?
+                let count = constr.len();
+                let map   = constr.into_iter().collect::<HashMap<_, _>>();
+                if (map.len() == count) {
+                    Ok(TypeDescriptor {
+                        constr : map,
+                        range  : Range(module.clone(), start, end),
+                        ..typ
+                    })
+                } else {
+                    Err("each constraint only once")
+                }
+            }
+        / start:position!() typ:type_descriptor_named()
+          args:(_ "<" _ args:((arg:type_descriptor() _ {arg}) ++ ("," _)) ("," _)? ">" {args})? end:position!()
+            {
+                match (args) {
+                    Some(args) => TypeDescriptor {
+                        parts  : TypeDescriptorParts::Generic(Box::new(typ), args),
+                        constr : HashMap::new(),
+                        range  : Range(module.clone(), start, end)
+                    },
+                    None => typ
+                }
+            }
+
+    rule type_constraint() -> (String, Literal)
+        = name:ident() _ "=" _ lit:(
+              start:position!() "-" _ int:$(['0'..='9']+) end:position!()
+                {Literal {
+                    lit   : LiteralType::Int(format!("-{}", int), String::new()),
+                    range : Range(module.clone(), start, end)
+                }}
+            / literal()
+        )
+            {(name, lit)}
+
+    rule type_descriptor_named() -> TypeDescriptor
+        = start:position!() parts:(ident() ++ (_ "::" _)) end:position!()
+            {
+                let parts = if (parts.len() == 1) {
+                    TypeDescriptorParts::BuiltIn(parts[0].clone())
+                } else {
+                    TypeDescriptorParts::Custom(parts)
+                };
+                TypeDescriptor {
+                    parts,
+                    constr : HashMap::new(),
+                    range  : Range(module.clone(), start, end)
+                }
+            }
+
     rule block() -> Block
-        = start:position!() "{" _ b:(s:((_ s:statement() _ {s}) ++ ";") r:";"? {(s, r)})? _ "}" end:position!()
+        = start:position!() "{" _ b:(s:((_ s:statement_or_skip() _ {s}) ++ ";") r:";"? {(s, r)})? _ "}" end:position!()
             {
                 let range = Range(module.clone(), start, end);
                 if let Some(body) = b {
@@ -226,7 +835,17 @@ parser! {grammar parser(module : &Vec<String>) for str {
             }
 
     rule ident() -> String
-        = i:$(['a'..='z' | 'A'..='Z'] ['a'..='z' | 'A'..='Z' | '0'..='9' | '_']*) {String::from(i)}
+        = !reserved() i:$(['a'..='z' | 'A'..='Z'] ident_char()*) {String::from(i)}
+    /// Keywords, which can not be used as names.
+    rule reserved()
+        = (
+              "true" / "false" / "if" / "elif" / "else" / "let" / "mut" / "fn" / "return"
+            / "while" / "loop" / "for" / "in" / "break" / "continue"
+            / "struct" / "enum" / "match" / "trait" / "impl" / "list"
+            / "pub" / "priv" / "mod" / "use" / "as"
+        ) !ident_char()
+    rule ident_char()
+        = ['a'..='z' | 'A'..='Z' | '0'..='9' | '_']
 
 
     rule _()
@@ -236,9 +855,11 @@ parser! {grammar parser(module : &Vec<String>) for str {
     rule ___()
         = quiet! {
               [' ' | '\t' | '\r' | '\n']
-            / "//" single_comment()
-            / "/*" multi_comment()
+            / comment()
         }
+    rule comment()
+        = start:position!() text:$("//" single_comment() / "/*" multi_comment()) end:position!()
+            {comments.borrow_mut().insert(start, (end, text));}
     rule single_comment()
         = (
               "/*" multi_comment()
@@ -3,9 +3,19 @@ pub mod node;
     mod node_fmt;
 pub mod config;
 
-use std::fs::read_to_string;
+pub(crate) use node_fmt::source;
+
+use std::{
+    collections::HashMap,
+    fs::read_to_string
+};
 
 use relative_path::RelativePathBuf;
+use static_init::dynamic;
+use peg::{
+    error::ParseError,
+    str::LineCol
+};
 
 use crate::{
     parse::node::{
@@ -17,12 +27,39 @@ use crate::{
 };
 
 
-fn read(importer : &Option<Range>, base : &RelativePathBuf, module : &Vec<String>) -> Option<String> {
+/// Scripts which have not been saved yet, by path.
+/// These are read instead of the files on disk.
+/// Used by the language server.
+#[dynamic]
+static mut UNSAVED : HashMap<String, String> = HashMap::new();
+
+/// Set the script of a path which has not been saved yet.
+/// If `None`, the file on disk is read again.
+pub(crate) fn set_unsaved(path : &RelativePathBuf, script : Option<String>) {
+    let mut lock = UNSAVED.write();
+    match (script) {
+        Some(script) => {lock.insert(path.to_string(), script);},
+        None         => {lock.remove(path.as_str());}
+    }
+}
+
+
+/// Get the path of the script of a module.
+pub(crate) fn module_path(base : &RelativePathBuf, module : &Vec<String>) -> RelativePathBuf {
     let mut path = base.clone();
     for part in module {
         path.push(part);
     }
-    return match (read_to_string(&path.with_extension("vsv").as_str())) {
+    return path.with_extension("vsv");
+}
+
+
+fn read(importer : &Option<Range>, base : &RelativePathBuf, module : &Vec<String>) -> Option<String> {
+    let path = module_path(base, module);
+    if let Some(script) = UNSAVED.read().get(path.as_str()) {
+        return Some(script.clone());
+    }
+    return match (read_to_string(path.as_str())) {
         Ok(script) => Some(script),
         Err(error) => {
             push_error!(ModuleNotFound, Always, {
@@ -35,26 +72,46 @@ fn read(importer : &Option<Range>, base : &RelativePathBuf, module : &Vec<String
 }
 
 fn parse(text : &str, module : Vec<String>) -> Option<Program> {
-    return grammer::parse(text.into(), &module)
-        .map_err(|e| {push_error!(UnexpectedToken, Always, {
-            Some(Range(module, e.location.offset, e.location.offset)) => {"{}.", {
-                let mut tokens = e.expected.tokens().collect::<Vec<_>>();
-                if (tokens.len() == 1) {
-                    format!("Expected {}", tokens[0])
-                } else if (tokens.len() == 2) {
-                    format!("Expected \x1b[91m{}\x1b[31m or \x1b[91m{}\x1b[31m", tokens[0], tokens[1])
-                } else {
-                    let last = tokens.remove(tokens.len() - 1);
-                    format!("Expected one of {}, or \x1b[91m{}\x1b[31m",
-                        tokens.iter()
-                            .map(|token| format!("\x1b[91m{}\x1b[31m", token))
-                            .collect::<Vec<_>>().join(", "),
-                        last
-                    )
-                }
-            }}
-        }); e})
-        .ok();
+    return match (grammer::parse(text.into(), &module)) {
+        Ok((program, errors, literal_errors)) => {
+            // Declarations and statements which failed to parse were skipped.
+            for error in &errors {
+                push_unexpected_token(&module, error);
+            }
+            for (range, message) in literal_errors {
+                push_error!(InvalidLiteral, Always, {
+                    Some(range) => {"{}", message}
+                });
+            }
+            Some(program)
+        },
+        Err(error) => {
+            push_unexpected_token(&module, &error);
+            None
+        }
+    };
+}
+
+/// Push an error showing what the parser expected to find.
+fn push_unexpected_token(module : &Vec<String>, e : &ParseError<LineCol>) {
+    push_error!(UnexpectedToken, Always, {
+        Some(Range(module.clone(), e.location.offset, e.location.offset)) => {"{}.", {
+            let mut tokens = e.expected.tokens().collect::<Vec<_>>();
+            if (tokens.len() == 1) {
+                format!("Expected {}", tokens[0])
+            } else if (tokens.len() == 2) {
+                format!("Expected \x1b[91m{}\x1b[31m or \x1b[91m{}\x1b[31m", tokens[0], tokens[1])
+            } else {
+                let last = tokens.remove(tokens.len() - 1);
+                format!("Expected one of {}, or \x1b[91m{}\x1b[31m",
+                    tokens.iter()
+                        .map(|token| format!("\x1b[91m{}\x1b[31m", token))
+                        .collect::<Vec<_>>().join(", "),
+                    last
+                )
+            }
+        }}
+    });
 }
 
 
@@ -8,7 +8,7 @@ use relative_path::RelativePathBuf;
 use line_col::LineColLookup;
 
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Range(pub Vec<String>, pub usize, pub usize);
 impl Range {
     pub fn to_linecolumn(&self, script : &String) -> LineColumn {
@@ -27,7 +27,15 @@ pub struct LineColumn(pub Vec<String>, pub (usize, usize), pub (usize, usize));
 
 #[derive(Debug)]
 pub(crate) struct Program {
-    pub(crate) decls : Vec<Declaration>
+    pub(crate) decls    : Vec<Declaration>,
+    pub(crate) comments : Vec<Comment>         // In order of appearance
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Comment {
+    pub(crate) text     : String,
+    pub(crate) own_line : bool,                // Nothing else comes before it on its line
+    pub(crate) range    : Range
 }
 
 
@@ -38,6 +46,12 @@ pub(crate) struct Declaration {
     pub(crate) decl    : DeclarationType,
     pub(crate) range   : Range
 }
+impl Declaration {
+    /// Where the declaration starts, including its headers and visibility.
+    pub fn start(&self) -> usize {
+        return self.headers.first().map_or(self.vis.range.1, |header| header.range.1);
+    }
+}
 
 #[derive(Debug)]
 pub(crate) struct DeclarationHeader {
@@ -66,16 +80,80 @@ pub(crate) enum DeclarationType {
         Vec<String>,
         Range
     ),
+    Use(
+        Vec<(String, Range)>,                          // Base Path (Part, Part Range)
+        Vec<(String, Range, Option<(String, Range)>)>, // Items (Name, Name Range, Alias)
+        bool                                           // Braced
+    ),
     Function(
-        String,                        // Name
-        Range,                         // Name Range
-        Vec<(String, TypeDescriptor)>, // Arguments
-        Option<TypeDescriptor>,        // Return
-        Block                          // Block
+        String,                               // Name
+        Range,                                // Name Range
+        Vec<Generic>,                         // Type Parameters
+        Vec<(String, Range, TypeDescriptor)>, // Arguments (Name, Name Range, Type)
+        Option<TypeDescriptor>,               // Return
+        Block                                 // Block
+    ),
+    Struct(
+        String,                                                     // Name
+        Range,                                                      // Name Range
+        Vec<Generic>,                                               // Type Parameters
+        Vec<(DeclarationVisibility, String, Range, TypeDescriptor)> // Fields (Visibility, Name, Name Range, Type)
+    ),
+    Enum(
+        String,                             // Name
+        Range,                              // Name Range
+        Vec<Generic>,                       // Type Parameters
+        Vec<(String, Range, VariantFields)> // Variants (Name, Name Range, Fields)
+    ),
+    Trait(
+        String,     // Name
+        Range,      // Name Range
+        Vec<Method> // Methods, without bodies
+    ),
+    Impl(
+        Vec<String>,    // Trait Path
+        Range,          // Trait Path Range
+        TypeDescriptor, // Type
+        Vec<Method>     // Methods
+    ),
+    /// A declaration which failed to parse. Already reported.
+    Error(
+        Option<(String, String, Range)> // Keyword, Name and Name Range, if they could be found
     )
 }
 
 
+/// A type parameter of a generic declaration, such as `T : Display`.
+#[derive(Debug, Clone)]
+pub(crate) struct Generic {
+    pub(crate) name   : String,
+    pub(crate) range  : Range,
+    pub(crate) bounds : Vec<(Vec<String>, Range)> // Traits that its type must implement (Path, Path Range)
+}
+
+
+/// The values that an enum variant holds.
+#[derive(Debug, Clone)]
+pub(crate) enum VariantFields {
+    Unit,
+    Tuple(Vec<TypeDescriptor>),
+    Named(Vec<(String, Range, TypeDescriptor)>) // Fields (Name, Name Range, Type)
+}
+
+
+/// A method declared in a trait or an impl. Always takes `self` first.
+#[derive(Debug, Clone)]
+pub(crate) struct Method {
+    pub(crate) name       : String,
+    pub(crate) name_range : Range,
+    pub(crate) receiver   : Range,                                // Range of `self`
+    pub(crate) args       : Vec<(String, Range, TypeDescriptor)>, // Arguments after `self` (Name, Name Range, Type)
+    pub(crate) ret        : Option<TypeDescriptor>,
+    pub(crate) block      : Option<Block>,                        // Not given in traits
+    pub(crate) range      : Range
+}
+
+
 #[derive(Debug, Clone)]
 pub(crate) struct Statement {
     pub(crate) stmt  : StatementType,
@@ -84,11 +162,32 @@ pub(crate) struct Statement {
 #[derive(Debug, Clone)]
 pub(crate) enum StatementType {
     InitVar(
-        String,    // Name
-        Range,     // Name Range
+        bool,                   // Mutable
+        String,                 // Name
+        Range,                  // Name Range
+        Option<TypeDescriptor>, // Declared Type
+        Expression              // Value
+    ),
+    Assign(
+        String,         // Name
+        Range,          // Name Range
+        Option<String>, // Operator, if compound
+        Expression      // Value
+    ),
+    /// A `let` which takes apart a tuple or an array, such as `let (a, b) = pair`.
+    Destructure(
+        bool,      // Mutable
+        Pattern,   // Pattern
         Expression // Value
     ),
-    Expression(Expression)
+    Break(Option<Expression>),
+    Continue,
+    Return(Option<Expression>),
+    Expression(Expression),
+    /// A statement which failed to parse. Already reported.
+    Error(
+        Option<(bool, String, Range)> // Mutable, Name and Name Range, if it was a `let`
+    )
 }
 
 #[derive(Debug, Clone)]
@@ -96,9 +195,30 @@ pub(crate) struct Expression {
     pub(crate) expr  : ExpressionType,
     pub(crate) range : Range
 }
+impl Expression {
+    /// Expand a compound assignment, such as `x += 1`, into the value that it stores.
+    pub fn compound(name : &String, name_range : &Range, op : &str, value : &Expression, range : &Range) -> Self {
+        let current = Expression {
+            expr  : ExpressionType::Atom(Atom {
+                atom  : AtomType::Literal(Literal {
+                    lit   : LiteralType::Identifier(name.clone()),
+                    range : name_range.clone()
+                }),
+                range : name_range.clone()
+            }),
+            range : name_range.clone()
+        };
+        return Expression {
+            expr  : ExpressionType::binary(op, current, value.clone()),
+            range : range.clone()
+        };
+    }
+}
 #[derive(Debug, Clone)]
 pub(crate) enum ExpressionType {
 
+    LogicalOrOperation(Box<Expression>, Box<Expression>),
+    LogicalAndOperation(Box<Expression>, Box<Expression>),
     EqualsOperation(Box<Expression>, Box<Expression>),
     NotEqualsOperation(Box<Expression>, Box<Expression>),
     GreaterOperation(Box<Expression>, Box<Expression>),
@@ -109,9 +229,62 @@ pub(crate) enum ExpressionType {
     SubtractionOperation(Box<Expression>, Box<Expression>),
     MultiplicationOperation(Box<Expression>, Box<Expression>),
     DivisionOperation(Box<Expression>, Box<Expression>),
+    ModuloOperation(Box<Expression>, Box<Expression>),
+    BitwiseOrOperation(Box<Expression>, Box<Expression>),
+    BitwiseXorOperation(Box<Expression>, Box<Expression>),
+    BitwiseAndOperation(Box<Expression>, Box<Expression>),
+    ShiftLeftOperation(Box<Expression>, Box<Expression>),
+    ShiftRightOperation(Box<Expression>, Box<Expression>),
+
+    NotOperation(Box<Expression>),
+    NegateOperation(Box<Expression>),
+
+    Field(
+        Box<Expression>, // Value
+        String,          // Field Name
+        Range            // Field Range
+    ),
+    MethodCall(
+        Box<Expression>, // Receiver
+        String,          // Method Name
+        Range,           // Method Name Range
+        Vec<Expression>  // Arguments
+    ),
+    Index(
+        Box<Expression>, // Value
+        Box<Expression>  // Index
+    ),
 
     Atom(Atom)
 }
+impl ExpressionType {
+    /// Create a binary operation from its operator.
+    pub fn binary(op : &str, left : Expression, right : Expression) -> Self {
+        let left  = Box::new(left);
+        let right = Box::new(right);
+        return match (op) {
+            "||" => Self::LogicalOrOperation(left, right),
+            "&&" => Self::LogicalAndOperation(left, right),
+            "==" => Self::EqualsOperation(left, right),
+            "!=" => Self::NotEqualsOperation(left, right),
+            ">"  => Self::GreaterOperation(left, right),
+            ">=" => Self::GreaterEqualsOperation(left, right),
+            "<"  => Self::LessOperation(left, right),
+            "<=" => Self::LessEqualsOperation(left, right),
+            "|"  => Self::BitwiseOrOperation(left, right),
+            "^"  => Self::BitwiseXorOperation(left, right),
+            "&"  => Self::BitwiseAndOperation(left, right),
+            "<<" => Self::ShiftLeftOperation(left, right),
+            ">>" => Self::ShiftRightOperation(left, right),
+            "+"  => Self::AdditionOperation(left, right),
+            "-"  => Self::SubtractionOperation(left, right),
+            "*"  => Self::MultiplicationOperation(left, right),
+            "/"  => Self::DivisionOperation(left, right),
+            "%"  => Self::ModuloOperation(left, right),
+            _    => panic!("INTERNAL ERROR")
+        };
+    }
+}
 
 
 #[derive(Debug, Clone)]
@@ -133,9 +306,76 @@ pub(crate) enum AtomType {
             Block, // Else block
             Range
         )>
+    ),
+    Call(
+        Vec<String>,    // Path
+        Range,          // Path Range
+        Vec<Expression> // Arguments
+    ),
+    While(
+        Box<Expression>, // Condition
+        Block
+    ),
+    Loop(Block),
+    For(
+        String,          // Name
+        Range,           // Name Range
+        Box<Expression>, // Start
+        Box<Expression>, // End
+        bool,            // Inclusive
+        Block
+    ),
+    Struct(
+        Vec<String>,                      // Path
+        Range,                            // Path Range
+        Vec<(String, Range, Expression)>  // Fields (Name, Name Range, Value)
+    ),
+    Array(Vec<Expression>),
+    Tuple(Vec<Expression>),
+    List(Vec<Expression>),
+    Closure(
+        Vec<(String, Range, TypeDescriptor)>, // Arguments (Name, Name Range, Type)
+        Option<TypeDescriptor>,               // Return. Found from the body if not given.
+        Block                                 // Body
+    ),
+    Match(
+        Box<Expression>, // Value
+        Vec<(
+            Pattern,
+            Block,       // Body
+            bool,        // Braced. If not, the body is a single expression.
+            Range
+        )>
     )
 }
 
+#[derive(Debug, Clone)]
+pub(crate) struct Pattern {
+    pub(crate) pat   : PatternType,
+    pub(crate) range : Range
+}
+#[derive(Debug, Clone)]
+pub(crate) enum PatternType {
+    /// `_`, which matches anything.
+    Wildcard,
+    /// Matches anything, and stores it in a variable.
+    Binding(String),
+    Literal(Literal),
+    Variant(
+        Vec<String>,    // Path
+        Range,          // Path Range
+        VariantPatterns // Fields
+    ),
+    Tuple(Vec<Pattern>),
+    Array(Vec<Pattern>)
+}
+#[derive(Debug, Clone)]
+pub(crate) enum VariantPatterns {
+    Unit,
+    Tuple(Vec<Pattern>),
+    Named(Vec<(String, Range, Option<Pattern>)>) // Fields (Name, Name Range, Pattern). Binds the name if no pattern is given.
+}
+
 #[derive(Debug, Clone)]
 pub(crate) struct Literal {
     pub(crate) lit   : LiteralType,
@@ -143,25 +383,62 @@ pub(crate) struct Literal {
 }
 #[derive(Debug, Clone)]
 pub(crate) enum LiteralType {
-    Int(String),
+    Int(
+        String, // Integer
+        String  // Suffix. Empty if there is none.
+    ),
     Float(
         String, // Integer
-        String  // Decimal
+        String, // Decimal
+        String  // Suffix. Empty if there is none.
     ),
-    Identifier(String)
+    Bool(bool),
+    String(
+        String, // As written
+        String  // Value
+    ),
+    RawString(
+        usize,  // Number of `#`
+        String  // Value
+    ),
+    Char(
+        String, // As written
+        char    // Value
+    ),
+    Identifier(String),
+    /// A path with more than one part, such as `Shape::Empty`.
+    Path(Vec<String>)
 }
 
 
 #[derive(Debug, Clone)]
 pub(crate) enum TypeDescriptorParts {
-    BuiltIn(String),
-    Custom(Vec<String>)
+    BuiltIn(String),            // Single name, such as `int`.
+    Custom(Vec<String>),        // Path, such as `module::Type`.
+    Tuple(Vec<TypeDescriptor>), // Tuple, such as `(int, bool)`.
+    List(Box<TypeDescriptor>),  // List, such as `list[int]`.
+    /// Array, such as `[int; 3]`.
+    Array(
+        Box<TypeDescriptor>, // Element
+        usize                // Length
+    ),
+    /// Function, such as `|int, int| -> int`.
+    Function(
+        Vec<TypeDescriptor>,        // Arguments
+        Option<Box<TypeDescriptor>> // Return
+    ),
+    /// Generic type with type arguments, such as `Pair<int, bool>`.
+    Generic(
+        Box<TypeDescriptor>, // Type
+        Vec<TypeDescriptor>  // Arguments
+    )
 }
 
 #[derive(Debug, Clone)]
 pub(crate) struct TypeDescriptor {
     pub(crate) parts  : TypeDescriptorParts,
-    pub(crate) constr : HashMap<String, Literal>
+    pub(crate) constr : HashMap<String, Literal>,
+    pub(crate) range  : Range
 }
 
 
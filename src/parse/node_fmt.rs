@@ -1,22 +1,186 @@
-use std::fmt::{
-    Display,
-    Formatter,
-    Result,
-    Debug
+use std::{
+    fmt::{
+        Display,
+        Formatter,
+        Result,
+        Debug
+    },
+    cell::RefCell,
+    collections::VecDeque
 };
 
 use crate::parse::node::*;
 
 
 
-const INDENT      : &'static str = "  ";
+const INDENT        : &'static str = "  ";
+const SOURCE_INDENT : &'static str = "    ";
 
 const HEADER      : Colour       = Colour( 124 , 166 , 104 , true  );
 const KEYWORD     : Colour       = Colour( 197 , 134 , 192 , false );
 const OBJECT      : Colour       = Colour( 86  , 156 , 214 , false );
 const NAME        : Colour       = Colour( 220 , 220 , 170 , false );
-//const LIT_STRING  : Colour       = Colour( 206 , 145 , 120 , false );
+const TYPE        : Colour       = Colour( 78  , 201 , 176 , false );
+const LIT_STRING  : Colour       = Colour( 206 , 145 , 120 , false );
 const LIT_NUMERIC : Colour       = Colour( 181 , 206 , 168 , false );
+const COMMENT     : Colour       = Colour( 106 , 153 , 85  , false );
+const ERROR       : Colour       = Colour( 244 , 71  , 71  , false );
+
+
+thread_local! {
+    /// Set while formatting source code for `vesuvius fmt`.
+    /// If `None`, nodes are formatted with colours for the terminal.
+    static SOURCE : RefCell<Option<Source>> = RefCell::new(None);
+}
+
+struct Source {
+    text     : String,
+    comments : VecDeque<Comment>   // Comments which have not been written yet
+}
+
+
+/// Format a program as canonical, colour-free source code.
+/// `text` is the script that the program was parsed from.
+pub(crate) fn source(program : &Program, text : &str) -> String {
+    SOURCE.with(|source| *source.borrow_mut() = Some(Source {
+        text     : String::from(text),
+        comments : program.comments.iter().cloned().collect()
+    }));
+    let text = program.to_string();
+    SOURCE.with(|source| *source.borrow_mut() = None);
+    return text;
+}
+
+fn is_source() -> bool {
+    return SOURCE.with(|source| source.borrow().is_some());
+}
+
+/// The indentation for a certain depth.
+/// Source code is indented further than terminal output.
+fn indentation(depth : usize) -> String {
+    return (if (is_source()) {SOURCE_INDENT} else {INDENT}).repeat(depth);
+}
+
+/// Format a binary operation. Terminal output wraps every
+/// operation in parentheses to show how it was parsed, while
+/// source code only keeps the parentheses that were written.
+fn binary(left : &Expression, op : &str, right : &Expression, indent : usize) -> String {
+    let left_text = left.format(indent);
+    // Comments between the left operand and the operator stay before the operator.
+    let comments  = inline_comments(operator_position(left.range.2, right.range.1, op))
+        .into_iter()
+        .map(|comment| format!(" {}", comment))
+        .collect::<String>();
+    let text = format!("{}{} {} {}", left_text, comments, op, right.format(indent));
+    return if (is_source()) {text} else {format!("({})", text)};
+}
+
+/// Find where an operator is written between `start` and `end` in the source code, skipping any comments.
+fn operator_position(start : usize, end : usize, op : &str) -> usize {
+    return SOURCE.with(|source| {
+        let source = source.borrow();
+        let Some(source) = source.as_ref() else {return start};
+        let mut pos = start;
+        while (pos < end) {
+            if let Some(comment) = source.comments.iter().find(|comment| comment.range.1 == pos) {
+                pos = comment.range.2;
+            } else if (source.text[pos..].starts_with(op)) {
+                return pos;
+            } else {
+                pos += source.text[pos..].chars().next().map_or(1, char::len_utf8);
+            }
+        }
+        return start;
+    });
+}
+
+/// What goes between the closing brace of a block and the `elif` or `else` after it.
+/// Source code keeps them on the same line, while terminal output starts a new line.
+fn chain_separator(indent : usize) -> String {
+    return if (is_source()) {String::from(" ")} else {format!("\n{}", indentation(indent))};
+}
+
+/// Write all comments which start before `pos` on their own lines,
+/// keeping the empty lines between them.
+/// Block comments on the same line as `pos` are left for `inline_comments`.
+fn leading_comments(pos : usize, indent : usize) -> String {
+    let mut text = String::new();
+    let mut prev = None;
+    while let Some(comment) = next_comment(|comment, source| comment.range.1 < pos && ! inline(comment, source, pos)) {
+        if let Some(end) = prev {
+            if (blank_line_between(end, comment.range.1)) {text += "\n";}
+        }
+        text += &format!("{}{}\n", indentation(indent), c!(COMMENT, comment.text));
+        prev = Some(comment.range.2);
+    }
+    if let Some(end) = prev {
+        if (blank_line_between(end, pos)) {text += "\n";}
+    }
+    return text;
+}
+
+/// Write the comments which start before `pos`
+/// and follow some code on the same line.
+/// Nothing can be written after a line comment, so it stops there.
+fn trailing_comment(pos : usize) -> String {
+    let mut text = String::new();
+    while let Some(comment) = next_comment(|comment, _| ! comment.own_line && comment.range.1 < pos) {
+        text += &format!(" {}", c!(COMMENT, comment.text));
+        if (comment.text.starts_with("//")) {break;}
+    }
+    return text;
+}
+
+/// Take the block comments which start before `pos` on the same line as it,
+/// so that they can be written where they were, instead of on their own line
+/// or at the end of the line.
+fn inline_comments(pos : usize) -> Vec<String> {
+    let mut comments = Vec::new();
+    while let Some(comment) = next_comment(|comment, source| comment.range.1 < pos && inline(comment, source, pos)) {
+        comments.push(c!(COMMENT, comment.text));
+    }
+    return comments;
+}
+
+/// Write the comments from `inline_comments` in front of the code at `pos`.
+fn inline_prefix(pos : usize) -> String {
+    return inline_comments(pos).into_iter().map(|comment| format!("{} ", comment)).collect();
+}
+
+/// Check if a comment is a block comment with nothing but whitespace
+/// and other comments between it and `pos` on the same line.
+fn inline(comment : &Comment, source : &str, pos : usize) -> bool {
+    return comment.text.starts_with("/*") && source.get(comment.range.2..pos).is_some_and(|between| ! between.contains('\n'));
+}
+
+fn next_comment(f : impl Fn(&Comment, &str) -> bool) -> Option<Comment> {
+    return SOURCE.with(|source| {
+        let mut source = source.borrow_mut();
+        let     source = source.as_mut()?;
+        if (f(source.comments.front()?, &source.text)) {
+            source.comments.pop_front()
+        } else {
+            None
+        }
+    });
+}
+
+/// Check if there is an empty line between two items,
+/// so that it can be kept when formatting source code.
+/// The next comment is counted as part of the second item.
+fn blank_line_between(end : usize, start : usize) -> bool {
+    return SOURCE.with(|source| {
+        let source = source.borrow();
+        let Some(source) = source.as_ref() else {return false};
+        let start = match (source.comments.front()) {
+            Some(comment) if (comment.range.1 < start) => comment.range.1,
+            _                                          => start
+        };
+        let Some(text) = source.text.get(end..start) else {return false};
+        let lines = text.split('\n').collect::<Vec<_>>();
+        lines.len() > 2 && lines[1..(lines.len() - 1)].iter().any(|line| line.trim().is_empty())
+    });
+}
 
 
 
@@ -39,25 +203,53 @@ impl Display for Colour {
 
 impl Display for Program {
     fn fmt(&self, f : &mut Formatter<'_>) -> Result {
-        return write!(f, "{}", self.decls.iter().map(|decl| format!("{};", decl.format(0))).collect::<Vec<String>>().join("\n"));
+        if (! is_source()) {
+            return write!(f, "{}", self.decls.iter().map(|decl| format!("{};", decl.format(0))).collect::<Vec<String>>().join("\n"));
+        }
+        let mut text = String::new();
+        for (i, decl) in self.decls.iter().enumerate() {
+            if (i > 0) {
+                // Imports are grouped together, everything else is separated by an empty line.
+                let prev = &self.decls[i - 1];
+                if (! (prev.is_import() && decl.is_import()) || blank_line_between(prev.range.2, decl.start())) {
+                    text += "\n";
+                }
+            }
+            text += &leading_comments(decl.start(), 0);
+            text += &format!("{}{};", inline_prefix(decl.start()), decl.format(0));
+            text += &trailing_comment(self.decls.get(i + 1).map_or(usize::MAX, |decl| decl.start()));
+            text += "\n";
+        }
+        let comments = leading_comments(usize::MAX, 0);
+        if (! comments.is_empty()) {
+            if (! self.decls.is_empty()) {text += "\n";}
+            text += &comments;
+        }
+        return write!(f, "{}", text);
     }
 }
 
 
 impl Declaration {
     fn format(&self, indent : usize) -> String {
-        return format!("{}{} {}",
+        let vis = self.vis.format(indent);
+        return format!("{}{}{}{}",
             self.headers.iter()
                 .map(|header| format!("{}\n{}",
                     header.format(indent),
-                    INDENT.repeat(indent)
+                    indentation(indent)
                 ))
                 .collect::<Vec<String>>()
                 .join(""),
-            self.vis.format(indent),
-            self.decl.format(indent)
+            vis,
+            if (vis.is_empty()) {""} else {" "},
+            self.decl.format(indent, self.range.2)
         );
     }
+
+    fn is_import(&self) -> bool {
+        return matches!(self.decl, DeclarationType::Module(..) | DeclarationType::Use(..));
+    }
 }
 
 
@@ -72,6 +264,10 @@ impl DeclarationHeader {
 
 impl DeclarationVisibility {
     fn format(&self, _indent : usize) -> String {
+        // Only write `priv` in source code if it was written there.
+        if (is_source() && matches!(self.vis, DeclarationVisibilityType::Private) && self.range.1 == self.range.2) {
+            return String::new();
+        }
         return c!(KEYWORD, match (self.vis) {
             DeclarationVisibilityType::Public  => "pub",
             DeclarationVisibilityType::Private => "priv"
@@ -81,7 +277,8 @@ impl DeclarationVisibility {
 
 
 impl DeclarationType {
-    fn format(&self, indent : usize) -> String {
+    /// `end` is where the declaration ends, so that comments before it can be written inside.
+    fn format(&self, indent : usize, end : usize) -> String {
         use DeclarationType::*;
         return match (self) {
 
@@ -92,12 +289,134 @@ impl DeclarationType {
                 )
             },
 
-            Function(name, _, _args, _ret, block) => {
-                format!("{} {} {}",
+            Use(base, items, braced) => {
+                let items = items.iter()
+                    .map(|(name, _, alias)| if let Some((alias, _)) = alias {
+                        format!("{} {} {}", name, c!(KEYWORD, "as"), c!(NAME, alias))
+                    } else {
+                        c!(NAME, name)
+                    })
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                format!("{} {}::{}",
+                    c!(KEYWORD, "use"),
+                    base.iter().map(|(part, _)| part.clone()).collect::<Vec<String>>().join("::"),
+                    if (*braced) {format!("{{{}}}", items)} else {items}
+                )
+            },
+
+            Function(name, _, generics, args, ret, block) => {
+                format!("{} {}{}({}){} {}",
                     c!(OBJECT, "fn"),
                     c!(NAME, name),
+                    Generic::format_all(generics),
+                    args.iter()
+                        .map(|(arg, _, typ)| format!("{} : {}",
+                            c!(NAME, arg),
+                            typ.format(indent)
+                        ))
+                        .collect::<Vec<String>>()
+                        .join(", "),
+                    if let Some(ret) = ret {
+                        format!(" -> {}", ret.format(indent))
+                    } else {
+                        String::new()
+                    },
                     block.format(indent)
                 )
+            },
+
+            Struct(name, _, generics, fields) => {
+                let mut text = format!("{} {}{} {{", c!(OBJECT, "struct"), c!(NAME, name), Generic::format_all(generics));
+                for (i, (vis, field, _, typ)) in fields.iter().enumerate() {
+                    text += "\n";
+                    text += &leading_comments(vis.range.1, indent + 1);
+                    text += &indentation(indent + 1);
+                    text += &inline_prefix(vis.range.1);
+                    let vis = vis.format(indent + 1);
+                    text += &format!("{}{}{} : {}{}",
+                        vis,
+                        if (vis.is_empty()) {""} else {" "},
+                        c!(NAME, field),
+                        typ.format(indent + 1),
+                        if (i == fields.len() - 1) {""} else {","}
+                    );
+                    text += &trailing_comment(fields.get(i + 1).map_or(end, |(vis, _, _, _)| vis.range.1));
+                }
+                let comments = leading_comments(end, indent + 1);
+                if (fields.is_empty() && comments.is_empty()) {
+                    return text + "}";
+                }
+                format!("{}\n{}{}}}", text, comments, indentation(indent))
+            },
+
+            Enum(name, _, generics, variants) => {
+                let mut text = format!("{} {}{} {{", c!(OBJECT, "enum"), c!(NAME, name), Generic::format_all(generics));
+                for (i, (variant, range, fields)) in variants.iter().enumerate() {
+                    text += "\n";
+                    text += &leading_comments(range.1, indent + 1);
+                    text += &format!("{}{}{}{}{}",
+                        indentation(indent + 1),
+                        inline_prefix(range.1),
+                        c!(NAME, variant),
+                        fields.format(indent + 1),
+                        if (i == variants.len() - 1) {""} else {","}
+                    );
+                    text += &trailing_comment(variants.get(i + 1).map_or(end, |(_, range, _)| range.1));
+                }
+                let comments = leading_comments(end, indent + 1);
+                if (variants.is_empty() && comments.is_empty()) {
+                    return text + "}";
+                }
+                format!("{}\n{}{}}}", text, comments, indentation(indent))
+            },
+
+            Trait(name, _, methods) => {
+                let text = format!("{} {} {{", c!(OBJECT, "trait"), c!(NAME, name));
+                Method::format_all(text, methods, false, indent, end)
+            },
+
+            Impl(path, _, typ, methods) => {
+                let text = format!("{} {} {} {} {{",
+                    c!(OBJECT, "impl"),
+                    c!(TYPE, path.join("::")),
+                    c!(KEYWORD, "for"),
+                    typ.format(indent)
+                );
+                Method::format_all(text, methods, true, indent, end)
+            },
+
+            Error(_) => c!(ERROR, "<error>")
+
+        };
+    }
+}
+
+
+impl VariantFields {
+    fn format(&self, indent : usize) -> String {
+        use VariantFields::*;
+        return match (self) {
+
+            Unit => String::new(),
+
+            Tuple(types) => {
+                format!("({})", types.iter()
+                    .map(|typ| typ.format(indent))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+                )
+            },
+
+            Named(fields) => {
+                if (fields.is_empty()) {
+                    return String::from(" {}");
+                }
+                format!(" {{ {} }}", fields.iter()
+                    .map(|(name, _, typ)| format!("{} : {}", c!(NAME, name), typ.format(indent)))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+                )
             }
 
         };
@@ -105,20 +424,106 @@ impl DeclarationType {
 }
 
 
+impl Method {
+    fn format(&self, indent : usize) -> String {
+        return format!("{} {}({}){}{}",
+            c!(OBJECT, "fn"),
+            c!(NAME, self.name),
+            [c!(KEYWORD, "self")].into_iter()
+                .chain(self.args.iter().map(|(arg, _, typ)| format!("{} : {}",
+                    c!(NAME, arg),
+                    typ.format(indent)
+                )))
+                .collect::<Vec<String>>()
+                .join(", "),
+            if let Some(ret) = &self.ret {
+                format!(" -> {}", ret.format(indent))
+            } else {
+                String::new()
+            },
+            if let Some(block) = &self.block {
+                format!(" {}", block.format(indent))
+            } else {
+                String::new()
+            }
+        );
+    }
+
+    /// Write the methods of a trait or an impl after `text`, which opens the braces.
+    /// Methods with bodies are separated by an empty line, like declarations.
+    fn format_all(text : String, methods : &Vec<Method>, bodies : bool, indent : usize, end : usize) -> String {
+        let mut text = text;
+        for (i, method) in methods.iter().enumerate() {
+            text += "\n";
+            if (i > 0 && (bodies || blank_line_between(methods[i - 1].range.2, method.range.1))) {
+                text += "\n";
+            }
+            text += &leading_comments(method.range.1, indent + 1);
+            text += &format!("{}{}{};", indentation(indent + 1), inline_prefix(method.range.1), method.format(indent + 1));
+            text += &trailing_comment(methods.get(i + 1).map_or(end, |method| method.range.1));
+        }
+        let comments = leading_comments(end, indent + 1);
+        if (methods.is_empty() && comments.is_empty()) {
+            return text + "}";
+        }
+        return format!("{}\n{}{}}}", text, comments, indentation(indent));
+    }
+}
+
+
 impl Statement {
     fn format(&self, indent : usize) -> String {
         use StatementType::*;
         return match (&self.stmt) {
 
-            InitVar(name, _, value) => {
-                format!("{} {} = {}",
+            InitVar(mutable, name, _, typ, value) => {
+                format!("{} {}{}{} = {}",
+                    c!(KEYWORD, "let"),
+                    if (*mutable) {format!("{} ", c!(KEYWORD, "mut"))} else {String::new()},
+                    c!(NAME, name),
+                    if let Some(typ) = typ {format!(" : {}", typ.format(indent))} else {String::new()},
+                    value.format(indent)
+                )
+            },
+
+            Destructure(mutable, pattern, value) => {
+                format!("{} {}{} = {}",
                     c!(KEYWORD, "let"),
+                    if (*mutable) {format!("{} ", c!(KEYWORD, "mut"))} else {String::new()},
+                    pattern.format(indent),
+                    value.format(indent)
+                )
+            },
+
+            Assign(name, _, op, value) => {
+                format!("{} {}= {}",
                     c!(NAME, name),
+                    op.as_deref().unwrap_or(""),
                     value.format(indent)
                 )
             },
 
-            Expression(expr) => expr.format(indent)
+            Break(value) => {
+                if let Some(value) = value {
+                    format!("{} {}", c!(KEYWORD, "break"), value.format(indent))
+                } else {
+                    c!(KEYWORD, "break")
+                }
+            },
+
+            Continue => c!(KEYWORD, "continue"),
+
+            Return(value) => {
+                if let Some(value) = value {
+                    format!("{} {}", c!(KEYWORD, "return"), value.format(indent))
+                } else {
+                    c!(KEYWORD, "return")
+                }
+            },
+
+            Expression(expr) => expr.format(indent),
+
+            Error(_) => c!(ERROR, "<error>")
 
         }
     }
@@ -128,22 +533,51 @@ impl Statement {
 impl Expression {
     fn format(&self, indent : usize) -> String {
         use ExpressionType::*;
-        return match (&self.expr) {
-
-            EqualsOperation         (left, right) => format!("({} == {})", left.format(indent), right.format(indent)),
-            NotEqualsOperation      (left, right) => format!("({} != {})", left.format(indent), right.format(indent)),
-            GreaterOperation        (left, right) => format!("({} > {})", left.format(indent), right.format(indent)),
-            GreaterEqualsOperation  (left, right) => format!("({} >= {})", left.format(indent), right.format(indent)),
-            LessOperation           (left, right) => format!("({} < {})", left.format(indent), right.format(indent)),
-            LessEqualsOperation     (left, right) => format!("({} <= {})", left.format(indent), right.format(indent)),
-            AdditionOperation       (left, right) => format!("({} + {})", left.format(indent), right.format(indent)),
-            SubtractionOperation    (left, right) => format!("({} - {})", left.format(indent), right.format(indent)),
-            MultiplicationOperation (left, right) => format!("({} * {})", left.format(indent), right.format(indent)),
-            DivisionOperation       (left, right) => format!("({} / {})", left.format(indent), right.format(indent)),
+        // Comments just before the expression stay in front of it.
+        let comments = inline_prefix(self.range.1);
+        let text = match (&self.expr) {
+
+            LogicalOrOperation      (left, right) => binary(left, "||", right, indent),
+            LogicalAndOperation     (left, right) => binary(left, "&&", right, indent),
+            EqualsOperation         (left, right) => binary(left, "==", right, indent),
+            NotEqualsOperation      (left, right) => binary(left, "!=", right, indent),
+            GreaterOperation        (left, right) => binary(left, ">", right, indent),
+            GreaterEqualsOperation  (left, right) => binary(left, ">=", right, indent),
+            LessOperation           (left, right) => binary(left, "<", right, indent),
+            LessEqualsOperation     (left, right) => binary(left, "<=", right, indent),
+            AdditionOperation       (left, right) => binary(left, "+", right, indent),
+            SubtractionOperation    (left, right) => binary(left, "-", right, indent),
+            MultiplicationOperation (left, right) => binary(left, "*", right, indent),
+            DivisionOperation       (left, right) => binary(left, "/", right, indent),
+            ModuloOperation         (left, right) => binary(left, "%", right, indent),
+            BitwiseOrOperation      (left, right) => binary(left, "|", right, indent),
+            BitwiseXorOperation     (left, right) => binary(left, "^", right, indent),
+            BitwiseAndOperation     (left, right) => binary(left, "&", right, indent),
+            ShiftLeftOperation      (left, right) => binary(left, "<<", right, indent),
+            ShiftRightOperation     (left, right) => binary(left, ">>", right, indent),
+
+            NotOperation    (expr) => format!("!{}", expr.format(indent)),
+            NegateOperation (expr) => format!("-{}", expr.format(indent)),
+
+            Field(expr, name, _) => format!("{}.{}", expr.format(indent), name),
+
+            Index(expr, index) => format!("{}[{}]", expr.format(indent), index.format(indent)),
+
+            MethodCall(expr, name, _, args) => {
+                format!("{}.{}({})",
+                    expr.format(indent),
+                    c!(NAME, name),
+                    args.iter()
+                        .map(|arg| arg.format(indent))
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                )
+            },
 
             Atom(atom) => atom.format(indent)
 
-        }
+        };
+        return comments + &text;
     }
 }
 
@@ -155,7 +589,11 @@ impl Atom {
 
             Literal(lit) => lit.format(indent),
 
-            Expression(expr) => expr.format(indent),
+            Expression(expr) => if (is_source()) {
+                format!("({})", expr.format(indent))
+            } else {
+                expr.format(indent)
+            },
 
             If(ifs, els) => {
                 format!("{}{}{}",
@@ -166,13 +604,13 @@ impl Atom {
                             block.format(indent)
                         ))
                         .collect::<Vec<String>>()
-                        .join(&format!("\n{}{}",
-                            INDENT.repeat(indent),
+                        .join(&format!("{}{}",
+                            chain_separator(indent),
                             c!(KEYWORD, "elif")
                         )),
                     if let Some((els, _)) = els {
-                        format!("\n{}{} {}",
-                            INDENT.repeat(indent),
+                        format!("{}{} {}",
+                            chain_separator(indent),
                             c!(KEYWORD, "else"),
                             els.format(indent)
                         )
@@ -180,6 +618,127 @@ impl Atom {
                         String::new()
                     }
                 )
+            },
+
+            Match(value, arms) => {
+                let mut text = format!("{} ({}) {{", c!(KEYWORD, "match"), value.format(indent));
+                for (i, (pattern, block, braced, range)) in arms.iter().enumerate() {
+                    text += "\n";
+                    text += &leading_comments(range.1, indent + 1);
+                    text += &format!("{}{}{} => {}{}",
+                        indentation(indent + 1),
+                        inline_prefix(range.1),
+                        pattern.format(indent + 1),
+                        if (*braced) {
+                            block.format(indent + 1)
+                        } else {
+                            block.stmts[0].format(indent + 1)
+                        },
+                        if (i == arms.len() - 1) {""} else {","}
+                    );
+                    text += &trailing_comment(arms.get(i + 1).map_or(self.range.2, |(_, _, _, range)| range.1));
+                }
+                let comments = leading_comments(self.range.2, indent + 1);
+                if (arms.is_empty() && comments.is_empty()) {
+                    return text + "}";
+                }
+                format!("{}\n{}{}}}", text, comments, indentation(indent))
+            },
+
+            Call(path, _, args) => {
+                let mut path = path.clone();
+                let     last = path.remove(path.len() - 1);
+                format!("{}{}({})",
+                    path.iter()
+                        .map(|part| format!("{}::", part))
+                        .collect::<Vec<String>>()
+                        .join(""),
+                    c!(NAME, last),
+                    args.iter()
+                        .map(|arg| arg.format(indent))
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                )
+            },
+
+            While(condition, block) => {
+                format!("{} ({}) {}",
+                    c!(KEYWORD, "while"),
+                    condition.format(indent),
+                    block.format(indent)
+                )
+            },
+
+            Loop(block) => format!("{} {}", c!(KEYWORD, "loop"), block.format(indent)),
+
+            For(name, _, start, end, inclusive, block) => {
+                format!("{} {} {} {}..{}{} {}",
+                    c!(KEYWORD, "for"),
+                    c!(NAME, name),
+                    c!(KEYWORD, "in"),
+                    start.format(indent),
+                    if (*inclusive) {"="} else {""},
+                    end.format(indent),
+                    block.format(indent)
+                )
+            },
+
+            Array(values) => format!("[{}]",
+                values.iter()
+                    .map(|value| value.format(indent))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+
+            Tuple(values) => format!("({}{})",
+                values.iter()
+                    .map(|value| value.format(indent))
+                    .collect::<Vec<String>>()
+                    .join(", "),
+                if (values.len() == 1) {","} else {""}
+            ),
+
+            List(values) => format!("{}[{}]",
+                c!(TYPE, "list"),
+                values.iter()
+                    .map(|value| value.format(indent))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+
+            Closure(args, ret, block) => {
+                format!("|{}|{} {}",
+                    args.iter()
+                        .map(|(arg, _, typ)| format!("{} : {}",
+                            c!(NAME, arg),
+                            typ.format(indent)
+                        ))
+                        .collect::<Vec<String>>()
+                        .join(", "),
+                    if let Some(ret) = ret {
+                        format!(" -> {}", ret.format(indent))
+                    } else {
+                        String::new()
+                    },
+                    block.format(indent)
+                )
+            },
+
+            Struct(path, _, fields) => {
+                let mut path = path.clone();
+                let     last = path.remove(path.len() - 1);
+                format!("{}{} {{{}}}",
+                    path.iter()
+                        .map(|part| format!("{}::", part))
+                        .collect::<Vec<String>>()
+                        .join(""),
+                    c!(TYPE, last),
+                    if (fields.is_empty()) {String::new()} else {format!(" {} ", fields.iter()
+                        .map(|(name, _, value)| format!("{} : {}", c!(NAME, name), value.format(indent)))
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                    )}
+                )
             }
 
         }
@@ -192,37 +751,243 @@ impl Literal {
         use LiteralType::*;
         return match (&self.lit) {
 
-            Int(int) => c!(LIT_NUMERIC, int),
+            Int(int, suffix) => c!(LIT_NUMERIC, format!("{}{}", int, suffix)),
 
-            Float(int, dec) => {
+            Float(int, dec, suffix) => {
                 format!("{}.{}",
                     c!(LIT_NUMERIC, int),
-                    c!(LIT_NUMERIC, dec)
+                    c!(LIT_NUMERIC, format!("{}{}", dec, suffix))
                 )
             },
 
-            Identifier(name) => c!(NAME, name)
+            Bool(value) => c!(OBJECT, value),
+
+            String(text, _) => c!(LIT_STRING, format!("\"{}\"", text)),
+
+            RawString(hashes, value) => {
+                let hashes = "#".repeat(*hashes);
+                c!(LIT_STRING, format!("r{}\"{}\"{}", hashes, value, hashes))
+            },
+
+            Char(text, _) => c!(LIT_STRING, format!("'{}'", text)),
+
+            Identifier(name) => c!(NAME, name),
+
+            Path(path) => {
+                let mut path = path.clone();
+                let     last = path.remove(path.len() - 1);
+                format!("{}{}",
+                    path.iter()
+                        .map(|part| format!("{}::", part))
+                        .collect::<Vec<_>>()
+                        .join(""),
+                    c!(NAME, last)
+                )
+            }
 
         }
     }
 }
 
 
-impl Block {
+impl Pattern {
     fn format(&self, indent : usize) -> String {
-        return format!("{{{}{}\n{}}}",
-            self.stmts.iter()
-                .map(|stmt| format!("\n{}{}",
-                    INDENT.repeat(indent + 1),
-                    stmt.format(indent + 1)
-                ))
+        use PatternType::*;
+        return match (&self.pat) {
+
+            Wildcard => String::from("_"),
+
+            Binding(name) => c!(NAME, name),
+
+            Literal(lit) => lit.format(indent),
+
+            Variant(path, _, fields) => {
+                let mut path = path.clone();
+                let     last = path.remove(path.len() - 1);
+                format!("{}{}{}",
+                    path.iter()
+                        .map(|part| format!("{}::", part))
+                        .collect::<Vec<String>>()
+                        .join(""),
+                    c!(NAME, last),
+                    fields.format(indent)
+                )
+            },
+
+            Tuple(patterns) => format!("({}{})",
+                patterns.iter()
+                    .map(|pattern| pattern.format(indent))
+                    .collect::<Vec<String>>()
+                    .join(", "),
+                if (patterns.len() == 1) {","} else {""}
+            ),
+
+            Array(patterns) => format!("[{}]",
+                patterns.iter()
+                    .map(|pattern| pattern.format(indent))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            )
+
+        };
+    }
+}
+
+impl VariantPatterns {
+    fn format(&self, indent : usize) -> String {
+        use VariantPatterns::*;
+        return match (self) {
+
+            Unit => String::new(),
+
+            Tuple(patterns) => {
+                format!("({})", patterns.iter()
+                    .map(|pattern| pattern.format(indent))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+                )
+            },
+
+            Named(fields) => {
+                if (fields.is_empty()) {
+                    return String::from(" {}");
+                }
+                format!(" {{ {} }}", fields.iter()
+                    .map(|(name, _, pattern)| if let Some(pattern) = pattern {
+                        format!("{} : {}", c!(NAME, name), pattern.format(indent))
+                    } else {
+                        c!(NAME, name)
+                    })
+                    .collect::<Vec<String>>()
+                    .join(", ")
+                )
+            }
+
+        };
+    }
+}
+
+
+impl TypeDescriptor {
+    fn format(&self, indent : usize) -> String {
+        use TypeDescriptorParts::*;
+        let typ = match (&self.parts) {
+
+            BuiltIn(name) => c!(TYPE, name),
+
+            Custom(parts) => {
+                let mut parts = parts.clone();
+                let     last  = parts.remove(parts.len() - 1);
+                format!("{}{}",
+                    parts.iter()
+                        .map(|part| format!("{}::", part))
+                        .collect::<Vec<String>>()
+                        .join(""),
+                    c!(TYPE, last)
+                )
+            },
+
+            Tuple(types) => format!("({}{})",
+                types.iter()
+                    .map(|typ| typ.format(indent))
+                    .collect::<Vec<String>>()
+                    .join(", "),
+                if (types.len() == 1) {","} else {""}
+            ),
+
+            List(element) => format!("{}[{}]", c!(TYPE, "list"), element.format(indent)),
+
+            Array(element, length) => format!("[{}; {}]", element.format(indent), length),
+
+            Function(args, ret) => {
+                format!("|{}|{}",
+                    args.iter()
+                        .map(|arg| arg.format(indent))
+                        .collect::<Vec<String>>()
+                        .join(", "),
+                    if let Some(ret) = ret {
+                        format!(" -> {}", ret.format(indent))
+                    } else {
+                        String::new()
+                    }
+                )
+            },
+
+            Generic(typ, args) => format!("{}<{}>",
+                typ.format(indent),
+                args.iter()
+                    .map(|arg| arg.format(indent))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            )
+
+        };
+        if (self.constr.is_empty()) {
+            return typ;
+        }
+        // Constraints are written in the order that they were given.
+        let mut constr = self.constr.iter().collect::<Vec<_>>();
+        constr.sort_by_key(|(_, lit)| lit.range.1);
+        return format!("{}<{}>",
+            typ,
+            constr.iter()
+                .map(|(name, lit)| format!("{} = {}", name, lit.format(indent)))
                 .collect::<Vec<String>>()
-                .join(";"),
-            if (self.retlast) {""} else {";"},
-            INDENT.repeat(indent)
+                .join(", ")
+        );
+    }
+}
+
+
+impl Generic {
+    /// Format the type parameters of a declaration, or nothing if it has none.
+    fn format_all(generics : &Vec<Generic>) -> String {
+        if (generics.is_empty()) {
+            return String::new();
+        }
+        return format!("<{}>", generics.iter()
+            .map(|generic| if (generic.bounds.is_empty()) {
+                c!(TYPE, generic.name)
+            } else {
+                format!("{} : {}",
+                    c!(TYPE, generic.name),
+                    generic.bounds.iter()
+                        .map(|(path, _)| c!(TYPE, path.join("::")))
+                        .collect::<Vec<String>>()
+                        .join(" + ")
+                )
+            })
+            .collect::<Vec<String>>()
+            .join(", ")
         );
     }
 }
+
+
+impl Block {
+    fn format(&self, indent : usize) -> String {
+        let mut text = String::from("{");
+        for (i, stmt) in self.stmts.iter().enumerate() {
+            text += "\n";
+            if (i > 0 && blank_line_between(self.stmts[i - 1].range.2, stmt.range.1)) {
+                text += "\n";
+            }
+            text += &leading_comments(stmt.range.1, indent + 1);
+            text += &format!("{}{}{}{}",
+                indentation(indent + 1),
+                inline_prefix(stmt.range.1),
+                stmt.format(indent + 1),
+                if (self.retlast && i == self.stmts.len() - 1) {""} else {";"}
+            );
+            text += &trailing_comment(self.stmts.get(i + 1).map_or(self.range.2, |stmt| stmt.range.1));
+        }
+        let comments = leading_comments(self.range.2, indent + 1);
+        if (self.stmts.is_empty() && comments.is_empty()) {
+            return text + "}";
+        }
+        return format!("{}\n{}{}}}", text, comments, indentation(indent));
+    }
+}
 impl Debug for Block {
     fn fmt(&self, f : &mut Formatter<'_>) -> Result {
         return write!(f, "{{...}}");
@@ -233,6 +998,10 @@ impl Debug for Block {
 
 macro c {
     ($colour:ident, $expr:expr) => {
-        format!("{}{}\x1b[0m", $colour, $expr)
+        if (is_source()) {
+            format!("{}", $expr)
+        } else {
+            format!("{}{}\x1b[0m", $colour, $expr)
+        }
     }
 }
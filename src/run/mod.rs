@@ -0,0 +1,833 @@
+//! Tree-walking interpreter for checked programs.
+//! Used by `vesuvius run` until a native backend exists.
+
+
+pub(crate) mod value;
+
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc,
+        Mutex
+    },
+    thread
+};
+
+use crate::{
+    parse::node::*,
+    scope::ProgramInfo,
+    check::types::ValueType,
+    notes::push_error,
+    run::value::{
+        RunValue,
+        FunctionValue,
+        Variable
+    }
+};
+
+
+/// Number of nested function calls allowed
+/// before the program is stopped.
+const MAX_CALL_DEPTH : usize = 1024;
+/// Size of the stack of the thread that the program runs on.
+/// Must be large enough to fit `MAX_CALL_DEPTH` calls.
+const STACK_SIZE     : usize = 512 * 1024 * 1024;
+
+
+/// Find the `#[entry]` function and run it.
+/// Returns the value returned by the entry function,
+/// or `None` if an error was pushed.
+pub(crate) fn run() -> Option<RunValue> {
+    let info = ProgramInfo::get();
+    let Some((module, name)) = info.entry() else {
+        push_error!(MissingEntryHeader, Always);
+        return None;
+    };
+    let Some(ValueType::Function(_, _, _, block)) = info.scope_of(module)
+        .and_then(|scope| scope.get_symbol(name))
+        .map(|symbol| symbol.value().value_ref())
+    else {
+        push_error!(InternalError, Always, {
+            None => {"Entry function `{}` was not found.", name}
+        });
+        return None;
+    };
+    return thread::scope(|scope| {
+        thread::Builder::new()
+            .stack_size(STACK_SIZE)
+            .spawn_scoped(scope, || block.run_function(&mut Frame::new(0)))
+            .unwrap()
+            .join()
+            .unwrap()
+    });
+}
+
+
+/// The local variables of a single function call.
+pub(crate) struct Frame {
+    depth  : usize,
+    scopes : Vec<HashMap<String, Variable>>,
+    /// Set by `break`, `continue` and `return`. Running stops as if an
+    /// error was pushed, until the jump is handled by a loop or function.
+    jump   : Option<Jump>
+}
+
+/// A jump out of the normal order of statements.
+enum Jump {
+    Break(RunValue),
+    Continue,
+    Return(RunValue)
+}
+
+impl Frame {
+
+    fn new(depth : usize) -> Self {
+        return Self {
+            depth,
+            scopes : vec![HashMap::new()],
+            jump   : None
+        };
+    }
+
+    /// Find out why the body of a loop stopped running.
+    /// Returns `Some` with the value that the loop was broken with, or `None` to keep looping.
+    /// Errors and `return` are left to be handled outside of the loop.
+    fn stopped_iteration(&mut self) -> Result<Option<RunValue>, ()> {
+        return match (self.jump.take()) {
+            Some(Jump::Break(value)) => Ok(Some(value)),
+            Some(Jump::Continue)     => Ok(None),
+            jump                     => {
+                self.jump = jump;
+                Err(())
+            }
+        };
+    }
+
+    fn get(&self, name : &String) -> Option<RunValue> {
+        return self.scopes.iter().rev()
+            .find_map(|scope| scope.get(name))
+            .map(|variable| variable.lock().unwrap().clone());
+    }
+
+    fn set(&mut self, name : String, value : RunValue) {
+        self.scopes.last_mut().unwrap().insert(name, Arc::new(Mutex::new(value)));
+    }
+
+    /// Start a new scope, holding new variables.
+    fn push_scope(&mut self, values : HashMap<String, RunValue>) {
+        self.scopes.push(values.into_iter()
+            .map(|(name, value)| (name, Arc::new(Mutex::new(value))))
+            .collect()
+        );
+    }
+
+    /// Run the body of a function, method or closure in a new frame,
+    /// with each value stored under the name of its argument.
+    /// The frame starts with the variables that a closure captured.
+    fn call<'l>(&self, range : &Range, names : impl Iterator<Item = &'l String>, values : Vec<RunValue>, block : &Block, captures : &Vec<HashMap<String, Variable>>) -> Option<RunValue> {
+        if (self.depth >= MAX_CALL_DEPTH) {
+            push_error!(StackOverflow, Always, {
+                Some(range.clone()) => {"More than {} nested function calls.", MAX_CALL_DEPTH}
+            });
+            return None;
+        }
+        let mut subframe = Frame::new(self.depth + 1);
+        subframe.scopes.splice(0..0, captures.iter().cloned());
+        for (name, value) in names.zip(values) {
+            subframe.set(name.clone(), value);
+        }
+        return block.run_function(&mut subframe);
+    }
+
+    /// Replace the value of an existing variable.
+    fn assign(&mut self, name : &String, value : RunValue) {
+        if let Some(variable) = self.scopes.iter().rev().find_map(|scope| scope.get(name)) {
+            *variable.lock().unwrap() = value;
+        }
+    }
+
+}
+
+
+
+impl Block {
+
+    pub(crate) fn run(&self, frame : &mut Frame) -> Option<RunValue> {
+        frame.scopes.push(HashMap::new());
+        let mut ret = RunValue::Void;
+        for stmt in &self.stmts {
+            match (stmt.run(frame)) {
+                Some(value) => {ret = value;},
+                None        => {
+                    frame.scopes.pop();
+                    return None;
+                }
+            }
+        }
+        frame.scopes.pop();
+        return Some(if (self.retlast) {ret} else {RunValue::Void});
+    }
+
+    /// Run the body of a function, and get the value that it returns.
+    pub(crate) fn run_function(&self, frame : &mut Frame) -> Option<RunValue> {
+        return self.run(frame).or_else(|| match (frame.jump.take()) {
+            Some(Jump::Return(value)) => Some(value),
+            _                         => None
+        });
+    }
+
+}
+
+
+
+impl Statement {
+
+    pub(crate) fn run(&self, frame : &mut Frame) -> Option<RunValue> {
+        use StatementType::*;
+        return match (&self.stmt) {
+
+            InitVar(_, name, _, _, value) => {
+                let value = value.run(frame)?;
+                frame.set(name.clone(), value);
+                Some(RunValue::Void)
+            },
+
+            Assign(name, name_range, op, value) => {
+                let value = match (op) {
+                    Some(op) => crate::parse::node::Expression::compound(name, name_range, op, value, &self.range).run(frame)?,
+                    None     => value.run(frame)?
+                };
+                frame.assign(name, value);
+                Some(RunValue::Void)
+            },
+
+            Break(value) => {
+                let value = match (value) {
+                    Some(value) => value.run(frame)?,
+                    None        => RunValue::Void
+                };
+                frame.jump = Some(Jump::Break(value));
+                None
+            },
+
+            Continue => {
+                frame.jump = Some(Jump::Continue);
+                None
+            },
+
+            Return(value) => {
+                let value = match (value) {
+                    Some(value) => value.run(frame)?,
+                    None        => RunValue::Void
+                };
+                frame.jump = Some(Jump::Return(value));
+                None
+            },
+
+            Destructure(_, pattern, value) => {
+                let value        = value.run(frame)?;
+                let mut bindings = HashMap::new();
+                if (! pattern.matches(&value, &mut bindings, frame)?) {
+                    push_error!(InternalError, Always, {
+                        Some(pattern.range.clone()) => {"Pattern does not match `{}`.", value}
+                    });
+                    return None;
+                }
+                for (name, value) in bindings {
+                    frame.set(name, value);
+                }
+                Some(RunValue::Void)
+            },
+
+            Expression(expr) => expr.run(frame),
+
+            Error(_) => {
+                push_error!(InternalError, Always, {
+                    Some(self.range.clone()) => {"Statement failed to parse."}
+                });
+                None
+            }
+
+        };
+    }
+
+}
+
+
+
+impl Expression {
+
+    pub(crate) fn run(&self, frame : &mut Frame) -> Option<RunValue> {
+        use ExpressionType::*;
+        let (op, left, right) = match (&self.expr) {
+            LogicalOrOperation      (left, right) => {return self.short_circuit(true, left, right, frame);},
+            LogicalAndOperation     (left, right) => {return self.short_circuit(false, left, right, frame);},
+            EqualsOperation         (left, right) => ("==", left, right),
+            NotEqualsOperation      (left, right) => ("!=", left, right),
+            GreaterOperation        (left, right) => (">",  left, right),
+            GreaterEqualsOperation  (left, right) => (">=", left, right),
+            LessOperation           (left, right) => ("<",  left, right),
+            LessEqualsOperation     (left, right) => ("<=", left, right),
+            AdditionOperation       (left, right) => ("+",  left, right),
+            SubtractionOperation    (left, right) => ("-",  left, right),
+            MultiplicationOperation (left, right) => ("*",  left, right),
+            DivisionOperation       (left, right) => ("/",  left, right),
+            ModuloOperation         (left, right) => ("%",  left, right),
+            BitwiseOrOperation      (left, right) => ("|",  left, right),
+            BitwiseXorOperation     (left, right) => ("^",  left, right),
+            BitwiseAndOperation     (left, right) => ("&",  left, right),
+            ShiftLeftOperation      (left, right) => ("<<", left, right),
+            ShiftRightOperation     (left, right) => (">>", left, right),
+            NotOperation    (expr) => {return self.operate_unary("!", expr.run(frame)?);},
+            NegateOperation (expr) => {return self.operate_unary("-", expr.run(frame)?);},
+            Field(expr, name, _) => {return self.access_field(expr.run(frame)?, name);},
+            MethodCall(expr, name, _, args) => {return self.call_method(expr, name, args, frame);},
+            Index(expr, index) => {return self.index(expr.run(frame)?, index.run(frame)?);},
+            Atom(atom) => {return atom.run(frame);}
+        };
+        let left  = left.run(frame)?;
+        let right = right.run(frame)?;
+        return self.operate(op, left, right);
+    }
+
+    /// Run `||` if `stop_on` is true, or `&&` if it is false.
+    /// The right side is only run if the left side does not decide the result.
+    fn short_circuit(&self, stop_on : bool, left : &Expression, right : &Expression, frame : &mut Frame) -> Option<RunValue> {
+        for side in [left, right] {
+            match (side.run(frame)?) {
+                RunValue::Bool(value) => {
+                    if (value == stop_on) {
+                        return Some(RunValue::Bool(stop_on));
+                    }
+                },
+                value => {
+                    push_error!(InternalError, Always, {
+                        Some(side.range.clone()) => {"Expected `bool`, found `{}`.", value.type_name()}
+                    });
+                    return None;
+                }
+            }
+        }
+        return Some(RunValue::Bool(! stop_on));
+    }
+
+    /// Apply a unary operator to a value.
+    fn operate_unary(&self, op : &str, value : RunValue) -> Option<RunValue> {
+        use RunValue::*;
+        return Some(match ((op, &value)) {
+            ("!", Bool(value))  => Bool(! value),
+            ("-", Int(int))     => self.overflow(&value, int.checked_neg().map(Int))?,
+            ("-", Float(float)) => Float(-float),
+            _ => {
+                push_error!(InternalError, Always, {
+                    Some(self.range.clone()) => {"Operator `{}` can not be applied to `{}`.", op, value.type_name()}
+                });
+                return None;
+            }
+        });
+    }
+
+    /// Get a field of a struct value, or an element of a tuple.
+    fn access_field(&self, value : RunValue, name : &String) -> Option<RunValue> {
+        if let RunValue::Struct(_, fields) = &value {
+            if let Some((_, field)) = fields.iter().find(|(field, _)| field == name) {
+                return Some(field.clone());
+            }
+        }
+        if let (RunValue::Tuple(values), Ok(i)) = (&value, name.parse::<usize>()) {
+            if let Some(value) = values.get(i) {
+                return Some(value.clone());
+            }
+        }
+        push_error!(InternalError, Always, {
+            Some(self.range.clone()) => {"`{}` has no field `{}`.", value.type_name(), name}
+        });
+        return None;
+    }
+
+    /// Get an element of an array or list.
+    fn index(&self, value : RunValue, index : RunValue) -> Option<RunValue> {
+        let ((RunValue::Array(values) | RunValue::List(values)), Some(index)) = (&value, index.as_int()) else {
+            push_error!(InternalError, Always, {
+                Some(self.range.clone()) => {"`{}` can not be indexed.", value.type_name()}
+            });
+            return None;
+        };
+        let Some(element) = usize::try_from(index).ok().and_then(|i| values.get(i)) else {
+            push_error!(Bound_Broken, Always, {
+                Some(self.range.clone()) => {"Index `{}` is out of bounds. The {} has {} element{}.", index, value.type_name(), values.len(), if (values.len() != 1) {"s"} else {""}}
+            });
+            return None;
+        };
+        return Some(element.clone());
+    }
+
+    /// Call a method, found from the impls for the type of the receiver.
+    fn call_method(&self, expr : &Expression, name : &String, args : &Vec<Expression>, frame : &mut Frame) -> Option<RunValue> {
+        let mut values = vec![expr.run(frame)?];
+        for arg in args {
+            values.push(arg.run(frame)?);
+        }
+        let typ     = values[0].type_of();
+        let methods = ProgramInfo::get().methods_of(&typ, name);
+        let Some(Method { args : params, block : Some(block), .. }) = methods.first().map(|(_, method)| method) else {
+            push_error!(InternalError, Always, {
+                Some(self.range.clone()) => {"`{}` has no method `{}`.", typ, name}
+            });
+            return None;
+        };
+        let self_name = String::from("self");
+        let names     = [&self_name].into_iter().chain(params.iter().map(|(param, _, _)| param));
+        return frame.call(&self.range, names, values, block, &Vec::new());
+    }
+
+    /// Apply a binary operator to two values.
+    fn operate(&self, op : &str, left : RunValue, right : RunValue) -> Option<RunValue> {
+        use RunValue::*;
+        // Numbers are only ever operated on with numbers of the same type.
+        if (left.type_name() == right.type_name()) {
+            if let (Some(l), Some(r)) = (left.as_int(), right.as_int()) {
+                return self.operate_int(op, &left, l, r);
+            }
+            if let (Some(l), Some(r)) = (left.as_float(), right.as_float()) {
+                return self.operate_float(op, &left, l, r);
+            }
+        }
+        let value = match ((op, &left, &right)) {
+
+            ("==", Bool(l), Bool(r))     => Bool(l == r),
+            ("==", Char(l), Char(r))     => Bool(l == r),
+            ("==", String(l), String(r)) => Bool(l == r),
+            ("==", Void, Void)           => Bool(true),
+            ("!=", Bool(l), Bool(r))     => Bool(l != r),
+            ("!=", Char(l), Char(r))     => Bool(l != r),
+            ("!=", String(l), String(r)) => Bool(l != r),
+            ("!=", Void, Void)           => Bool(false),
+
+            ("&", Bool(l), Bool(r)) => Bool(l & r),
+            ("|", Bool(l), Bool(r)) => Bool(l | r),
+            ("^", Bool(l), Bool(r)) => Bool(l ^ r),
+
+            _ => {
+                push_error!(InternalError, Always, {
+                    Some(self.range.clone()) => {"Operator `{}` can not be applied to `{}` and `{}`.", op, left.type_name(), right.type_name()}
+                });
+                return None;
+            }
+
+        };
+        return Some(value);
+    }
+
+    /// Apply a binary operator to two integers of the same type as `left`.
+    /// The operation is done on wider integers, then checked to fit back in that type.
+    fn operate_int(&self, op : &str, left : &RunValue, l : i128, r : i128) -> Option<RunValue> {
+        let value = match (op) {
+
+            "==" => return Some(RunValue::Bool(l == r)),
+            "!=" => return Some(RunValue::Bool(l != r)),
+            ">"  => return Some(RunValue::Bool(l > r)),
+            ">=" => return Some(RunValue::Bool(l >= r)),
+            "<"  => return Some(RunValue::Bool(l < r)),
+            "<=" => return Some(RunValue::Bool(l <= r)),
+
+            "/" | "%" if (r == 0) => {
+                push_error!(DivisionByZero, Always, {
+                    Some(self.range.clone()) => {"Attempted to divide `{}` by zero.", left}
+                });
+                return None;
+            },
+            "+" => l.checked_add(r),
+            "-" => l.checked_sub(r),
+            "*" => l.checked_mul(r),
+            "/" => l.checked_div(r),
+            "%" => l.checked_rem(r),
+
+            "&" => Some(l & r),
+            "|" => Some(l | r),
+            "^" => Some(l ^ r),
+            "<<" | ">>" => return self.shift(op, left, r),
+
+            _ => {
+                push_error!(InternalError, Always, {
+                    Some(self.range.clone()) => {"Operator `{}` can not be applied to `{}`.", op, left.type_name()}
+                });
+                return None;
+            }
+
+        };
+        return self.overflow(left, value.and_then(|value| left.with_int(value)));
+    }
+
+    /// Shift an integer by `amount` bits. Bits shifted past the end of its type are lost.
+    fn shift(&self, op : &str, value : &RunValue, amount : i128) -> Option<RunValue> {
+        use RunValue::*;
+        let Some(amount) = u32::try_from(amount).ok().filter(|amount| *amount < value.int_bits()) else {
+            push_error!(ArithmeticOverflow, Always, {
+                Some(self.range.clone()) => {"Shift amount must be between 0 and {}.", value.int_bits() - 1}
+            });
+            return None;
+        };
+        return Some(match ((op, value)) {
+            ("<<", Byte(value)) => Byte(value << amount),
+            ("<<", Int(value))  => Int(value << amount),
+            ("<<", UInt(value)) => UInt(value << amount),
+            (_,    Byte(value)) => Byte(value >> amount),
+            (_,    Int(value))  => Int(value >> amount),
+            (_,    UInt(value)) => UInt(value >> amount),
+            _                   => value.clone()
+        });
+    }
+
+    /// Apply a binary operator to two floats of the same type as `left`.
+    fn operate_float(&self, op : &str, left : &RunValue, l : f64, r : f64) -> Option<RunValue> {
+        let value = match (op) {
+
+            "==" => return Some(RunValue::Bool(l == r)),
+            "!=" => return Some(RunValue::Bool(l != r)),
+            ">"  => return Some(RunValue::Bool(l > r)),
+            ">=" => return Some(RunValue::Bool(l >= r)),
+            "<"  => return Some(RunValue::Bool(l < r)),
+            "<=" => return Some(RunValue::Bool(l <= r)),
+
+            "+" => l + r,
+            "-" => l - r,
+            "*" => l * r,
+            "/" => l / r,
+            "%" => l % r,
+
+            _ => {
+                push_error!(InternalError, Always, {
+                    Some(self.range.clone()) => {"Operator `{}` can not be applied to `{}`.", op, left.type_name()}
+                });
+                return None;
+            }
+
+        };
+        return self.overflow(left, left.with_float(value));
+    }
+
+    /// Push an error if the result of an operation on `typ` did not fit in its type.
+    fn overflow(&self, typ : &RunValue, value : Option<RunValue>) -> Option<RunValue> {
+        if (value.is_none()) {
+            push_error!(ArithmeticOverflow, Always, {
+                Some(self.range.clone()) => {"Result does not fit in {}.", typ.type_name_article()}
+            });
+        }
+        return value;
+    }
+
+}
+
+
+
+impl Atom {
+
+    pub(crate) fn run(&self, frame : &mut Frame) -> Option<RunValue> {
+        use AtomType::*;
+        return match (&self.atom) {
+
+            Literal(lit) => lit.run(frame),
+
+            Expression(expr) => expr.run(frame),
+
+            Array(values) => Some(RunValue::Array(run_all(values, frame)?)),
+
+            Tuple(values) => Some(RunValue::Tuple(run_all(values, frame)?)),
+
+            List(values) => Some(RunValue::List(run_all(values, frame)?)),
+
+            If(ifs, els) => {
+                for (condition, block, _) in ifs {
+                    match (condition.run(frame)?) {
+                        RunValue::Bool(true)  => {return block.run(frame);},
+                        RunValue::Bool(false) => {},
+                        value                 => {
+                            push_error!(InternalError, Always, {
+                                Some(condition.range.clone()) => {"Expected `bool`, found `{}`.", value.type_name()}
+                            });
+                            return None;
+                        }
+                    }
+                }
+                if let Some((block, _)) = els {
+                    block.run(frame)
+                } else {
+                    Some(RunValue::Void)
+                }
+            },
+
+            Call(path, path_range, args) => {
+                let mut values = Vec::new();
+                for arg in args {
+                    values.push(arg.run(frame)?);
+                }
+                if let Some((name, variant)) = variant_at(path, path_range) {
+                    return Some(RunValue::Enum(name, variant, values.into_iter().map(|value| (None, value)).collect()));
+                }
+                // Variables hide functions with the same name.
+                let function = match (frame.get(&path[0]).filter(|_| path.len() == 1)) {
+                    Some(value) => value,
+                    None        => function_at(path, path_range)?
+                };
+                let RunValue::Function(function) = function else {
+                    push_error!(InternalError, Always, {
+                        Some(path_range.clone()) => {"`{}` is not a function.", path.join("::")}
+                    });
+                    return None;
+                };
+                frame.call(&self.range, function.params.iter(), values, &function.block, &function.captures)
+            },
+
+            While(condition, block) => {
+                loop {
+                    match (condition.run(frame)?) {
+                        RunValue::Bool(true)  => {},
+                        RunValue::Bool(false) => {break;},
+                        value                 => {
+                            push_error!(InternalError, Always, {
+                                Some(condition.range.clone()) => {"Expected `bool`, found `{}`.", value.type_name()}
+                            });
+                            return None;
+                        }
+                    }
+                    if (block.run(frame).is_none() && frame.stopped_iteration().ok()?.is_some()) {
+                        break;
+                    }
+                }
+                Some(RunValue::Void)
+            },
+
+            Loop(block) => {
+                loop {
+                    if (block.run(frame).is_none()) {
+                        if let Some(value) = frame.stopped_iteration().ok()? {
+                            return Some(value);
+                        }
+                    }
+                }
+            },
+
+            For(name, _, start, end, inclusive, block) => {
+                let (start, end) = (start.run(frame)?, end.run(frame)?);
+                let (Some(mut i), Some(end)) = (start.as_int(), end.as_int()) else {
+                    push_error!(InternalError, Always, {
+                        Some(self.range.clone()) => {"Expected a range of integers."}
+                    });
+                    return None;
+                };
+                while (i < end || (*inclusive && i == end)) {
+                    frame.push_scope(HashMap::from([(name.clone(), start.with_int(i)?)]));
+                    let result = block.run(frame);
+                    frame.scopes.pop();
+                    if (result.is_none() && frame.stopped_iteration().ok()?.is_some()) {
+                        break;
+                    }
+                    // Counted in a wider integer, so this does not overflow if the range ends at the largest value.
+                    i += 1;
+                }
+                Some(RunValue::Void)
+            },
+
+            Struct(path, path_range, fields) => {
+                let mut values = Vec::new();
+                for (name, _, value) in fields {
+                    values.push((name.clone(), value.run(frame)?));
+                }
+                if let Some((name, variant)) = variant_at(path, path_range) {
+                    return Some(RunValue::Enum(name, variant, values.into_iter().map(|(field, value)| (Some(field), value)).collect()));
+                }
+                let symbol = ProgramInfo::get().scope_of(&self.range.0)?.resolve_path(path, path_range, true)?;
+                let ValueType::StructType(struct_path, _) = symbol.value().value_ref() else {
+                    push_error!(InternalError, Always, {
+                        Some(path_range.clone()) => {"`{}` is not a struct.", path.join("::")}
+                    });
+                    return None;
+                };
+                Some(RunValue::Struct(struct_path.clone(), values))
+            },
+
+            Closure(args, _, block) => Some(RunValue::Function(Arc::new(FunctionValue {
+                params   : args.iter().map(|(arg, _, _)| arg.clone()).collect(),
+                block    : block.clone(),
+                captures : frame.scopes.clone()
+            }))),
+
+            Match(value, arms) => {
+                let value = value.run(frame)?;
+                for (pattern, block, _, _) in arms {
+                    let mut bindings = HashMap::new();
+                    if (pattern.matches(&value, &mut bindings, frame)?) {
+                        frame.push_scope(bindings);
+                        let result = block.run(frame);
+                        frame.scopes.pop();
+                        return result;
+                    }
+                }
+                push_error!(InternalError, Always, {
+                    Some(self.range.clone()) => {"No arm matched `{}`.", value}
+                });
+                None
+            }
+
+        };
+    }
+
+}
+
+
+
+impl Literal {
+
+    pub(crate) fn run(&self, frame : &mut Frame) -> Option<RunValue> {
+        use LiteralType::*;
+        return match (&self.lit) {
+
+            Int(int, suffix) => {
+                let (value, typ) = match (suffix.as_str()) {
+                    "u" => (int.parse().ok().map(RunValue::UInt), "a `uint`"),
+                    "b" => (int.parse().ok().map(RunValue::Byte), "a `byte`"),
+                    _   => (int.parse().ok().map(RunValue::Int),  "an `int`")
+                };
+                if (value.is_none()) {
+                    push_error!(ArithmeticOverflow, Always, {
+                        Some(self.range.clone()) => {"`{}` does not fit in {}.", int, typ}
+                    });
+                }
+                value
+            },
+
+            Float(int, dec, suffix) => {
+                let value = format!("{}.{}", int, dec).parse().unwrap();
+                Some(if (suffix == "u") {RunValue::UFloat(value)} else {RunValue::Float(value)})
+            },
+
+            Bool(value) => Some(RunValue::Bool(*value)),
+
+            String(_, value) | RawString(_, value) => Some(RunValue::String(value.clone())),
+
+            Char(_, value) => Some(RunValue::Char(*value)),
+
+            Identifier(name) => {
+                if let Some(value) = frame.get(name) {
+                    Some(value)
+                } else {
+                    function_at(&vec![name.clone()], &self.range)
+                }
+            },
+
+            Path(path) => {
+                if let Some((name, variant)) = variant_at(path, &self.range) {
+                    Some(RunValue::Enum(name, variant, Vec::new()))
+                } else {
+                    function_at(path, &self.range)
+                }
+            }
+
+        };
+    }
+
+}
+
+
+
+impl Pattern {
+
+    /// Check whether the pattern matches the value.
+    /// The variables that it creates are added to `bindings`.
+    fn matches(&self, value : &RunValue, bindings : &mut HashMap<String, RunValue>, frame : &mut Frame) -> Option<bool> {
+        return Some(match (&self.pat) {
+
+            PatternType::Wildcard => true,
+
+            PatternType::Binding(name) => {
+                bindings.insert(name.clone(), value.clone());
+                true
+            },
+
+            PatternType::Literal(lit) => lit.run(frame)? == *value,
+
+            PatternType::Variant(path, _, patterns) => {
+                // The checker makes sure that the value is of the right enum.
+                let RunValue::Enum(_, variant, fields) = value else {
+                    return Some(false);
+                };
+                if (*variant != path[path.len() - 1]) {
+                    return Some(false);
+                }
+                match (patterns) {
+                    VariantPatterns::Unit            => {},
+                    VariantPatterns::Tuple(patterns) => {
+                        for (pattern, (_, field)) in patterns.iter().zip(fields) {
+                            if (! pattern.matches(field, bindings, frame)?) {
+                                return Some(false);
+                            }
+                        }
+                    },
+                    VariantPatterns::Named(patterns) => {
+                        for (name, _, pattern) in patterns {
+                            let Some((_, field)) = fields.iter().find(|(field, _)| field.as_ref() == Some(name)) else {
+                                return Some(false);
+                            };
+                            if let Some(pattern) = pattern {
+                                if (! pattern.matches(field, bindings, frame)?) {
+                                    return Some(false);
+                                }
+                            } else {
+                                bindings.insert(name.clone(), field.clone());
+                            }
+                        }
+                    }
+                }
+                true
+            },
+
+            PatternType::Tuple(patterns) | PatternType::Array(patterns) => {
+                let (RunValue::Tuple(values) | RunValue::Array(values)) = value else {
+                    return Some(false);
+                };
+                for (pattern, value) in patterns.iter().zip(values) {
+                    if (! pattern.matches(value, bindings, frame)?) {
+                        return Some(false);
+                    }
+                }
+                true
+            }
+
+        });
+    }
+
+}
+
+
+/// Run each expression in order, and get their values.
+fn run_all(values : &Vec<Expression>, frame : &mut Frame) -> Option<Vec<RunValue>> {
+    return values.iter().map(|value| value.run(frame)).collect();
+}
+
+
+/// Get the function declared at the path, such as `module::function`, as a value.
+fn function_at(path : &Vec<String>, range : &Range) -> Option<RunValue> {
+    let symbol = ProgramInfo::get().scope_of(&range.0)?.resolve_path(path, range, true)?;
+    let ValueType::Function(_, params, _, block) = symbol.value().value_ref() else {
+        push_error!(InternalError, Always, {
+            Some(range.clone()) => {"`{}` is not a function.", path.join("::")}
+        });
+        return None;
+    };
+    return Some(RunValue::Function(Arc::new(FunctionValue {
+        params   : params.iter().map(|(param, _, _)| param.clone()).collect(),
+        block    : block.clone(),
+        captures : Vec::new()
+    })));
+}
+
+/// Find the enum variant at the path, such as `Shape::Circle`.
+/// Returns the path of the enum and the name of the variant.
+fn variant_at(path : &Vec<String>, range : &Range) -> Option<(Vec<String>, String)> {
+    let (symbol, variant) = ProgramInfo::get().scope_of(&range.0)?.resolve_variant(path, range)?.ok()?;
+    let ValueType::EnumType(enum_path, _) = symbol.value().value_ref() else {
+        return None;
+    };
+    return Some((enum_path.clone(), variant.0.clone()));
+}
@@ -0,0 +1,264 @@
+use std::{
+    collections::HashMap,
+    fmt::{
+        self,
+        Display,
+        Formatter
+    },
+    ptr,
+    sync::{
+        Arc,
+        Mutex
+    }
+};
+
+use crate::{
+    parse::node::{
+        Block,
+        VariantFields
+    },
+    scope::ProgramInfo,
+    check::{
+        types::{
+            ValueType,
+            Type
+        },
+        generics::Inference
+    }
+};
+
+
+/// A variable while running. Shared with the closures that capture it.
+pub(crate) type Variable = Arc<Mutex<RunValue>>;
+
+
+/// A value created while running.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum RunValue {
+    Void,
+    Byte(u8),
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    UFloat(f64),
+    Bool(bool),
+    Char(char),
+    String(String),
+    Struct(
+        Vec<String>,            // Path
+        Vec<(String, RunValue)> // Fields
+    ),
+    Enum(
+        Vec<String>,                    // Enum Path
+        String,                         // Variant Name
+        Vec<(Option<String>, RunValue)> // Fields. Unnamed if the variant holds a tuple.
+    ),
+    Function(Arc<FunctionValue>),
+    Array(Vec<RunValue>),
+    Tuple(Vec<RunValue>),
+    List(Vec<RunValue>)
+}
+
+impl RunValue {
+
+    /// The name of the type of this value, used in error messages.
+    pub(crate) fn type_name(&self) -> &'static str {
+        return match (self) {
+            Self::Void          => "void",
+            Self::Byte(_)       => "byte",
+            Self::Int(_)        => "int",
+            Self::UInt(_)       => "uint",
+            Self::Float(_)      => "float",
+            Self::UFloat(_)     => "ufloat",
+            Self::Bool(_)       => "bool",
+            Self::Char(_)       => "char",
+            Self::String(_)     => "string",
+            Self::Struct(_, _)  => "struct",
+            Self::Enum(_, _, _) => "enum",
+            Self::Function(_)   => "fn",
+            Self::Array(_)      => "array",
+            Self::Tuple(_)      => "tuple",
+            Self::List(_)       => "list"
+        };
+    }
+
+    /// The name of the type of this value with an article, used in error messages.
+    pub(crate) fn type_name_article(&self) -> String {
+        let name = self.type_name();
+        return format!("{} `{}`", if (name == "int") {"an"} else {"a"}, name);
+    }
+
+    /// The type of this value, used to find the methods that can be called on it.
+    /// The type arguments of structs and enums are inferred from their fields.
+    pub(crate) fn type_of(&self) -> Type {
+        return match (self) {
+            Self::Void             => Type::Void,
+            Self::Byte(_)          => Type::Byte,
+            Self::Int(_)           => Type::Int,
+            Self::UInt(_)          => Type::UInt,
+            Self::Float(_)         => Type::Float,
+            Self::UFloat(_)        => Type::UFloat,
+            Self::Bool(_)          => Type::Bool,
+            Self::Char(_)          => Type::Char,
+            Self::String(_)        => Type::String,
+            Self::Struct(path, _)  => Type::Struct(path.clone(), self.type_args(path)),
+            Self::Enum(path, _, _) => Type::Enum(path.clone(), self.type_args(path)),
+            // Nothing can be implemented for function types.
+            Self::Function(_)      => Type::Failed,
+            Self::Array(values)    => Type::Array(Box::new(values.first().map_or(Type::Failed, |value| value.type_of())), values.len()),
+            Self::Tuple(values)    => Type::Tuple(values.iter().map(|value| value.type_of()).collect()),
+            Self::List(values)     => Type::List(Box::new(values.first().map_or(Type::Failed, |value| value.type_of())))
+        };
+    }
+
+    /// The value of an integer of any type, widened so that it can hold all of them.
+    pub(crate) fn as_int(&self) -> Option<i128> {
+        return match (self) {
+            Self::Byte(value) => Some(i128::from(*value)),
+            Self::Int(value)  => Some(i128::from(*value)),
+            Self::UInt(value) => Some(i128::from(*value)),
+            _                 => None
+        };
+    }
+
+    /// An integer of the same type as this one, or `None` if `value` does not fit in that type.
+    pub(crate) fn with_int(&self, value : i128) -> Option<RunValue> {
+        return match (self) {
+            Self::Byte(_) => u8::try_from(value).ok().map(Self::Byte),
+            Self::Int(_)  => i64::try_from(value).ok().map(Self::Int),
+            Self::UInt(_) => u64::try_from(value).ok().map(Self::UInt),
+            _             => None
+        };
+    }
+
+    /// The number of bits in an integer of the same type as this one.
+    pub(crate) fn int_bits(&self) -> u32 {
+        return if (matches!(self, Self::Byte(_))) {8} else {64};
+    }
+
+    /// The value of a float of any type.
+    pub(crate) fn as_float(&self) -> Option<f64> {
+        return match (self) {
+            Self::Float(value) | Self::UFloat(value) => Some(*value),
+            _                                        => None
+        };
+    }
+
+    /// A float of the same type as this one, or `None` if `value` is negative and this is a `ufloat`.
+    pub(crate) fn with_float(&self, value : f64) -> Option<RunValue> {
+        return match (self) {
+            Self::Float(_)                    => Some(Self::Float(value)),
+            Self::UFloat(_) if (value >= 0.0) => Some(Self::UFloat(value)),
+            _                                 => None
+        };
+    }
+
+    /// Infer the type arguments of the struct or enum at `path` that this value is,
+    /// by matching the declared type of each field against the value in it.
+    fn type_args(&self, path : &Vec<String>) -> Vec<Type> {
+        let mut inference = Inference::new(path);
+        let declared      = ProgramInfo::get().scope_of(&path[..(path.len() - 1)].to_vec())
+            .and_then(|scope| scope.get_symbol(&path[path.len() - 1]))
+            .map(|symbol| symbol.value().value_ref());
+        match ((self, declared)) {
+            (Self::Struct(_, values), Some(ValueType::StructType(_, fields))) => {
+                for (name, value) in values {
+                    if let Some((_, _, _, typ)) = fields.iter().find(|(field, _, _, _)| field == name) {
+                        inference.unify(&typ.resolve(false), &value.type_of());
+                    }
+                }
+            },
+            (Self::Enum(_, variant, values), Some(ValueType::EnumType(_, variants))) => {
+                let fields = variants.iter().find(|(name, _, _)| name == variant).map(|(_, _, fields)| fields);
+                for (i, (name, value)) in values.iter().enumerate() {
+                    let typ = match ((fields, name)) {
+                        (Some(VariantFields::Tuple(types)), None)        => types.get(i),
+                        (Some(VariantFields::Named(fields)), Some(name)) => fields.iter().find(|(field, _, _)| field == name).map(|(_, _, typ)| typ),
+                        _                                                => None
+                    };
+                    if let Some(typ) = typ {
+                        inference.unify(&typ.resolve(false), &value.type_of());
+                    }
+                }
+            },
+            _ => {}
+        }
+        return inference.args();
+    }
+
+}
+
+impl Display for RunValue {
+
+    fn fmt(&self, f : &mut Formatter) -> fmt::Result {
+        let list = |values : &Vec<RunValue>| values.iter()
+            .map(|value| value.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        return match (self) {
+            Self::Void          => write!(f, "()"),
+            Self::Byte(value)   => write!(f, "{}", value),
+            Self::Int(value)    => write!(f, "{}", value),
+            Self::UInt(value)   => write!(f, "{}", value),
+            Self::Float(value)  => write!(f, "{:?}", value),
+            Self::UFloat(value) => write!(f, "{:?}", value),
+            Self::Bool(value)   => write!(f, "{}", value),
+            Self::Char(value)   => write!(f, "{:?}", value),
+            Self::String(value) => write!(f, "{:?}", value),
+            Self::Struct(path, fields) => {
+                let name = &path[path.len() - 1];
+                if (fields.is_empty()) {
+                    return write!(f, "{} {{}}", name);
+                }
+                write!(f, "{} {{ {} }}", name, fields.iter()
+                    .map(|(field, value)| format!("{} : {}", field, value))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+                )
+            },
+            Self::Enum(path, variant, fields) => {
+                write!(f, "{}::{}", path[path.len() - 1], variant)?;
+                if (fields.is_empty()) {
+                    return Ok(());
+                }
+                if (fields.iter().all(|(field, _)| field.is_none())) {
+                    return write!(f, "({})", fields.iter()
+                        .map(|(_, value)| value.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                    );
+                }
+                write!(f, " {{ {} }}", fields.iter()
+                    .map(|(field, value)| format!("{} : {}", field.as_deref().unwrap_or("_"), value))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+                )
+            },
+            Self::Function(function) => write!(f, "|{}| {{...}}", function.params.join(", ")),
+            Self::Array(values)      => write!(f, "[{}]", list(values)),
+            Self::Tuple(values)      => write!(f, "({}{})", list(values), if (values.len() == 1) {","} else {""}),
+            Self::List(values)       => write!(f, "list[{}]", list(values))
+        };
+    }
+
+}
+
+
+/// A function or closure, which can be called while running.
+#[derive(Debug)]
+pub(crate) struct FunctionValue {
+    pub(crate) params   : Vec<String>,
+    pub(crate) block    : Block,
+    /// The variables that a closure could see where it was created.
+    /// Empty for declared functions.
+    pub(crate) captures : Vec<HashMap<String, Variable>>
+}
+
+impl PartialEq for FunctionValue {
+
+    /// Functions are only equal to themselves.
+    fn eq(&self, other : &Self) -> bool {
+        return ptr::eq(self, other);
+    }
+
+}
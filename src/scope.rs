@@ -3,7 +3,12 @@
 
 use std::{
     collections::HashMap,
-    cell::UnsafeCell,
+    slice,
+    cell::{
+        Cell,
+        RefCell,
+        UnsafeCell
+    },
     fmt::{
         self,
         Debug,
@@ -19,17 +24,37 @@ use crate::{
         node::{
             Range,
             Program,
-            DeclarationType
+            DeclarationType,
+            VariantFields,
+            Method,
+            Generic,
+            TypeDescriptor
         }
     },
     notes::{
         self,
-        push_error
+        NoteType,
+        NoteOccurance,
+        ErrorType,
+        push_error,
+        push_warn
     },
-    check::types::Value
+    check::{
+        types::{
+            Value,
+            ValueType,
+            Type
+        },
+        generics
+    }
 };
 
 
+/// Number of aliases that can be followed in a row
+/// before giving up. Cycles are reported by `Scope::check_import`.
+const MAX_ALIAS_DEPTH : usize = 64;
+
+
 /// The `ProgramInfo` of the current execution.
 static mut PROGRAM_INFO : ProgramInfo<'static> = ProgramInfo::new();
 
@@ -45,7 +70,25 @@ pub(crate) fn reset() {
 
 /// Global information about the program.
 pub(crate) struct ProgramInfo<'l> {
-    modules : Option<HashMap<Vec<String>, (String, Option<Program>, Option<Scope<'l>>)>>
+    modules : Option<HashMap<Vec<String>, (String, Option<Program>, Option<Scope<'l>>)>>,
+    /// Module dependency graph. Maps each parsed module to the
+    /// modules that it declares, and the ranges of the `mod` declarations.
+    graph   : Option<HashMap<Vec<String>, Vec<(Vec<String>, Range)>>>,
+    /// The `#[entry]` header range, module, and function name.
+    entry      : Option<(Range, Vec<String>, String)>,
+    /// Every place a symbol was defined or used while checking.
+    /// Used by the language server.
+    references : Vec<Reference>,
+    /// Every trait implementation in the program.
+    impls      : Vec<Implementation>,
+    /// The type parameters of every generic declaration (Path, Declaration Range, Parameters).
+    generics   : Vec<(Vec<String>, Range, Vec<Generic>)>,
+    /// Every set of type arguments that a generic declaration is used with.
+    /// Each one is a separate copy of the declaration once compiled.
+    instances  : Vec<Instance>,
+    /// The type that each type descriptor resolved to in the `expand_types` pass,
+    /// by the range of the descriptor.
+    types      : Option<HashMap<Range, Type>>
 }
 
 impl<'l> ProgramInfo<'l> {
@@ -56,13 +99,26 @@ impl<'l> ProgramInfo<'l> {
         if (matches!(info.modules, None)) {
             info.modules = Some(HashMap::new());
         }
+        if (matches!(info.graph, None)) {
+            info.graph = Some(HashMap::new());
+        }
+        if (matches!(info.types, None)) {
+            info.types = Some(HashMap::new());
+        }
         return info;
     }
 
     /// Create a new instance.
     const fn new() -> Self {
         return Self {
-            modules : None
+            modules : None,
+            graph      : None,
+            entry      : None,
+            references : Vec::new(),
+            impls      : Vec::new(),
+            generics   : Vec::new(),
+            instances  : Vec::new(),
+            types      : None
         };
     }
 
@@ -76,24 +132,267 @@ impl<'l> ProgramInfo<'l> {
         modules.insert(module, (script, None, None));
     }
 
-    /// Add a parsed program to the known modules list.
+    /// Add a parsed program to the known modules list,
+    /// then load all of the modules that it declares.
     pub(crate) fn load_module(&mut self, base : &RelativePathBuf, module : Vec<String>, program : Program) {
-        let modules = self.modules.as_mut().unwrap();
-        let (_, target, _) = modules.get_mut(&module).unwrap();
-        *target = Some(program);
         let mut dir = module.clone();
         dir.remove(dir.len() - 1);
-        for decl in &modules[&module].1.as_ref().unwrap().decls {
+        let mut edges = Vec::new();
+        for decl in &program.decls {
             if let DeclarationType::Module(subpath_parts, range) = &decl.decl {
                 let mut subpath = dir.clone();
                 for subpath_part in subpath_parts {
                     subpath.push(subpath_part.clone());
                 }
-                if (! modules.contains_key(&subpath)) {
-                    get_all_modules(Some(range.clone()), base, subpath);
+                edges.push((subpath, range.clone()));
+            }
+        }
+        let modules = self.modules.as_mut().unwrap();
+        let (_, target, _) = modules.get_mut(&module).unwrap();
+        *target = Some(program);
+        self.graph.as_mut().unwrap().insert(module, edges.clone());
+        // Loading a module adds to `modules`, so the edges are collected first.
+        for (subpath, range) in edges {
+            if (! self.modules.as_ref().unwrap().contains_key(&subpath)) {
+                get_all_modules(Some(range), base, subpath);
+            }
+        }
+    }
+
+    /// Check the module dependency graph for modules that
+    /// declare one of their parents, and for modules that
+    /// are declared by more than one module.
+    pub(crate) fn check_module_graph(&self) {
+        let graph      = self.graph.as_ref().unwrap();
+        let mut done   = Vec::new();
+        let mut stack  = Vec::new();
+        let mut cycles = Vec::new();
+        Self::find_cycles(graph, &vec![String::from("main")], &mut stack, &mut done, &mut cycles);
+        for cycle in &cycles {
+            let mut details = cycle.iter()
+                .map(|(module, range) : &(Vec<String>, Range)| (Some(range.clone()), format!("`{}` declares `{}` here.", range.0.join("::"), module.join("::"))))
+                .collect::<Vec<_>>();
+            let (_, first) = &cycle[0];
+            details.push((None, format!("`{}` ends up declaring itself.", first.0.join("::"))));
+            notes::push_note_details!(
+                NoteType::Error(ErrorType::CircularModuleDeclaration),
+                NoteOccurance::Always,
+                details
+            );
+        }
+        // Modules declared by more than one module, ignoring declarations that are part of a cycle.
+        let mut importers : Vec<(&Vec<String>, Vec<&Range>)> = Vec::new();
+        let mut modules = graph.keys().collect::<Vec<_>>();
+        modules.sort();
+        for module in modules {
+            for (subpath, range) in &graph[module] {
+                if (cycles.iter().any(|cycle| cycle.iter().any(|(_, cycle_range)| cycle_range.0 == range.0 && cycle_range.1 == range.1))) {
+                    continue;
+                }
+                match (importers.iter_mut().find(|(target, _)| *target == subpath)) {
+                    Some((_, ranges)) => ranges.push(range),
+                    None              => importers.push((subpath, vec![range]))
+                }
+            }
+        }
+        for (module, ranges) in importers {
+            if (ranges.len() > 1) {
+                push_warn!(ModuleDeclaredMultipleTimes, Always, {
+                    Some(ranges[0].clone()) => {"`{}` is declared here.", module.join("::")},
+                    Some(ranges[1].clone()) => {"And again here."}
+                });
+            }
+        }
+    }
+
+    /// Depth first search of the module dependency graph.
+    /// Each cycle found is added to `cycles` as the `mod` declarations that make it up.
+    fn find_cycles(
+        graph  : &HashMap<Vec<String>, Vec<(Vec<String>, Range)>>,
+        module : &Vec<String>,
+        stack  : &mut Vec<(Vec<String>, Option<Range>)>,
+        done   : &mut Vec<Vec<String>>,
+        cycles : &mut Vec<Vec<(Vec<String>, Range)>>
+    ) {
+        if (done.contains(module)) {
+            return;
+        }
+        let Some(edges) = graph.get(module) else {
+            // Module failed to load. Already reported.
+            return;
+        };
+        for (subpath, range) in edges {
+            if let Some(i) = stack.iter().position(|(parent, _)| parent == subpath) {
+                let mut cycle = stack[(i + 1)..].iter()
+                    .map(|(parent, range)| (parent.clone(), range.clone().unwrap()))
+                    .collect::<Vec<_>>();
+                cycle.push((subpath.clone(), range.clone()));
+                cycles.push(cycle);
+            } else if (subpath != module) {
+                stack.push((subpath.clone(), Some(range.clone())));
+                Self::find_cycles(graph, subpath, stack, done, cycles);
+                stack.pop();
+            } else {
+                cycles.push(vec![(subpath.clone(), range.clone())]);
+            }
+        }
+        done.push(module.clone());
+    }
+
+    /// Get the module dependency graph in DOT format.
+    /// Modules that failed to load are drawn in red.
+    pub(crate) fn module_graph_dot(&self) -> String {
+        let graph   = self.graph.as_ref().unwrap();
+        let modules = self.modules.as_ref().unwrap();
+        let mut lines   = Vec::new();
+        let mut sources = graph.keys().collect::<Vec<_>>();
+        sources.sort();
+        for source in sources {
+            lines.push(format!("    \"{}\";", source.join("::")));
+            for (target, _) in &graph[source] {
+                if (! matches!(modules.get(target), Some((_, Some(_), _)))) {
+                    lines.push(format!("    \"{}\" [color = red, style = dashed];", target.join("::")));
                 }
+                lines.push(format!("    \"{}\" -> \"{}\";", source.join("::"), target.join("::")));
             }
         }
+        return format!("digraph modules {{\n{}\n}}\n", lines.join("\n"));
+    }
+
+    /// Get all of the parsed programs from the known modules list.
+    pub(crate) fn programs(&self) -> Vec<(&Vec<String>, &Program)> {
+        return self.modules.as_ref().unwrap().iter()
+            .filter_map(|(module, (_, program, _))| program.as_ref().map(|program| (module, program)))
+            .collect();
+    }
+
+    /// Get the root scope of a checked module from the known modules list.
+    pub(crate) fn scope_of(&self, path : &Vec<String>) -> Option<&Scope<'l>> {
+        return self.modules.as_ref().unwrap().get(path).and_then(|(_, _, scope)| scope.as_ref());
+    }
+
+    /// Set the function that the program starts at.
+    /// If one was already set, push an error, and keep the one written first.
+    pub(crate) fn set_entry(&mut self, header : Range, name : String) {
+        if let Some((old_header, _, _)) = &self.entry {
+            // Modules are registered in any order, so the one written first is reported first.
+            let mut ranges = [old_header.clone(), header.clone()];
+            ranges.sort_by_key(|range| (range.0.clone(), range.1));
+            push_error!(DuplicateEntryHeader, Always, {
+                Some(ranges[0].clone()) => {"Already defined here."},
+                Some(ranges[1].clone()) => {"Defined again here."}
+            });
+            if (ranges[0] != header) {
+                return;
+            }
+        }
+        let module = header.0.clone();
+        self.entry = Some((header, module, name));
+    }
+
+    /// Get the module and name of the function that the program starts at.
+    pub(crate) fn entry(&self) -> Option<(&Vec<String>, &String)> {
+        return self.entry.as_ref().map(|(_, module, name)| (module, name));
+    }
+
+    /// Remember that a trait is implemented for a type.
+    /// If it already was, push an error instead.
+    pub(crate) fn add_impl(&mut self, implementation : Implementation) {
+        if let Some(old) = self.impls.iter().find(|old| old.trait_path == implementation.trait_path && old.typ == implementation.typ) {
+            // Modules are checked in any order, so the one written first is reported first.
+            let mut ranges = [&old.range, &implementation.range];
+            ranges.sort_by_key(|range| (&range.0, range.1));
+            push_error!(ConflictingImpl, Always, {
+                Some(ranges[0].clone()) => {"`{}` is already implemented for `{}` here.", old.trait_path.join("::"), old.typ},
+                Some(ranges[1].clone()) => {"Implemented again here."}
+            });
+        } else {
+            self.impls.push(implementation);
+        }
+    }
+
+    /// Get every method called `name` that can be called on a value of type `typ`,
+    /// with the implementation that it is in.
+    pub(crate) fn methods_of(&self, typ : &Type, name : &String) -> Vec<(&Implementation, &Method)> {
+        return self.impls.iter()
+            .filter(|implementation| implementation.typ.accepts(typ))
+            .filter_map(|implementation| implementation.methods.iter()
+                .find(|method| method.name == *name)
+                .map(|method| (implementation, method))
+            )
+            .collect();
+    }
+
+    /// Whether the trait at `trait_path` is implemented for `typ`.
+    pub(crate) fn is_implemented(&self, trait_path : &Vec<String>, typ : &Type) -> bool {
+        return self.impls.iter().any(|implementation| implementation.trait_path == *trait_path && implementation.typ.accepts(typ));
+    }
+
+    /// Remember the type parameters of the generic declaration at `path`.
+    pub(crate) fn add_generics(&mut self, path : Vec<String>, range : Range, generics : Vec<Generic>) {
+        if (! generics.is_empty()) {
+            self.generics.push((path, range, generics));
+        }
+    }
+
+    /// Get the type parameters of the declaration at `path`.
+    /// Empty if it is not generic.
+    pub(crate) fn generics_of(&self, path : &Vec<String>) -> &[Generic] {
+        return self.generics.iter()
+            .find(|(other, _, _)| other == path)
+            .map_or(&[], |(_, _, generics)| generics.as_slice());
+    }
+
+    /// Find the type parameter called `name` that can be used at `range`,
+    /// with the path of the declaration that it belongs to.
+    pub(crate) fn generic_at(&self, range : &Range, name : &String) -> Option<(&Vec<String>, &Generic)> {
+        return self.generics.iter()
+            .filter(|(_, declared, _)| declared.0 == range.0 && declared.1 <= range.1 && range.2 <= declared.2)
+            .find_map(|(path, _, generics)| generics.iter().find(|generic| generic.name == *name).map(|generic| (path, generic)));
+    }
+
+    /// Remember that a generic declaration was used with a set of type arguments,
+    /// unless it already was.
+    pub(crate) fn add_instance(&mut self, instance : Instance) {
+        if (! self.instances.iter().any(|old| old.path == instance.path && old.args == instance.args)) {
+            self.instances.push(instance);
+        }
+    }
+
+    /// Get every set of type arguments that a generic declaration was used with.
+    pub(crate) fn instances(&self) -> &Vec<Instance> {
+        return &self.instances;
+    }
+
+    /// Remember that a symbol was defined or used at a range.
+    pub(crate) fn add_reference(&mut self, range : Range, name : &str, symbol : &Symbol) {
+        let target = match (symbol.value.value_ref()) {
+            ValueType::ModuleAccess(module) => Range(module.clone(), 0, 0),
+            _                               => symbol.name_range.clone()
+        };
+        self.references.push(Reference {
+            range,
+            target,
+            hover : symbol.describe(name)
+        });
+    }
+
+    /// Get the innermost reference in a module which contains an offset.
+    pub(crate) fn reference_at(&self, module : &Vec<String>, offset : usize) -> Option<&Reference> {
+        return self.references.iter()
+            .filter(|reference| reference.range.0 == *module && reference.range.1 <= offset && offset <= reference.range.2)
+            .min_by_key(|reference| reference.range.2 - reference.range.1);
+    }
+
+    /// Remember the type that the type descriptor at `range` resolved to.
+    pub(crate) fn add_type(&mut self, range : Range, typ : Type) {
+        self.types.as_mut().unwrap().insert(range, typ);
+    }
+
+    /// Get the type that the type descriptor at `range` resolved to,
+    /// if it was resolved in the `expand_types` pass.
+    pub(crate) fn type_at(&self, range : &Range) -> Option<&Type> {
+        return self.types.as_ref().unwrap().get(range);
     }
 
     /// Get the script of a parsed program from the known modules list.
@@ -101,6 +400,17 @@ impl<'l> ProgramInfo<'l> {
         return &self.modules.as_ref().unwrap()[path].0;
     }
 
+    /// Whether every module which was loaded could be parsed.
+    /// Declarations which failed to parse are skipped, so the rest can still be checked.
+    pub(crate) fn all_parsed(&self) -> bool {
+        return self.modules.as_ref().unwrap().values().all(|(_, program, _)| program.is_some());
+    }
+
+    /// Get the script of a module, if it was loaded.
+    pub(crate) fn try_script_of(&self, path : &Vec<String>) -> Option<&String> {
+        return self.modules.as_ref().unwrap().get(path).map(|(script, _, _)| script);
+    }
+
     /// Check all of the loaded modules.
     pub(crate) fn check_modules(&mut self) {
         let mut modules = self.modules.as_mut().unwrap().iter_mut()
@@ -110,8 +420,15 @@ impl<'l> ProgramInfo<'l> {
             })
             .collect::<Vec<_>>();
         modules.iter_mut().for_each(|(_, (program, scopes))| program.register_decls(scopes.as_mut().unwrap()));
+        if (matches!(self.entry, None)) {
+            push_error!(MissingEntryHeader, Always, {
+                None => {"Add `#[entry]` to the function that the program should start at."}
+            });
+        }
         modules.iter_mut().for_each(|(_, (program, scopes))| program.expand_types(scopes.as_mut().unwrap()));
         modules.iter_mut().for_each(|(_, (program, scopes))| program.check_contents(scopes.as_mut().unwrap()));
+        generics::check_instances();
+        modules.iter_mut().for_each(|(_, (_, scopes))| scopes.as_mut().unwrap().warn_unused_imports());
     }
 
 }
@@ -122,16 +439,38 @@ impl<'l> ProgramInfo<'l> {
 pub(crate) struct Scope<'l> {
     name    : String,
     parent  : Option<&'l Scope<'l>>,
-    symbols : UnsafeCell<HashMap<String, Symbol>>
+    symbols : UnsafeCell<HashMap<String, Symbol>>,
+    /// Set if this scope is the body of a loop.
+    looping : Option<LoopInfo>,
+    /// Set if this scope is the body of a function.
+    /// The return type, and the type that was declared, if there was one.
+    returns  : Option<(Type, Option<TypeDescriptor>)>,
+    /// Set if this scope is the body of a closure. The mutable variables
+    /// declared outside of it that it uses (Name, Declared Range, Scope Depth).
+    captures : Option<RefCell<Vec<(String, Range, usize)>>>,
+    /// Number of scopes that this scope is inside of.
+    depth    : usize
+}
+
+/// A loop that `break` and `continue` refer to.
+pub(crate) struct LoopInfo {
+    /// Whether `break` can give the loop a value. Only allowed in `loop`.
+    pub(crate) values : bool,
+    /// The values that the loop was broken with.
+    pub(crate) breaks : RefCell<Vec<Value>>
 }
 
 impl<'l> Scope<'l> {
 
     fn new<S : Into<String>>(name : S, parent : Option<&'l Scope<'l>>) -> Self {
         return Self {
-            name    : name.into(),
+            name     : name.into(),
             parent,
-            symbols : UnsafeCell::new(HashMap::new())
+            symbols  : UnsafeCell::new(HashMap::new()),
+            looping  : None,
+            returns  : None,
+            captures : None,
+            depth    : parent.map_or(0, |parent| parent.depth + 1)
         };
     }
 
@@ -143,6 +482,62 @@ impl<'l> Scope<'l> {
         return Self::new(name, Some(self));
     }
 
+    /// Enter the body of a loop. If `values` is set, `break` can give the loop a value.
+    pub(crate) fn enter_loop<S : Into<String>>(&'l self, name : S, values : bool) -> Self {
+        let mut scope = Self::new(name, Some(self));
+        scope.looping = Some(LoopInfo {
+            values,
+            breaks : RefCell::new(Vec::new())
+        });
+        return scope;
+    }
+
+    /// Enter the body of a function, which returns `typ`, as declared by `ret`.
+    pub(crate) fn enter_function<S : Into<String>>(&'l self, name : S, typ : Type, ret : Option<TypeDescriptor>) -> Self {
+        let mut scope = Self::new(name, Some(self));
+        scope.returns = Some((typ, ret));
+        return scope;
+    }
+
+    /// Enter the body of a closure, which returns `typ`.
+    /// Mutable variables from outside of it that it uses are remembered as captures.
+    pub(crate) fn enter_closure<S : Into<String>>(&'l self, name : S, typ : Type, ret : Option<TypeDescriptor>) -> Self {
+        let mut scope = self.enter_function(name, typ, ret);
+        scope.captures = Some(RefCell::new(Vec::new()));
+        return scope;
+    }
+
+    /// Get the innermost loop that this scope is in.
+    /// Loops outside of the current function are not included.
+    pub(crate) fn current_loop(&self) -> Option<&LoopInfo> {
+        if (self.returns.is_some()) {
+            return None;
+        }
+        return self.looping.as_ref().or_else(|| self.parent.and_then(|parent| parent.current_loop()));
+    }
+
+    /// Get the return type of the function that this scope is in,
+    /// and the type that was declared.
+    pub(crate) fn return_type(&self) -> Option<&(Type, Option<TypeDescriptor>)> {
+        return self.returns.as_ref().or_else(|| self.parent.and_then(|parent| parent.return_type()));
+    }
+
+    /// Get the depth of the body of the function that this scope is in.
+    pub(crate) fn function_depth(&self) -> usize {
+        return if (self.returns.is_some()) {
+            self.depth
+        } else {self.parent.map_or(0, |parent| parent.function_depth())};
+    }
+
+    pub(crate) fn depth(&self) -> usize {
+        return self.depth;
+    }
+
+    /// Get the mutable variables captured by this closure.
+    pub(crate) fn captures(&self) -> Vec<(String, Range, usize)> {
+        return self.captures.as_ref().map_or_else(Vec::new, |captures| captures.borrow().clone());
+    }
+
 }
 
 impl<'l> Scope<'l> {
@@ -159,6 +554,224 @@ impl<'l> Scope<'l> {
         }
     }
 
+    /// Get a symbol from this scope or any of its parents.
+    pub(crate) fn get_symbol(&self, name : &String) -> Option<&Symbol> {
+        return self.find_symbol(name).map(|(symbol, _)| symbol);
+    }
+
+    /// Get the depth of the scope that a symbol is in.
+    pub(crate) fn symbol_depth(&self, name : &String) -> Option<usize> {
+        return self.find_symbol(name).map(|(_, depth)| depth);
+    }
+
+    /// Get a symbol from this scope or any of its parents, and the depth of the scope that it is in.
+    /// Mutable variables found outside of a closure are captured by it. Their constants are
+    /// forgotten, as the closure may be called, and assign to them, at any time.
+    fn find_symbol(&self, name : &String) -> Option<(&Symbol, usize)> {
+        let symbols = unsafe{&*self.symbols.get()};
+        if let Some(symbol) = symbols.get(name) {
+            return Some((symbol, self.depth));
+        }
+        let parent = self.parent?;
+        let (symbol, depth) = parent.find_symbol(name)?;
+        // Symbols in the root scope belong to the module, not to a function.
+        if let Some(captures) = &self.captures {
+            if (symbol.mutable && depth > 0) {
+                let range = symbol.range.clone();
+                if (! symbol.captured.replace(true)) {
+                    parent.set_symbol_value(name, Value::new(symbol.unknown_value(), symbol.value.range().clone()));
+                }
+                let mut captures = captures.borrow_mut();
+                if (! captures.iter().any(|(_, other, _)| other.0 == range.0 && other.1 == range.1)) {
+                    captures.push((name.clone(), range, depth));
+                }
+            }
+        }
+        return Some((symbol, depth));
+    }
+
+    /// Replace the value of a symbol in this scope or any of its parents.
+    /// If the symbol is in a parent, the new value is only assigned in some
+    /// cases, so its constant is forgotten and only the type is kept.
+    pub(crate) fn set_symbol_value(&self, name : &String, value : Value) {
+        let symbols = unsafe{&mut*self.symbols.get()};
+        if let Some(symbol) = symbols.get_mut(name) {
+            symbol.value = if (symbol.captured.get()) {
+                Value::new(symbol.unknown_value(), value.range().clone())
+            } else {value};
+        } else if let Some(parent) = self.parent {
+            parent.forget_symbol_value(name, value.range().clone());
+        }
+    }
+
+    /// Forget the constant value of a symbol in this scope or its parents,
+    /// which was assigned to in a scope that may not always run.
+    fn forget_symbol_value(&self, name : &String, range : Range) {
+        let symbols = unsafe{&mut*self.symbols.get()};
+        if let Some(symbol) = symbols.get_mut(name) {
+            symbol.value = Value::new(symbol.unknown_value(), range);
+        } else if let Some(parent) = self.parent {
+            parent.forget_symbol_value(name, range);
+        }
+    }
+
+    /// Forget the constant values of every mutable symbol in this scope and its parents.
+    /// Used before checking a loop, which may assign to them before any iteration.
+    pub(crate) fn forget_mutable_constants(&self) {
+        let symbols = unsafe{&mut*self.symbols.get()};
+        for symbol in symbols.values_mut() {
+            if (symbol.mutable) {
+                symbol.value = Value::new(symbol.unknown_value(), symbol.value.range().clone());
+            }
+        }
+        if let Some(parent) = self.parent {
+            parent.forget_mutable_constants();
+        }
+    }
+
+    /// Get a symbol from a path, such as `module::function`.
+    /// The first part is found in this scope or any of its parents,
+    /// and the rest are found in the modules that they lead to.
+    /// Symbols in other modules must be public.
+    /// If `report` is set, push an error if it could not be found.
+    pub(crate) fn resolve_path(&self, path : &Vec<String>, range : &Range, report : bool) -> Option<&Symbol> {
+        return self.resolve_path_inner(path, slice::from_ref(range), report, true);
+    }
+
+    /// See `resolve_path`.
+    /// `ranges` holds either the range of the whole path, or the range of each part of it.
+    /// Errors are put on the part which failed to resolve.
+    /// If `follow_last` is not set, the last part of the path will not be followed if it is an alias.
+    fn resolve_path_inner(&self, path : &Vec<String>, ranges : &[Range], report : bool, follow_last : bool) -> Option<&Symbol> {
+        let range = |i : usize| ranges[i.min(ranges.len() - 1)].clone();
+        let Some(mut symbol) = self.get_symbol(&path[0]) else {
+            if (report) {
+                push_error!(UnknownSymbol, Always, {
+                    Some(range(0)) => {"`{}` was not found in this scope.", path[0]}
+                });
+            }
+            return None;
+        };
+        for i in 1..path.len() {
+            symbol = Self::follow_alias(symbol)?;
+            let ValueType::ModuleAccess(module) = symbol.value.value_ref() else {
+                if (report) {
+                    push_error!(UnknownSymbol, Always, {
+                        Some(range(i - 1))         => {"`{}` is not a module.", path[0..i].join("::")},
+                        Some(symbol.range.clone()) => {"Defined here."}
+                    });
+                }
+                return None;
+            };
+            let Some(subscope) = ProgramInfo::get().scope_of(module) else {
+                // Module failed to load. Already reported.
+                return None;
+            };
+            let symbols = unsafe{&*subscope.symbols.get()};
+            let Some(subsymbol) = symbols.get(&path[i]) else {
+                if (report) {
+                    push_error!(UnknownSymbol, Always, {
+                        Some(range(i)) => {"`{}` was not found in module `{}`.", path[i], module.join("::")}
+                    });
+                }
+                return None;
+            };
+            if (! subsymbol.public && *module != ranges[0].0) {
+                if (report) {
+                    push_error!(PrivateSymbolAccessed, Always, {
+                        Some(range(i))                => {"`{}` is private to module `{}`.", path[i], module.join("::")},
+                        Some(subsymbol.range.clone()) => {"Defined here. Add `pub` to make it accessible."}
+                    });
+                }
+                return None;
+            }
+            symbol = subsymbol;
+        }
+        return if (follow_last) {
+            Self::follow_alias(symbol)
+        } else {Some(symbol)};
+    }
+
+    /// Get an enum variant from a path, such as `Shape::Circle`.
+    /// Returns `None` if the path without its last part does not lead to an enum.
+    /// If it does, but the enum has no such variant, an error is pushed and `Some(Err)` is returned.
+    pub(crate) fn resolve_variant(&self, path : &Vec<String>, range : &Range) -> Option<Result<(&Symbol, &(String, Range, VariantFields)), ()>> {
+        if (path.len() < 2) {
+            return None;
+        }
+        let symbol = self.resolve_path(&path[..(path.len() - 1)].to_vec(), range, false)?;
+        let ValueType::EnumType(enum_path, variants) = symbol.value.value_ref() else {
+            return None;
+        };
+        let name = &path[path.len() - 1];
+        let Some(variant) = variants.iter().find(|(variant, _, _)| variant == name) else {
+            push_error!(UnknownSymbol, Always, {
+                Some(range.clone())        => {"`{}` has no variant `{}`.", enum_path.join("::"), name},
+                Some(symbol.range.clone()) => {"Defined here."}
+            });
+            return Some(Err(()));
+        };
+        return Some(Ok((symbol, variant)));
+    }
+
+    /// If the symbol was created by a `use` declaration, get the symbol that it points to.
+    /// Broken imports are reported where they are declared, so nothing is reported here.
+    fn follow_alias(symbol : &Symbol) -> Option<&Symbol> {
+        let mut symbol = symbol;
+        for _ in 0..MAX_ALIAS_DEPTH {
+            let ValueType::Alias(path) = symbol.value.value_ref() else {
+                return Some(symbol);
+            };
+            symbol.used.set(true);
+            symbol = ProgramInfo::get().scope_of(&symbol.range.0)?
+                .resolve_path_inner(path, slice::from_ref(&symbol.range), false, false)?;
+        }
+        return None;
+    }
+
+    /// Check that the target of a `use` declaration exists and is visible,
+    /// and that following it does not lead back to itself.
+    /// `ranges` holds the range of each part of the path.
+    pub(crate) fn check_import(&self, path : &Vec<String>, ranges : &Vec<Range>) {
+        let range = &ranges[ranges.len() - 1];
+        let Some(mut symbol) = self.resolve_path_inner(path, ranges, true, false) else {
+            return;
+        };
+        let mut visited = Vec::new();
+        while let ValueType::Alias(subpath) = symbol.value.value_ref() {
+            if (visited.contains(&(symbol as *const Symbol))) {
+                push_error!(UnknownSymbol, Always, {
+                    Some(range.clone())        => {"Import of `{}` leads back to itself.", path.join("::")},
+                    Some(symbol.range.clone()) => {"Imported again here."}
+                });
+                return;
+            }
+            visited.push(symbol as *const Symbol);
+            let Some(subsymbol) = ProgramInfo::get().scope_of(&symbol.range.0)
+                .and_then(|scope| scope.resolve_path_inner(subpath, slice::from_ref(&symbol.range), false, false))
+            else {
+                return;
+            };
+            symbol = subsymbol;
+        }
+    }
+
+    /// Warn about any symbols in this scope created by `use` declarations that were never used.
+    /// Broken imports were already reported, so they are skipped.
+    pub(crate) fn warn_unused_imports(&self) {
+        let symbols = unsafe{&*self.symbols.get()};
+        let mut unused = symbols.iter()
+            .filter(|(_, symbol)| matches!(symbol.value.value_ref(), ValueType::Alias(_)) && ! symbol.used.get())
+            .filter(|(_, symbol)| matches!(Self::follow_alias(symbol), Some(_)))
+            .collect::<Vec<_>>();
+        unused.sort_by_key(|(_, symbol)| symbol.range.1);
+        for (name, symbol) in unused {
+            push_warn!(UnusedImport, Always, {
+                Some(symbol.range.clone()) => {"`{}` is never used.", name}
+            });
+        }
+    }
+
 }
 
 impl<'l> Debug for Scope<'l> {
@@ -177,20 +790,184 @@ impl<'l> Debug for Scope<'l> {
 }
 
 
+/// A trait implemented for a type by an `impl` declaration.
+pub(crate) struct Implementation {
+    pub(crate) trait_path : Vec<String>,
+    pub(crate) typ        : Type,
+    pub(crate) methods    : Vec<Method>,
+    /// From the trait to the type in the `impl` declaration.
+    pub(crate) range      : Range
+}
+
+
+/// A generic declaration used with a set of type arguments.
+pub(crate) struct Instance {
+    pub(crate) path  : Vec<String>,
+    pub(crate) args  : Vec<Type>,
+    /// Where it was first used with these arguments.
+    pub(crate) range : Range
+}
+
+
+/// A place where a symbol was defined or used.
+pub(crate) struct Reference {
+    pub(crate) range  : Range,
+    /// Where the symbol was defined.
+    pub(crate) target : Range,
+    /// A short description of the symbol.
+    pub(crate) hover  : String
+}
+
+
 pub(crate) struct Symbol {
-    value   : Value,
-    mutable : bool,
-    range   : Range
+    value    : Value,
+    mutable  : bool,
+    /// Whether the symbol can be accessed from other modules.
+    public   : bool,
+    /// Whether the symbol has been accessed through an alias.
+    used     : Cell<bool>,
+    /// Whether a closure captures the symbol. Its constant is never known then.
+    captured : Cell<bool>,
+    /// The type that a variable was declared with, if it was given one.
+    /// Values assigned to it are checked against the bounds of the type.
+    declared : Option<TypeDescriptor>,
+    range    : Range,
+    /// Where the name of the symbol is written. Go to definition jumps here.
+    name_range : Range
 }
 
 impl Symbol {
 
-    pub(crate) fn new(value : Value, mutable : bool, range : Range) -> Self {
+    pub(crate) fn new(value : Value, mutable : bool, public : bool, range : Range) -> Self {
         return Self {
             value,
             mutable,
+            public,
+            used     : Cell::new(false),
+            captured : Cell::new(false),
+            declared : None,
+            name_range : range.clone(),
             range
         };
     }
 
+    /// Set where the name is written, for declarations
+    /// where the symbol covers more than just its name.
+    pub(crate) fn with_name_range(mut self, name_range : Range) -> Self {
+        self.name_range = name_range;
+        return self;
+    }
+
+    /// Set the type that a variable was declared with.
+    pub(crate) fn with_declared(mut self, declared : TypeDescriptor) -> Self {
+        self.declared = Some(declared);
+        return self;
+    }
+
+    pub(crate) fn value(&self) -> &Value {
+        return &self.value;
+    }
+
+    pub(crate) fn declared(&self) -> Option<&TypeDescriptor> {
+        return self.declared.as_ref();
+    }
+
+    /// Get a value of the type of this symbol, without a known constant.
+    /// Integers keep the bounds of the type that they were declared with.
+    fn unknown_value(&self) -> ValueType {
+        let typ = self.value.value_ref().type_of();
+        return self.declared.as_ref().map_or_else(|| typ.unknown_value(), |declared| declared.value_of(&typ));
+    }
+
+    pub(crate) fn range(&self) -> &Range {
+        return &self.range;
+    }
+
+    pub(crate) fn is_mutable(&self) -> bool {
+        return self.mutable;
+    }
+
+    /// Get a short description of the symbol, as it would be declared.
+    pub(crate) fn describe(&self, name : &str) -> String {
+        return match (self.value.value_ref()) {
+            ValueType::ModuleAccess(module) => format!("mod {}", module.join("::")),
+            ValueType::Function(function, args, ret, _) => {
+                format!("fn {}{}({}) -> {}",
+                    name,
+                    describe_generics(&[self.range.0.clone(), vec![function.clone()]].concat()),
+                    args.iter()
+                        .map(|(arg, _, typ)| format!("{} : {}", arg, typ.name()))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    ret.as_ref().map_or_else(|| Type::Void.to_string(), |ret| ret.name())
+                )
+            },
+            ValueType::EnumType(path, variants) => {
+                format!("enum {}{} {{{}}}",
+                    name,
+                    describe_generics(path),
+                    variants.iter()
+                        .map(|(variant, _, _)| format!(" {}", variant))
+                        .collect::<Vec<_>>()
+                        .join(",")
+                        + if (variants.is_empty()) {""} else {" "}
+                )
+            },
+            ValueType::TraitType(_, methods) => {
+                format!("trait {} {{{}}}",
+                    name,
+                    methods.iter()
+                        .map(|method| format!(" fn {}({}){};",
+                            method.name,
+                            ["self".to_string()].into_iter()
+                                .chain(method.args.iter().map(|(arg, _, typ)| format!("{} : {}", arg, typ.name())))
+                                .collect::<Vec<_>>()
+                                .join(", "),
+                            method.ret.as_ref().map_or_else(String::new, |ret| format!(" -> {}", ret.name()))
+                        ))
+                        .collect::<Vec<_>>()
+                        .join("")
+                        + if (methods.is_empty()) {""} else {" "}
+                )
+            },
+            ValueType::StructType(path, fields) => {
+                format!("struct {}{} {{{}}}",
+                    name,
+                    describe_generics(path),
+                    fields.iter()
+                        .map(|(field, _, public, typ)| format!(" {}{} : {}", if (*public) {"pub "} else {""}, field, typ.name()))
+                        .collect::<Vec<_>>()
+                        .join(",")
+                        + if (fields.is_empty()) {""} else {" "}
+                )
+            },
+            // The type parameter itself, rather than a value of it.
+            ValueType::Param(path) if (path[path.len() - 1] == name) => {
+                let generics = ProgramInfo::get().generics_of(&path[..(path.len() - 1)].to_vec());
+                let bounds   = generics.iter()
+                    .find(|generic| generic.name == name)
+                    .map_or_else(Vec::new, |generic| generic.bounds.iter().map(|(bound, _)| bound.join("::")).collect());
+                format!("type {}{}", name, if (bounds.is_empty()) {String::new()} else {format!(" : {}", bounds.join(" + "))})
+            },
+            value => format!("{}{} : {}", if (self.mutable) {"mut "} else {""}, name, value.type_of())
+        };
+    }
+
+}
+
+/// Describe the type parameters of the declaration at `path`, as they would be declared.
+fn describe_generics(path : &Vec<String>) -> String {
+    let generics = ProgramInfo::get().generics_of(path);
+    if (generics.is_empty()) {
+        return String::new();
+    }
+    return format!("<{}>", generics.iter()
+        .map(|generic| if (generic.bounds.is_empty()) {
+            generic.name.clone()
+        } else {
+            format!("{} : {}", generic.name, generic.bounds.iter().map(|(bound, _)| bound.join("::")).collect::<Vec<_>>().join(" + "))
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+    );
 }
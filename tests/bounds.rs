@@ -0,0 +1,74 @@
+mod common;
+use common::Project;
+
+
+/// Check a project with `body` as the body of a function that takes `value : int<min = 0, max = 9>`.
+fn check(name : &str, body : &str) -> common::Output {
+    let project = Project::new(name, &format!("\
+fn small(value : int<min = 0, max = 5>) {{}};
+
+fn test(value : int<min = 0, max = 9>) {{
+{}
+}};
+
+#[entry]
+fn main() {{}};
+", body));
+    return project.json("check");
+}
+
+
+#[test]
+fn for_loop_variable() {
+    let output = check("for_loop_variable", "    for i in 0..6 {\n        small(i);\n    };");
+    assert_eq!(output.errors(), 0, "{}", output.text);
+    assert_eq!(output.count("warning", "Bound sometimes broken"), 0, "{}", output.text);
+    let output = check("for_loop_variable_inclusive", "    for i in 0..=6 {\n        small(i);\n    };");
+    assert_eq!(output.count("warning", "Bound sometimes broken"), 1, "{}", output.text);
+}
+
+#[test]
+fn sometimes_is_a_warning() {
+    let output = check("sometimes_is_a_warning", "    small(value);");
+    assert_eq!(output.errors(), 0, "{}", output.text);
+    assert_eq!(output.count("warning", "Bound sometimes broken"), 1, "{}", output.text);
+}
+
+#[test]
+fn always_is_an_error() {
+    let output = check("always_is_an_error", "    small(value + 10);");
+    assert_eq!(output.count("error", "Bound always broken"), 1, "{}", output.text);
+}
+
+#[test]
+fn operators() {
+    let output = check("operators", "    small(value % 6);\n    small(value & 5);\n    small((value & 1) | 2);\n    small(value >> 1);\n    small((value & 1) << 2);");
+    assert_eq!(output.errors(), 0, "{}", output.text);
+    assert_eq!(output.count("warning", "Bound sometimes broken"), 0, "{}", output.text);
+}
+
+#[test]
+fn declared_variable() {
+    let output = check("declared_variable", "    let mut x : int<min = 0, max = 5> = 3;\n    x = value % 6;\n    x -= 0;");
+    assert_eq!(output.errors(), 0, "{}", output.text);
+    assert_eq!(output.count("warning", "Bound sometimes broken"), 0, "{}", output.text);
+    let output = check("declared_variable_sometimes", "    let mut x : int<min = 0, max = 5> = 3;\n    x = value;");
+    assert_eq!(output.errors(), 0, "{}", output.text);
+    assert_eq!(output.count("warning", "Bound sometimes broken"), 1, "{}", output.text);
+    let output = check("declared_variable_init", "    let x : int<min = 0, max = 5> = 6;");
+    assert_eq!(output.count("error", "Bound always broken"), 1, "{}", output.text);
+    let output = check("declared_variable_assign", "    let mut x : int<min = 0, max = 5> = 0;\n    x = 6;");
+    assert_eq!(output.count("error", "Bound always broken"), 1, "{}", output.text);
+    let output = check("declared_variable_compound", "    let mut x : int<min = 0, max = 5> = 5;\n    x += 1;");
+    assert_eq!(output.count("error", "Bound always broken"), 1, "{}", output.text);
+    let output = check("declared_variable_type", "    let x : uint = 1;");
+    assert_eq!(output.count("error", "Invalid type received"), 1, "{}", output.text);
+}
+
+#[test]
+fn constant_overflow() {
+    let output = check("constant_overflow", "    let x = 0u - 1u;");
+    assert_eq!(output.count("error", "Arithmetic overflow"), 1, "{}", output.text);
+    let output = check("literal_overflow", "    let x = 256b;");
+    assert_eq!(output.count("error", "Arithmetic overflow"), 1, "{}", output.text);
+}
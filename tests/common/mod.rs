@@ -0,0 +1,109 @@
+//! Runs `vesuvius` on small projects, for the integration tests.
+
+#![allow(dead_code, unused_parens)]
+
+use std::{
+    env,
+    fs,
+    path::PathBuf,
+    process::{
+        self,
+        Command
+    }
+};
+
+
+/// A project with a single `main` module, in a temporary directory.
+pub struct Project {
+    dir : PathBuf
+}
+
+impl Project {
+
+    /// Create a project with `source` as its `main` module.
+    /// `name` must be different for every test, as they run at the same time.
+    pub fn new(name : &str, source : &str) -> Self {
+        let dir = env::temp_dir().join(format!("vesuvius-test-{}-{}", process::id(), name));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("config.vsv.ron"), "Config(project : (name : \"test\", version : \"0.1.0\"))\n").unwrap();
+        fs::write(dir.join("main.vsv"), source).unwrap();
+        return Self { dir };
+    }
+
+    /// Run a command on the project, with notes printed as JSON.
+    pub fn json(&self, command : &str) -> Output {
+        return self.command(&[command, "--message-format", "json"]);
+    }
+
+    /// Run a command on the project, with notes printed as text without colours.
+    pub fn human(&self, command : &str) -> Output {
+        return self.command(&[command]);
+    }
+
+    /// Get the contents of the `main` module.
+    pub fn source(&self) -> String {
+        return fs::read_to_string(self.dir.join("main.vsv")).unwrap();
+    }
+
+    fn command(&self, args : &[&str]) -> Output {
+        let output = Command::new(env!("CARGO_BIN_EXE_vesuvius"))
+            .args(args)
+            .current_dir(&self.dir)
+            .output()
+            .unwrap();
+        return Output {
+            success : output.status.success(),
+            text    : strip_ansi(&String::from_utf8_lossy(&output.stdout))
+        };
+    }
+
+}
+
+impl Drop for Project {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+
+/// What a command printed, and whether it succeeded.
+pub struct Output {
+    pub success : bool,
+    pub text    : String
+}
+
+impl Output {
+
+    /// Count the JSON notes with a severity and title, such as `"error"` and `"Bound always broken"`.
+    pub fn count(&self, severity : &str, title : &str) -> usize {
+        let severity = format!("\"severity\":\"{}\"", severity);
+        let title    = format!("\"title\":\"{}\"", title);
+        return self.text.lines()
+            .filter(|line| line.contains(&severity) && line.contains(&title))
+            .count();
+    }
+
+    /// Count the JSON notes which are errors.
+    pub fn errors(&self) -> usize {
+        return self.text.lines()
+            .filter(|line| line.contains("\"severity\":\"error\""))
+            .count();
+    }
+
+}
+
+
+fn strip_ansi(text : &str) -> String {
+    let mut result = String::new();
+    let mut chars  = text.chars();
+    while let Some(ch) = chars.next() {
+        if (ch == '\x1b') {
+            while let Some(ch) = chars.next() {
+                if (ch.is_ascii_alphabetic()) {break;}
+            }
+        } else {
+            result.push(ch);
+        }
+    }
+    return result;
+}
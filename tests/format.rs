@@ -0,0 +1,54 @@
+mod common;
+use common::Project;
+
+
+const COMMENTED : &str = "\
+// A module with comments everywhere.
+
+/* Adds one. */
+fn inc(x : int) -> int {
+    x + /* one */ 1 // the result
+};
+
+#[entry]
+fn main() -> int {
+    let a = inc(/* start */ 2);
+    let b = a /* twice */ * 2;
+    /* on its own line */
+    let c = if (b > 4) {
+        b // big
+    } elif (b > 2) {
+        /* medium */ b
+    } else {
+        0
+    };
+    c
+};
+";
+
+
+#[test]
+fn keeps_comments_in_place() {
+    let project = Project::new("keeps_comments_in_place", COMMENTED);
+    let output  = project.human("fmt");
+    assert!(output.success, "{}", output.text);
+    assert_eq!(project.source(), COMMENTED);
+}
+
+#[test]
+fn joins_else_to_the_closing_brace() {
+    let project = Project::new("joins_else_to_the_closing_brace", "\
+#[entry]
+fn main() -> int {
+    if (true) {
+        1
+    }
+    else {
+        2
+    }
+};
+");
+    let output  = project.human("fmt");
+    assert!(output.success, "{}", output.text);
+    assert!(project.source().contains("    } else {\n"), "{}", project.source());
+}
@@ -0,0 +1,26 @@
+mod common;
+use common::Project;
+
+
+#[test]
+fn keyword_as_imported_item() {
+    let project = Project::new("keyword_as_imported_item", "use std::consts::true;\n\n#[entry]\nfn main() {};\n");
+    let output  = project.json("check");
+    assert_eq!(output.count("error", "Unexpected token"), 0, "{}", output.text);
+    // There is no `std` module to import from yet.
+    assert_eq!(output.count("error", "Unknown symbol"), 1, "{}", output.text);
+}
+
+#[test]
+fn keyword_as_variable_name() {
+    let project = Project::new("keyword_as_variable_name", "#[entry]\nfn main() {\n    let true = 1;\n};\n");
+    let output  = project.json("check");
+    assert_eq!(output.count("error", "Unexpected token"), 1, "{}", output.text);
+}
+
+#[test]
+fn keyword_as_import_alias() {
+    let project = Project::new("keyword_as_import_alias", "use std::consts::true as if;\n\n#[entry]\nfn main() {};\n");
+    let output  = project.json("check");
+    assert_eq!(output.count("error", "Unexpected token"), 1, "{}", output.text);
+}
@@ -0,0 +1,102 @@
+mod common;
+use common::Project;
+
+
+#[test]
+fn method_on_uint() {
+    let project = Project::new("method_on_uint", "\
+trait Halve {
+    fn halve(self) -> uint;
+};
+
+impl Halve for uint {
+    fn halve(self) -> uint {
+        self / 2u
+    };
+};
+
+#[entry]
+fn main() -> uint {
+    10u.halve()
+};
+");
+    let output = project.human("run");
+    assert!(output.success, "{}", output.text);
+    assert!(output.text.contains("Returned 5."), "{}", output.text);
+}
+
+#[test]
+fn suffixed_literals() {
+    let project = Project::new("suffixed_literals", "\
+struct Numbers {
+    a : int,
+    b : uint,
+    c : byte,
+    d : float,
+    e : ufloat
+};
+
+#[entry]
+fn main() -> Numbers {
+    Numbers {a : 1, b : 2u, c : 3b, d : 4.5, e : 6.5u}
+};
+");
+    let output = project.human("run");
+    assert!(output.success, "{}", output.text);
+    assert!(output.text.contains("Returned Numbers { a : 1, b : 2, c : 3, d : 4.5, e : 6.5 }."), "{}", output.text);
+}
+
+#[test]
+fn byte_suffix_on_float() {
+    let project = Project::new("byte_suffix_on_float", "#[entry]\nfn main() -> float {\n    1.5b\n};\n");
+    let output  = project.json("check");
+    assert_eq!(output.count("error", "Invalid literal"), 1, "{}", output.text);
+}
+
+#[test]
+fn uint_overflow() {
+    let project = Project::new("uint_overflow", "\
+fn decrement(value : uint) -> uint {
+    value - 1u
+};
+
+#[entry]
+fn main() -> uint {
+    decrement(0u)
+};
+");
+    let output = project.json("run");
+    assert_eq!(output.count("error", "Arithmetic overflow"), 1, "{}", output.text);
+}
+
+#[test]
+fn byte_overflow() {
+    let project = Project::new("byte_overflow", "\
+fn double(value : byte) -> byte {
+    value * 2b
+};
+
+#[entry]
+fn main() -> byte {
+    double(200b)
+};
+");
+    let output = project.json("run");
+    assert_eq!(output.count("error", "Arithmetic overflow"), 1, "{}", output.text);
+}
+
+#[test]
+fn negative_ufloat() {
+    let project = Project::new("negative_ufloat", "\
+fn decrement(value : ufloat) -> ufloat {
+    value - 1.0u
+};
+
+#[entry]
+fn main() -> ufloat {
+    decrement(0.5u)
+};
+");
+    let output = project.json("run");
+    assert_eq!(output.count("error", "Arithmetic overflow"), 1, "{}", output.text);
+}
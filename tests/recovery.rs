@@ -0,0 +1,37 @@
+mod common;
+use common::Project;
+
+
+const SOURCE : &str = "\
+fn broken() {
+    let x = ;
+    y
+};
+
+#[entry]
+fn main() {};
+";
+
+
+#[test]
+fn checks_after_syntax_errors() {
+    let project = Project::new("checks_after_syntax_errors", SOURCE);
+    let output  = project.json("check");
+    assert!(! output.success, "{}", output.text);
+    assert_eq!(output.count("error", "Unexpected token"), 1, "{}", output.text);
+    // The statement after the one that failed to parse is still checked, only once.
+    assert_eq!(output.count("error", "Unknown symbol"), 1, "{}", output.text);
+}
+
+#[test]
+fn checking_is_a_separate_step() {
+    let project = Project::new("checking_is_a_separate_step", SOURCE);
+    let output  = project.human("check");
+    assert!(! output.success, "{}", output.text);
+    let parsing  = output.text.find("=> Parsing... [FAILURE]").expect(&output.text);
+    let checking = output.text.find("=> Checking... [FAILURE]").expect(&output.text);
+    assert!(parsing < output.text.find("Unexpected token").unwrap());
+    assert!(output.text.find("Unexpected token").unwrap() < checking);
+    assert!(checking < output.text.find("Unknown symbol").unwrap());
+    assert_eq!(output.text.matches("Failed with").count(), 1, "{}", output.text);
+}